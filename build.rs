@@ -0,0 +1,7 @@
+fn main() {
+    // variadic JNI functions are implemented in C
+    println!("cargo:rerun-if-changed=src/vm/jni/varargs.c");
+    cc::Build::new()
+        .file("src/vm/jni/varargs.c")
+        .compile("kafa_jni_varargs");
+}
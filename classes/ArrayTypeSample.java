@@ -0,0 +1,105 @@
+import java.io.Serializable;
+
+// assignability of array types, checked by aastore, checkcast and instanceof
+public class ArrayTypeSample {
+    interface Shape {
+    }
+
+    static class Circle implements Shape {
+    }
+
+    static class Square implements Shape {
+    }
+
+    // elements must be assignable to the component type of the runtime array type
+    public static int store() {
+        Shape[] shapes = new Circle[2];
+        shapes[0] = new Circle();
+        shapes[1] = null;
+        Object[] objs = new Object[2];
+        objs[0] = new int[1];
+        objs[1] = shapes;
+        try {
+            shapes[1] = new Square();
+            return 0;
+        } catch (ArrayStoreException e) {
+            return 1;
+        }
+    }
+
+    // store an element to a String[] (null if len is negative). the array and the index are checked before the element
+    public static int storeElement(int len, int idx, boolean isString) {
+        Object[] arr = len < 0 ? null : new String[len];
+        arr[idx] = isString ? "s" : new Object();
+        return arr.length;
+    }
+
+    // bit flags of the results of instanceof
+    public static int instanceOf() {
+        Object strs = new String[2][];
+        Object ints = new int[2][3];
+        Object circles = new Circle[1];
+        boolean[] results = {
+            strs instanceof Object[],
+            strs instanceof Object[][],
+            strs instanceof Comparable[][],
+            strs instanceof Serializable[],
+            strs instanceof Cloneable,
+            ints instanceof Object[],
+            ints instanceof Cloneable[],
+            ints instanceof int[][],
+            circles instanceof Shape[],
+            // the following are false
+            ints instanceof long[][],
+            ints instanceof Object[][],
+            strs instanceof Integer[][],
+            circles instanceof Square[],
+        };
+        int flags = 0;
+        for (int i = 0; i < results.length; i++) {
+            if (results[i]) flags |= 1 << i;
+        }
+        return flags;
+    }
+
+    public static int cast() {
+        Object circles = new Circle[1];
+        Shape[] shapes = (Shape[]) circles;
+        String[] none = (String[]) null;
+        try {
+            Square[] squares = (Square[]) circles;
+            return 0;
+        } catch (ClassCastException e) {
+            return shapes.length == 1 && none == null ? 1 : -1;
+        }
+    }
+
+    // array classes are reflected like ordinary classes, with links to their component types
+    public static int reflect() {
+        Class<?> strs = new String[0][].getClass();
+        boolean[] results = {
+            strs == String[][].class,
+            strs.isArray(),
+            strs.getComponentType() == String[].class,
+            strs.getSuperclass() == Object.class,
+            Object[].class.isAssignableFrom(strs),
+            Cloneable.class.isInstance(new int[0]),
+            int[].class.getComponentType() == int.class,
+            int.class.isPrimitive(),
+            (strs.getModifiers() & 0x411) == 0x411, // public final abstract
+            // the following are false
+            String.class.isArray(),
+            int[].class.isAssignableFrom(long[].class),
+            Shape.class.getSuperclass() != null,
+        };
+        int flags = 0;
+        for (int i = 0; i < results.length; i++) {
+            if (results[i]) flags |= 1 << i;
+        }
+        return flags;
+    }
+
+    public static String elementName() {
+        return Circle[][].class.getComponentType().getComponentType().getName();
+    }
+}
@@ -0,0 +1,35 @@
+public class GcSample {
+    static class Node {
+        int value;
+        Node next;
+
+        Node(int value, Node next) {
+            this.value = value;
+            this.next = next;
+        }
+    }
+
+    // allocate a lot of garbage, while keeping a list alive
+    public static int churn(int n) {
+        Node list = null;
+        int length = 0;
+        int sum = 0;
+        for (int i = 0; i < n; i++) {
+            int[] tmp = new int[64];
+            tmp[i % 64] = i;
+            sum += tmp[i % 64] % 3;
+            if (i % 1000 == 0) {
+                list = new Node(i / 1000, list);
+                length++;
+            }
+        }
+        System.gc();
+        int listSum = 0;
+        Node node = list;
+        for (int i = 0; i < length; i++) {
+            listSum += node.value;
+            node = node.next;
+        }
+        return sum + listSum;
+    }
+}
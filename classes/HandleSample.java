@@ -0,0 +1,51 @@
+public class HandleSample {
+    static int counter = 10;
+    static String label = "initial";
+
+    int x;
+    long y;
+    String name;
+    int[] data;
+
+    public HandleSample(int x) {
+        this.x = x;
+    }
+
+    public static int sumData(HandleSample s) {
+        int sum = 0;
+        for (int d : s.data) {
+            sum += d;
+        }
+        return sum * s.x;
+    }
+
+    public static double[] weights(int n) {
+        double[] ws = new double[n];
+        for (int i = 0; i < n; i++) {
+            ws[i] = i * 0.5;
+        }
+        return ws;
+    }
+
+    public static double total(double[] ws) {
+        double t = 0;
+        for (double w : ws) {
+            t += w;
+        }
+        return t;
+    }
+
+    public static String[] names() {
+        return new String[] { "foo", label };
+    }
+
+    public static class Sub extends HandleSample {
+        // hides HandleSample.x
+        int x;
+
+        public Sub() {
+            super(1);
+            this.x = 2;
+        }
+    }
+}
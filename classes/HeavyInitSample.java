@@ -0,0 +1,28 @@
+// <clinit> allocates enough to run GC while the class is initialized
+public class HeavyInitSample {
+    static class Holder {
+        final int x;
+
+        Holder(int x) {
+            this.x = x;
+        }
+    }
+
+    static int[] last;
+
+    static {
+        for (int i = 0; i < 20; i++) {
+            last = new int[256 * 1024];
+        }
+    }
+
+    final Holder holder;
+
+    HeavyInitSample(Holder holder) {
+        this.holder = holder;
+    }
+
+    static int valueOf(Holder holder) {
+        return holder.x;
+    }
+}
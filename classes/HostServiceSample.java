@@ -0,0 +1,33 @@
+// natives bound to closures by the host
+public class HostServiceSample {
+    static native int lookup(String key);
+
+    native int weigh(int x);
+
+    int base;
+
+    HostServiceSample(int base) {
+        this.base = base;
+    }
+
+    // called back from the host for unknown keys
+    static int fallback(String key) {
+        return key.length();
+    }
+
+    public static int total() {
+        return lookup("apple") + lookup("banana") + lookup("unknown");
+    }
+
+    public static int weighed(int x) {
+        return new HostServiceSample(10).weigh(x);
+    }
+
+    public static int lookupNull() {
+        try {
+            return lookup(null);
+        } catch (NullPointerException e) {
+            return -1;
+        }
+    }
+}
@@ -0,0 +1,43 @@
+public class InitSample {
+    // assigned from the ConstantValue attribute during initialization
+    static final String GREETING = "Hello, kafa!";
+
+    static class Faulty {
+        static int x = 1 / zero();
+
+        static int zero() {
+            return 0;
+        }
+    }
+
+    static int div(int a, int b) {
+        return a / b;
+    }
+
+    public static int start() {
+        int r = 0;
+        try {
+            r += Faulty.x;
+        } catch (ExceptionInInitializerError e) {
+            if (e.getCause() instanceof ArithmeticException) {
+                r += 1;
+            }
+            if (e.getCause().getMessage().length() == 9) {
+                r += 2;
+            }
+        }
+        try {
+            r += Faulty.x;
+        } catch (NoClassDefFoundError e) {
+            r += 4;
+        }
+        try {
+            r += div(1, 0);
+        } catch (ArithmeticException e) {
+            r += 8;
+        } finally {
+            r += 16;
+        }
+        return r;
+    }
+}
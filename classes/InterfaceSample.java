@@ -0,0 +1,6 @@
+// static methods of interfaces, called from the host
+public interface InterfaceSample {
+    static int twice(int x) {
+        return x * 2;
+    }
+}
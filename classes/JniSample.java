@@ -0,0 +1,92 @@
+public class JniSample {
+    static {
+        System.loadLibrary("jnisample");
+    }
+
+    private int base;
+
+    JniSample(int base) {
+        this.base = base;
+    }
+
+    static native int add(int a, int b);
+
+    static native int sum(int[] xs);
+
+    static native int callback(int x);
+
+    static native JniSample create(int base);
+
+    native long scaled(long x);
+
+    static native void fill(int[] xs, int v);
+
+    static native String greet(String name);
+
+    static native void fail(String msg);
+
+    static native void describe(String msg);
+
+    static native int pinEmpty(int[] a, int[] b, String s, String t);
+
+    static native boolean sameClass(Object obj, Class<?> expected);
+
+    static native Class<?> thisClass();
+
+    // registered by JNI_OnLoad
+    static native double average(double[] xs);
+
+    static int twice(int x) {
+        return x * 2;
+    }
+
+    public static int start() {
+        int res = add(1, 2);
+        res += sum(new int[] { 1, 2, 3, 4 });
+        res += callback(5);
+
+        JniSample s = create(7);
+        res += (int) s.scaled(3);
+
+        int[] xs = new int[3];
+        fill(xs, 100);
+        return res + xs[0] + xs[2];
+    }
+
+    public static String greeting() {
+        return greet("kafa");
+    }
+
+    public static int catchFailure() {
+        try {
+            fail("boom");
+        } catch (IllegalStateException e) {
+            return e.getMessage().length();
+        }
+        return -1;
+    }
+
+    public static int pinEmptyBuffers() {
+        return pinEmpty(new int[0], new int[0], "", "");
+    }
+
+    // bit flags of whether classes seen by native code are the class objects of Java
+    public static int classObjects() {
+        JniSample s = new JniSample(1);
+        int[] arr = new int[1];
+        boolean[] results = {
+            sameClass(s, s.getClass()),
+            sameClass(arr, arr.getClass()),
+            thisClass() == JniSample.class,
+        };
+        int flags = 0;
+        for (int i = 0; i < results.length; i++) {
+            flags |= (results[i] ? 1 : 0) << i;
+        }
+        return flags;
+    }
+
+    public static double averageOf() {
+        return average(new double[] { 1.0, 2.0, 4.5 });
+    }
+}
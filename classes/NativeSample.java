@@ -0,0 +1,38 @@
+public class NativeSample {
+    static native int missing(int x);
+
+    static native int overloaded(int x);
+
+    static native int overloaded(String s);
+
+    public static int start() {
+        int[] src = { 1, 2, 3, 4, 5 };
+        int[] dest = new int[5];
+        System.arraycopy(src, 1, dest, 0, 4);
+        // overlapping copy
+        System.arraycopy(src, 0, src, 1, 4);
+
+        int res = dest[0] * 1000 + src[4] * 100;
+        return res + dest.length;
+    }
+
+    public static int arrayStore() {
+        Object[] src = { "a", "b", new Object() };
+        String[] dest = new String[3];
+        try {
+            System.arraycopy(src, 0, dest, 0, 3);
+        } catch (ArrayStoreException e) {
+            // elements before the unassignable one are copied
+            return dest[0].length() + dest[1].length();
+        }
+        return -1;
+    }
+
+    public static int callMissing() {
+        return missing(1);
+    }
+
+    public static int callOverloaded() {
+        return overloaded("x");
+    }
+}
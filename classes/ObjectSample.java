@@ -0,0 +1,119 @@
+import java.util.HashMap;
+import java.util.Map;
+
+// natives of java.lang.Object, which are built on identity hash codes and Class objects
+public class ObjectSample {
+    static Object kept;
+    static int keptHash;
+
+    static class Key {
+    }
+
+    static class Point implements Cloneable {
+        int x;
+        int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        Point copy() throws CloneNotSupportedException {
+            return (Point) clone();
+        }
+    }
+
+    static class Plain {
+        Object copy() throws CloneNotSupportedException {
+            return clone();
+        }
+    }
+
+    public static int hashOnce() {
+        kept = new Object();
+        keptHash = System.identityHashCode(kept);
+        return keptHash == kept.hashCode() ? 1 : 0;
+    }
+
+    // the host triggers GC (which may move the object) before this call
+    public static int sameHash() {
+        return System.identityHashCode(kept) == keptHash ? 1 : 0;
+    }
+
+    // keys without hashCode()/equals() overridden are looked up by their identities
+    public static int identityMap(int n) {
+        Map<Key, Key> map = new HashMap<>();
+        Key[] keys = new Key[n];
+        for (int i = 0; i < n; i++) {
+            keys[i] = new Key();
+            map.put(keys[i], keys[i]);
+        }
+        int found = 0;
+        for (Key k : keys) {
+            if (map.get(k) == k) found++;
+        }
+        return map.containsKey(new Key()) ? -1 : found;
+    }
+
+    public static String className() {
+        return new Point(0, 0).getClass().getName();
+    }
+
+    public static int sameClass() {
+        return new Key().getClass() == new Key().getClass() ? 1 : 0;
+    }
+
+    public static int cloned() throws CloneNotSupportedException {
+        Point p = new Point(3, 4);
+        Point q = p.copy();
+        q.y = 5;
+        return q != p ? p.x * 100 + p.y * 10 + q.y : -1;
+    }
+
+    public static int notCloneable() {
+        try {
+            new Plain().copy();
+            return 0;
+        } catch (CloneNotSupportedException e) {
+            return 1;
+        }
+    }
+
+    public static int timedWait() throws InterruptedException {
+        Object lock = new Object();
+        synchronized (lock) {
+            lock.wait(1);
+            lock.notifyAll();
+        }
+        return 1;
+    }
+
+    // arrays are instances of Object, and are always cloneable
+    public static int cloneArrays() {
+        int[] ints = {1, 2, 3};
+        int[] intsCopy = ints.clone();
+        intsCopy[0] = 9;
+        boolean[] flags = {true, false, true};
+        boolean[] flagsCopy = flags.clone();
+        String[] strs = {"a", "b"};
+        String[] strsCopy = strs.clone();
+        long[][] nested = {{1L}, {2L}};
+        long[][] nestedCopy = nested.clone();
+        nestedCopy[1][0] = 5L;
+
+        int flagsOk = flagsCopy.length == 3 && flagsCopy[0] && !flagsCopy[1] && flagsCopy[2] ? 1 : 0;
+        int strsOk = strsCopy != strs && strsCopy[1] == strs[1] ? 1 : 0;
+        // only the outer array is copied
+        int nestedOk = nestedCopy != nested && nestedCopy[1] == nested[1] && nested[1][0] == 5L ? 1 : 0;
+        return ints[0] * 1000 + intsCopy[0] * 100 + flagsOk * 10 + strsOk + nestedOk;
+    }
+
+    public static String arrayClassName() {
+        return new String[0][].getClass().getName();
+    }
+
+    public static int arrayHash() {
+        int[] arr = new int[4];
+        return arr.hashCode() == System.identityHashCode(arr) && !arr.equals(arr.clone()) ? 1 : 0;
+    }
+}
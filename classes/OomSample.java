@@ -0,0 +1,48 @@
+public class OomSample {
+    // rejected before allocating anything on the host
+    public static int hugeArray() {
+        try {
+            long[] arr = new long[Integer.MAX_VALUE];
+            return arr.length;
+        } catch (OutOfMemoryError e) {
+            return -1;
+        }
+    }
+
+    // OutOfMemoryError is not caught
+    public static int longArray(int n) {
+        return new long[n].length;
+    }
+
+    public static int negativeArray(int n) {
+        try {
+            return new int[n].length;
+        } catch (NegativeArraySizeException e) {
+            return -2;
+        }
+    }
+
+    // allocate much more than the limit of the heap in total, which succeeds since garbage is collected on pressure
+    public static int churn(int mib) {
+        int sum = 0;
+        for (int i = 0; i < mib; i++) {
+            byte[] buf = new byte[1024 * 1024];
+            sum += buf.length / 1024;
+        }
+        return sum;
+    }
+
+    // keep arrays alive until the heap is exhausted. returns the number of arrays allocated
+    public static int exhaust() {
+        Object[] held = new Object[1024];
+        int i = 0;
+        try {
+            for (; i < held.length; i++) {
+                held[i] = new byte[1024 * 1024];
+            }
+        } catch (OutOfMemoryError e) {
+            return i;
+        }
+        return -1;
+    }
+}
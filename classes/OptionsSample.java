@@ -0,0 +1,23 @@
+public class OptionsSample {
+    static int depth = 0;
+
+    public static int propertyLength() {
+        String name = System.getProperty("app.name");
+        String missing = System.getProperty("no.such.key", "default");
+        return name.length() * 100 + missing.length();
+    }
+
+    public static int maxDepth() {
+        try {
+            recurse();
+        } catch (StackOverflowError e) {
+            return depth;
+        }
+        return -1;
+    }
+
+    static void recurse() {
+        depth++;
+        recurse();
+    }
+}
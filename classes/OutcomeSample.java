@@ -0,0 +1,34 @@
+public class OutcomeSample {
+    static int counter;
+
+    public static void main(String[] args) {
+        counter += 1;
+    }
+
+    public static int counter() {
+        return counter;
+    }
+
+    // long and double occupy two local variable slots
+    public static double mix(long a, int b, double c, boolean d, char e) {
+        return d ? a * b + c + e : 0.0;
+    }
+
+    public static int divide(int a, int b) {
+        return helper(a, b);
+    }
+
+    private static int helper(int a, int b) {
+        return a / b;
+    }
+
+    // exceptions thrown by the VM have the cause and suppressed exceptions unset, like ones created by constructors
+    public static int initCause(int a) {
+        try {
+            return a / 0;
+        } catch (ArithmeticException e) {
+            e.initCause(new IllegalStateException());
+            return e.getSuppressed().length * 10 + (e.getCause() instanceof IllegalStateException ? 1 : 0);
+        }
+    }
+}
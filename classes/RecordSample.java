@@ -0,0 +1,59 @@
+public class RecordSample {
+  record Point(int x, int y) {}
+
+  record Line(Point from, Point to) {}
+
+  record Tagged(int[] xs, String s) {}
+
+  static int start() {
+    Point p1 = new Point(1, 2);
+    Point p2 = new Point(1, 2);
+    Point p3 = new Point(2, 1);
+
+    int res = 0;
+    if (p1.equals(p2)) {
+      res += 1;
+    }
+    if (!p1.equals(p3)) {
+      res += 2;
+    }
+    // (31 * 1) + 2 = 33
+    if (p1.hashCode() == 33) {
+      res += 4;
+    }
+    // "Line[from=Point[x=1, y=2], to=Point[x=2, y=1]]"
+    Line l = new Line(p1, p3);
+    if (l.toString().length() == 46) {
+      res += 8;
+    }
+    if (l.equals(new Line(p2, p3)) && l.hashCode() == new Line(p2, p3).hashCode()) {
+      res += 16;
+    }
+    // 1 + 2 + 4 + 8 + 16 = 31
+    return res;
+  }
+
+  // the methods of the array component are invoked on the array
+  static int taggedHashCode() {
+    int[] xs = {1};
+    Tagged t = new Tagged(xs, "a");
+    return t.hashCode() == 31 * xs.hashCode() + "a".hashCode() ? 1 : 0;
+  }
+
+  static String taggedToString() {
+    return new Tagged(new int[] {1}, "a").toString();
+  }
+
+  static int taggedEquals() {
+    int[] xs = {1};
+    int res = 0;
+    if (new Tagged(xs, "a").equals(new Tagged(xs, "a"))) {
+      res += 1;
+    }
+    // arrays are compared by identity
+    if (!new Tagged(xs, "a").equals(new Tagged(new int[] {1}, "a"))) {
+      res += 2;
+    }
+    return res;
+  }
+}
@@ -0,0 +1,80 @@
+import java.lang.ref.Cleaner;
+import java.lang.ref.PhantomReference;
+import java.lang.ref.Reference;
+import java.lang.ref.ReferenceQueue;
+import java.lang.ref.SoftReference;
+import java.lang.ref.WeakReference;
+
+// the host triggers GC between the calls
+public class RefSample {
+    static final ReferenceQueue<Object> queue = new ReferenceQueue<>();
+    static final Cleaner cleaner = Cleaner.create();
+
+    static Object strong;
+    static WeakReference<Object> weak;
+    static WeakReference<Object> weakToStrong;
+    static SoftReference<int[]> soft;
+    static PhantomReference<Object> phantom;
+
+    static int cleaned;
+    static int finalized;
+
+    static class Resource {
+        int id;
+
+        Resource(int id) {
+            this.id = id;
+        }
+    }
+
+    static class Cleanup implements Runnable {
+        public void run() {
+            cleaned++;
+        }
+    }
+
+    static class Finalizable {
+        @SuppressWarnings({"deprecation", "removal"})
+        protected void finalize() {
+            finalized++;
+        }
+    }
+
+    public static void setUp() {
+        strong = new Object();
+        weak = new WeakReference<>(new Resource(1), queue);
+        weakToStrong = new WeakReference<>(strong, queue);
+        soft = new SoftReference<>(new int[1024], queue);
+        phantom = new PhantomReference<>(new Resource(2), queue);
+        cleaner.register(new Resource(3), new Cleanup());
+        new Finalizable();
+    }
+
+    // bit flags of the state of the references, with the numbers of enqueued references, cleanups and finalizations
+    public static int check() {
+        int flags = 0;
+        if (weak.get() == null) flags |= 1;
+        if (weakToStrong.get() == strong) flags |= 2;
+        if (soft.get() != null) flags |= 4;
+        if (phantom.refersTo(null)) flags |= 8;
+        int enqueued = 0;
+        for (Reference<?> r; (r = queue.poll()) != null; ) {
+            enqueued++;
+        }
+        return flags + enqueued * 100 + cleaned * 1000 + finalized * 10000;
+    }
+
+    // soft references are cleared before OutOfMemoryError is thrown
+    public static int pressure() {
+        SoftReference<byte[]> cache = new SoftReference<>(new byte[8 * 1024 * 1024]);
+        byte[][] held = new byte[2][];
+        try {
+            for (int i = 0; i < held.length; i++) {
+                held[i] = new byte[6 * 1024 * 1024];
+            }
+        } catch (OutOfMemoryError e) {
+            return -1;
+        }
+        return cache.get() == null ? 1 : 0;
+    }
+}
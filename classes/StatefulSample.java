@@ -0,0 +1,22 @@
+public class StatefulSample {
+    private static int counter = 0;
+
+    private final int value;
+
+    private StatefulSample(int value) {
+        this.value = value;
+    }
+
+    public static int increment() {
+        counter++;
+        return counter;
+    }
+
+    public static StatefulSample create(int value) {
+        return new StatefulSample(value);
+    }
+
+    public static int valueOf(StatefulSample s) {
+        return s.value;
+    }
+}
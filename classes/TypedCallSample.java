@@ -0,0 +1,46 @@
+public class TypedCallSample {
+    public static boolean inRange(int x, long max) {
+        return 0 <= x && x <= max;
+    }
+
+    public static int length(String s) {
+        return s.length();
+    }
+
+    public static String nameOrDefault(String name) {
+        return name.isEmpty() ? "nobody" : name;
+    }
+
+    public static int sum(int[] xs) {
+        int s = 0;
+        for (int x : xs) {
+            s += x;
+        }
+        return s;
+    }
+
+    public static double[] scale(double[] xs, double k) {
+        double[] res = new double[xs.length];
+        for (int i = 0; i < xs.length; i++) {
+            res[i] = xs[i] * k;
+        }
+        return res;
+    }
+
+    public static String[] split(String s) {
+        return new String[] { s, s };
+    }
+
+    // converted to None for Option<String>, and rejected for String
+    public static String nullName() {
+        return null;
+    }
+
+    public static int overloaded(int x) {
+        return x;
+    }
+
+    public static int overloaded(long x) {
+        return (int) (x * 2);
+    }
+}
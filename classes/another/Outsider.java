@@ -0,0 +1,10 @@
+package another;
+
+import linkage.SealedShape;
+
+// listed in the permitted subclasses of SealedShape, but in a different package from it
+public final class Outsider extends SealedShape {
+    public static int sides() {
+        return 5;
+    }
+}
@@ -0,0 +1,122 @@
+// native methods of JniSample. build with:
+//   cc -shared -fPIC -I$JAVA_HOME/include -I$JAVA_HOME/include/linux -o classes/libjnisample.so classes/jni/jnisample.c
+
+#include <jni.h>
+#include <stdio.h>
+
+JNIEXPORT jint JNICALL Java_JniSample_add(JNIEnv *env, jclass cls, jint a, jint b) {
+    return a + b;
+}
+
+JNIEXPORT jint JNICALL Java_JniSample_sum(JNIEnv *env, jclass cls, jintArray xs) {
+    jsize len = (*env)->GetArrayLength(env, xs);
+    jint *elems = (*env)->GetIntArrayElements(env, xs, NULL);
+    jint sum = 0;
+    for (jsize i = 0; i < len; i++) {
+        sum += elems[i];
+    }
+    (*env)->ReleaseIntArrayElements(env, xs, elems, JNI_ABORT);
+    return sum;
+}
+
+JNIEXPORT jint JNICALL Java_JniSample_callback(JNIEnv *env, jclass cls, jint x) {
+    jmethodID twice = (*env)->GetStaticMethodID(env, cls, "twice", "(I)I");
+    return (*env)->CallStaticIntMethod(env, cls, twice, x) + 1;
+}
+
+JNIEXPORT jobject JNICALL Java_JniSample_create(JNIEnv *env, jclass cls, jint base) {
+    jmethodID init = (*env)->GetMethodID(env, cls, "<init>", "(I)V");
+    return (*env)->NewObject(env, cls, init, base);
+}
+
+JNIEXPORT jlong JNICALL Java_JniSample_scaled(JNIEnv *env, jobject this, jlong x) {
+    jclass cls = (*env)->GetObjectClass(env, this);
+    jfieldID base = (*env)->GetFieldID(env, cls, "base", "I");
+    return x * (*env)->GetIntField(env, this, base);
+}
+
+JNIEXPORT void JNICALL Java_JniSample_fill(JNIEnv *env, jclass cls, jintArray xs, jint v) {
+    jsize len = (*env)->GetArrayLength(env, xs);
+    for (jsize i = 0; i < len; i++) {
+        jint x = v + i;
+        (*env)->SetIntArrayRegion(env, xs, i, 1, &x);
+    }
+}
+
+JNIEXPORT jstring JNICALL Java_JniSample_greet(JNIEnv *env, jclass cls, jstring name) {
+    const char *s = (*env)->GetStringUTFChars(env, name, NULL);
+    char buf[256];
+    snprintf(buf, sizeof(buf), "Hello, %s!", s);
+    (*env)->ReleaseStringUTFChars(env, name, s);
+    return (*env)->NewStringUTF(env, buf);
+}
+
+JNIEXPORT void JNICALL Java_JniSample_fail(JNIEnv *env, jclass cls, jstring msg) {
+    const char *s = (*env)->GetStringUTFChars(env, msg, NULL);
+    jclass exc = (*env)->FindClass(env, "java/lang/IllegalStateException");
+    (*env)->ThrowNew(env, exc, s);
+    (*env)->ReleaseStringUTFChars(env, msg, s);
+}
+
+// throws and describes the exception, which clears it
+JNIEXPORT void JNICALL Java_JniSample_describe(JNIEnv *env, jclass cls, jstring msg) {
+    Java_JniSample_fail(env, cls, msg);
+    (*env)->ExceptionDescribe(env);
+}
+
+// pins empty arrays and strings at once, and releases each of them
+JNIEXPORT jint JNICALL Java_JniSample_pinEmpty(JNIEnv *env, jclass cls, jintArray a, jintArray b, jstring s, jstring t) {
+    jint *ea = (*env)->GetIntArrayElements(env, a, NULL);
+    jint *eb = (*env)->GetIntArrayElements(env, b, NULL);
+    const jchar *cs = (*env)->GetStringChars(env, s, NULL);
+    const jchar *ct = (*env)->GetStringChars(env, t, NULL);
+    (*env)->ReleaseIntArrayElements(env, a, ea, 0);
+    (*env)->ReleaseIntArrayElements(env, b, eb, 0);
+    (*env)->ReleaseStringChars(env, s, cs);
+    (*env)->ReleaseStringChars(env, t, ct);
+    return (*env)->ExceptionCheck(env) ? -1 : 1;
+}
+
+// jclass is the same object as the one Object.getClass() returns
+JNIEXPORT jboolean JNICALL Java_JniSample_sameClass(JNIEnv *env, jclass cls, jobject obj, jclass expected) {
+    jclass c = (*env)->GetObjectClass(env, obj);
+    jobject g = (*env)->NewGlobalRef(env, c);
+    jclass class_class = (*env)->FindClass(env, "java/lang/Class");
+    jboolean res = (*env)->IsSameObject(env, c, expected) && (*env)->IsSameObject(env, g, expected)
+        && (*env)->IsInstanceOf(env, c, class_class);
+    (*env)->DeleteGlobalRef(env, g);
+    return res;
+}
+
+JNIEXPORT jclass JNICALL Java_JniSample_thisClass(JNIEnv *env, jclass cls) {
+    return cls;
+}
+
+static jdouble average(JNIEnv *env, jclass cls, jdoubleArray xs) {
+    jsize len = (*env)->GetArrayLength(env, xs);
+    jdouble buf[16];
+    if (len == 0 || len > 16) {
+        return 0.0;
+    }
+    (*env)->GetDoubleArrayRegion(env, xs, 0, len, buf);
+    jdouble sum = 0.0;
+    for (jsize i = 0; i < len; i++) {
+        sum += buf[i];
+    }
+    return sum / len;
+}
+
+JNIEXPORT jint JNICALL JNI_OnLoad(JavaVM *vm, void *reserved) {
+    JNIEnv *env;
+    if ((*vm)->GetEnv(vm, (void **)&env, JNI_VERSION_1_8) != JNI_OK) {
+        return JNI_ERR;
+    }
+    jclass cls = (*env)->FindClass(env, "JniSample");
+    JNINativeMethod methods[] = {
+        {"average", "([D)D", (void *)average},
+    };
+    if ((*env)->RegisterNatives(env, cls, methods, 1) != JNI_OK) {
+        return JNI_ERR;
+    }
+    return JNI_VERSION_1_8;
+}
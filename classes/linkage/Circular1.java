@@ -0,0 +1,3 @@
+package linkage;
+
+public class Circular1 extends Circular2 {}
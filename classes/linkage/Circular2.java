@@ -0,0 +1,3 @@
+package linkage;
+
+public class Circular2 extends Circular1 {}
@@ -0,0 +1,7 @@
+package linkage;
+
+public interface DefaultA {
+    default int m() {
+        return 1;
+    }
+}
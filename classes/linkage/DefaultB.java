@@ -0,0 +1,7 @@
+package linkage;
+
+public interface DefaultB {
+    default int m() {
+        return 2;
+    }
+}
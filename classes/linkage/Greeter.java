@@ -0,0 +1,5 @@
+package linkage;
+
+public interface Greeter {
+    int greet();
+}
@@ -0,0 +1,39 @@
+package linkage;
+
+// some classes in this package are recompiled after LinkageSample is compiled, so that the class hierarchy gets broken:
+// - Circular2 extends Circular1 (which extends Circular2)
+// - NotAClass is an interface
+// - NotAnInterface is a class
+public class LinkageSample {
+    public static int start() {
+        int r = 0;
+        try {
+            newCircular();
+        } catch (ClassCircularityError e) {
+            r += 1;
+        }
+        try {
+            newExtendsInterface();
+        } catch (IncompatibleClassChangeError e) {
+            r += 2;
+        }
+        try {
+            newImplementsClass();
+        } catch (IncompatibleClassChangeError e) {
+            r += 4;
+        }
+        return r;
+    }
+
+    public static void newCircular() {
+        new Circular1();
+    }
+
+    public static void newExtendsInterface() {
+        new ExtendsInterface();
+    }
+
+    public static void newImplementsClass() {
+        new ImplementsClass();
+    }
+}
@@ -0,0 +1,70 @@
+package linkage;
+
+// some classes in this package are recompiled after MethodLinkageSample is compiled, so that method linkage fails:
+// - Greeter.greet() is an abstract method, which GreeterImpl doesn't implement
+// - DefaultB declares the default method m(), which conflicts with DefaultA.m()
+// - Mismatch.s() is an instance method, and Mismatch.i() is a static method
+// - Removed.gone() is removed
+// - Stranger no longer implements Greeter
+public class MethodLinkageSample {
+    public static int start() {
+        int r = 0;
+        try {
+            r += greet();
+        } catch (AbstractMethodError e) {
+            r += 1;
+        }
+        try {
+            r += conflictingDefaults();
+        } catch (IncompatibleClassChangeError e) {
+            r += 2;
+        }
+        try {
+            r += callStatic();
+        } catch (IncompatibleClassChangeError e) {
+            r += 4;
+        }
+        try {
+            r += callInstance();
+        } catch (IncompatibleClassChangeError e) {
+            r += 8;
+        }
+        try {
+            r += callRemoved();
+        } catch (NoSuchMethodError e) {
+            r += 16;
+        }
+        try {
+            r += greetStranger();
+        } catch (IncompatibleClassChangeError e) {
+            r += 32;
+        }
+        return r;
+    }
+
+    public static int greet() {
+        Greeter g = new GreeterImpl();
+        return g.greet();
+    }
+
+    public static int greetStranger() {
+        Greeter g = new Stranger();
+        return g.greet();
+    }
+
+    public static int conflictingDefaults() {
+        return new BothDefaults().m();
+    }
+
+    public static int callStatic() {
+        return Mismatch.s();
+    }
+
+    public static int callInstance() {
+        return new Mismatch().i();
+    }
+
+    public static int callRemoved() {
+        return new Removed().gone();
+    }
+}
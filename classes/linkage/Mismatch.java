@@ -0,0 +1,11 @@
+package linkage;
+
+public class Mismatch {
+    public int s() {
+        return 1;
+    }
+
+    public static int i() {
+        return 1;
+    }
+}
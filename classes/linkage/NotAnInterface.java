@@ -0,0 +1,3 @@
+package linkage;
+
+public class NotAnInterface {}
@@ -0,0 +1,8 @@
+package linkage;
+
+// extends SealedShape, which does not permit this class
+public final class NotPermitted extends SealedShape {
+    public static int sides() {
+        return 3;
+    }
+}
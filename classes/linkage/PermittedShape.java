@@ -0,0 +1,7 @@
+package linkage;
+
+public final class PermittedShape extends SealedShape {
+    public static int sides() {
+        return 4;
+    }
+}
@@ -0,0 +1,5 @@
+package linkage;
+
+// recompiled as a sealed class after NotPermitted is compiled.
+// another/Outsider is added to the permitted subclasses by editing the class file, since javac rejects permitted subclasses in other packages
+public sealed class SealedShape permits PermittedShape {}
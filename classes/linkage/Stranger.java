@@ -0,0 +1,7 @@
+package linkage;
+
+public class Stranger {
+    public int greet() {
+        return 1;
+    }
+}
@@ -0,0 +1,4 @@
+package plugin;
+
+public abstract class AbstractPlugin implements Plugin {
+}
@@ -0,0 +1,9 @@
+package plugin;
+
+public interface Plugin {
+    int apply(int x);
+
+    default int applyTwice(int x) {
+        return apply(apply(x));
+    }
+}
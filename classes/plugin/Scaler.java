@@ -0,0 +1,24 @@
+package plugin;
+
+public class Scaler implements Plugin {
+    private final int factor;
+    private int calls;
+
+    public Scaler(int factor) {
+        this.factor = factor;
+    }
+
+    public Scaler(int numerator, int denominator) {
+        this(numerator / denominator);
+    }
+
+    @Override
+    public int apply(int x) {
+        calls++;
+        return x * factor;
+    }
+
+    public int calls() {
+        return calls;
+    }
+}
@@ -6,6 +6,7 @@ mod attr;
 mod const_pool;
 
 use attr::{parse_attributes, Attribute, CodeAttr};
+pub use attr::{BootstrapMethod, ExceptionTableEntry, RecordComponentInfo};
 use bitflags::bitflags;
 pub use const_pool::{CPInfo, ConstantPool};
 
@@ -28,6 +29,10 @@ impl ClassAccessFlags {
     pub fn is_interface(&self) -> bool {
         self.contains(ClassAccessFlags::INTERFACE)
     }
+
+    pub fn is_abstract(&self) -> bool {
+        self.contains(ClassAccessFlags::ABSTRACT)
+    }
 }
 
 #[derive(Debug)]
@@ -39,6 +44,11 @@ pub struct ClassFile {
     pub interfaces: Vec<String>,
     pub fields: Vec<FieldInfo>,
     pub methods: Vec<MethodInfo>,
+    pub bootstrap_methods: Vec<BootstrapMethod>,
+    // None if the class is not sealed
+    pub permitted_subclasses: Option<Vec<String>>,
+    // None if the class is not a record class
+    pub record_components: Option<Vec<RecordComponentInfo>>,
 }
 
 impl ClassFile {
@@ -72,7 +82,18 @@ impl ClassFile {
         let fields = parse_fields(&mut bs, &cp);
         let methods = parse_methods(&mut bs, &cp);
 
-        // skip attributes
+        // parse attributes
+        let mut bootstrap_methods = Vec::new();
+        let mut permitted_subclasses = None;
+        let mut record_components = None;
+        for attr in parse_attributes(&mut bs, &cp) {
+            match attr {
+                Attribute::BootstrapMethods(a) => bootstrap_methods = a.bootstrap_methods,
+                Attribute::PermittedSubclasses(a) => permitted_subclasses = Some(a.classes),
+                Attribute::Record(a) => record_components = Some(a.components),
+                _ => {}
+            }
+        }
 
         Ok(ClassFile {
             constant_pool: cp,
@@ -82,6 +103,9 @@ impl ClassFile {
             interfaces,
             fields,
             methods,
+            bootstrap_methods,
+            permitted_subclasses,
+            record_components,
         })
     }
 }
@@ -186,13 +210,6 @@ impl MethodAccessFlags {
     pub fn is_public_non_static(&self) -> bool {
         self.contains(MethodAccessFlags::PUBLIC) && !self.contains(MethodAccessFlags::STATIC)
     }
-
-    /// whether the method is `default` interface method?
-    pub fn is_interface_default(&self) -> bool {
-        !self.intersects(
-            MethodAccessFlags::PRIVATE | MethodAccessFlags::STATIC | MethodAccessFlags::ABSTRACT,
-        )
-    }
 }
 
 #[cfg(test)]
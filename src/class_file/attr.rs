@@ -5,6 +5,9 @@ use crate::support::ByteSeq;
 pub enum Attribute {
     ConstantValue(ConstValAttr),
     Code(CodeAttr),
+    BootstrapMethods(BootstrapMethodsAttr),
+    PermittedSubclasses(PermittedSubclassesAttr),
+    Record(RecordAttr),
     Unsupported,
 }
 
@@ -27,6 +30,21 @@ pub fn parse_attributes(bs: &mut ByteSeq, cp: &ConstantPool) -> Vec<Attribute> {
                 let code_attr = parse_code_attr(bs, cp);
                 Attribute::Code(code_attr)
             }
+            // BootstrapMethods_attribute
+            BootstrapMethodsAttr::NAME => {
+                let bsm_attr = parse_bootstrap_methods_attr(bs);
+                Attribute::BootstrapMethods(bsm_attr)
+            }
+            // PermittedSubclasses_attribute
+            PermittedSubclassesAttr::NAME => {
+                let ps_attr = parse_permitted_subclasses_attr(bs, cp);
+                Attribute::PermittedSubclasses(ps_attr)
+            }
+            // Record_attribute
+            RecordAttr::NAME => {
+                let rec_attr = parse_record_attr(bs, cp);
+                Attribute::Record(rec_attr)
+            }
             _ => {
                 bs.skip(len);
                 Attribute::Unsupported
@@ -59,6 +77,7 @@ pub struct CodeAttr {
     pub max_stack: u16,
     pub max_locals: u16,
     pub code: Vec<u8>,
+    pub exception_table: Vec<ExceptionTableEntry>,
 }
 
 impl CodeAttr {
@@ -72,9 +91,23 @@ fn parse_code_attr(bs: &mut ByteSeq, cp: &ConstantPool) -> CodeAttr {
     let code_len = bs.read_u32() as usize;
     let code = bs.read_bytes(code_len);
 
-    // skip exception table(8 * len)
     let exc_tbl_len = bs.read_u16() as usize;
-    bs.skip(8 * exc_tbl_len);
+    let mut exception_table = Vec::with_capacity(exc_tbl_len);
+    for _ in 0..exc_tbl_len {
+        let start_pc = bs.read_u16();
+        let end_pc = bs.read_u16();
+        let handler_pc = bs.read_u16();
+        let catch_type = match bs.read_u16() {
+            0 => None, // catches any exceptions (used to implement `finally`)
+            idx => Some(cp.get_class(idx).name.to_string()),
+        };
+        exception_table.push(ExceptionTableEntry {
+            start_pc,
+            end_pc,
+            handler_pc,
+            catch_type,
+        });
+    }
 
     // skip attributes
     let _ = parse_attributes(bs, cp);
@@ -83,5 +116,93 @@ fn parse_code_attr(bs: &mut ByteSeq, cp: &ConstantPool) -> CodeAttr {
         max_stack,
         max_locals,
         code,
+        exception_table,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExceptionTableEntry {
+    // the handler is active while pc is in [start_pc, end_pc)
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct BootstrapMethodsAttr {
+    pub bootstrap_methods: Vec<BootstrapMethod>,
+}
+
+impl BootstrapMethodsAttr {
+    const NAME: &str = "BootstrapMethods";
+}
+
+#[derive(Debug, Clone)]
+pub struct BootstrapMethod {
+    // index of CONSTANT_MethodHandle
+    pub method_ref: u16,
+    // indices of loadable constants
+    pub arguments: Vec<u16>,
+}
+
+fn parse_bootstrap_methods_attr(bs: &mut ByteSeq) -> BootstrapMethodsAttr {
+    let count = bs.read_u16() as usize;
+    let mut bootstrap_methods = Vec::with_capacity(count);
+    for _ in 0..count {
+        let method_ref = bs.read_u16();
+        let num_args = bs.read_u16() as usize;
+        let arguments = (0..num_args).map(|_| bs.read_u16()).collect();
+        bootstrap_methods.push(BootstrapMethod {
+            method_ref,
+            arguments,
+        });
+    }
+    BootstrapMethodsAttr { bootstrap_methods }
+}
+
+#[derive(Debug)]
+pub struct PermittedSubclassesAttr {
+    pub classes: Vec<String>,
+}
+
+impl PermittedSubclassesAttr {
+    const NAME: &str = "PermittedSubclasses";
+}
+
+fn parse_permitted_subclasses_attr(bs: &mut ByteSeq, cp: &ConstantPool) -> PermittedSubclassesAttr {
+    let count = bs.read_u16() as usize;
+    let classes = (0..count)
+        .map(|_| cp.get_class(bs.read_u16()).name.to_string())
+        .collect();
+    PermittedSubclassesAttr { classes }
+}
+
+#[derive(Debug)]
+pub struct RecordAttr {
+    pub components: Vec<RecordComponentInfo>,
+}
+
+impl RecordAttr {
+    const NAME: &str = "Record";
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordComponentInfo {
+    pub name: String,
+    pub descriptor: String,
+}
+
+fn parse_record_attr(bs: &mut ByteSeq, cp: &ConstantPool) -> RecordAttr {
+    let count = bs.read_u16() as usize;
+    let mut components = Vec::with_capacity(count);
+    for _ in 0..count {
+        let name = cp.get_utf8(bs.read_u16()).to_string();
+        let descriptor = cp.get_utf8(bs.read_u16()).to_string();
+        // skip attributes of the component (Signature, annotations, etc.)
+        let _ = parse_attributes(bs, cp);
+
+        components.push(RecordComponentInfo { name, descriptor });
     }
+    RecordAttr { components }
 }
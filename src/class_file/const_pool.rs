@@ -28,7 +28,6 @@ impl ConstantPool {
         if let CPInfo::Utf8(s) = self.get_info(idx) {
             s.as_str()
         } else {
-            eprintln!("not a CONSTANT_Utf8");
             ""
         }
     }
@@ -38,9 +37,8 @@ impl ConstantPool {
         &self.0[idx as usize - 1]
     }
 
-    pub(in crate::class_file) fn get_class(&self, idx: u16) -> CPClassResolved {
+    pub(in crate::class_file) fn get_class(&self, idx: u16) -> CPClassResolved<'_> {
         let CPInfo::Class { name_idx } = self.get_info(idx) else {
-            eprintln!("not a CONSTANT_Class");
             return CPClassResolved::default();
         };
         CPClassResolved {
@@ -48,38 +46,6 @@ impl ConstantPool {
         }
     }
 
-    pub(in crate::class_file) fn get_name_and_type(&self, idx: u16) -> CPNameAndTypeResolved {
-        let CPInfo::NameAndType {
-            name_idx,
-            descriptor_idx,
-        } = self.get_info(idx)
-        else {
-            eprintln!("not a CONSTANT_NameAndType");
-            return CPNameAndTypeResolved::default();
-        };
-        CPNameAndTypeResolved {
-            name: self.get_utf8(*name_idx),
-            descriptor: self.get_utf8(*descriptor_idx),
-        }
-    }
-
-    pub(in crate::class_file) fn get_method_ref(&self, idx: u16) -> CPMethodrefResolved {
-        let CPInfo::Methodref {
-            class_idx,
-            name_and_type_idx,
-        } = self.get_info(idx)
-        else {
-            eprintln!("not a CONSTANT_Methodref");
-            return CPMethodrefResolved::default();
-        };
-        let cls = self.get_class(*class_idx);
-        let nt = self.get_name_and_type(*name_and_type_idx);
-        CPMethodrefResolved {
-            class: cls,
-            name_and_type: nt,
-        }
-    }
-
     pub fn infos(&self) -> impl Iterator<Item = &CPInfo> {
         self.0.iter()
     }
@@ -90,24 +56,6 @@ pub(in crate::class_file) struct CPClassResolved<'a> {
     pub name: &'a str,
 }
 
-#[derive(Default)]
-pub(in crate::class_file) struct CPNameAndTypeResolved<'a> {
-    name: &'a str,
-    descriptor: &'a str,
-}
-
-#[derive(Default)]
-pub(in crate::class_file) struct CPFieldrefResolved<'a> {
-    class: CPClassResolved<'a>,
-    name_and_type: CPNameAndTypeResolved<'a>,
-}
-
-#[derive(Default)]
-pub(in crate::class_file) struct CPMethodrefResolved<'a> {
-    class: CPClassResolved<'a>,
-    name_and_type: CPNameAndTypeResolved<'a>,
-}
-
 /// Class/MethodRef/NameAndTypeが持つインデックスは、ConstantPool内の他のエントリへの参照。
 /// インデックスは1-オリジンであることに注意!
 #[derive(Debug, Clone)]
@@ -139,6 +87,21 @@ pub enum CPInfo {
         name_idx: u16,
         descriptor_idx: u16, // format: (<param type>*)<return type>
     },
+    MethodHandle {
+        reference_kind: u8,
+        reference_idx: u16,
+    },
+    MethodType {
+        descriptor_idx: u16,
+    },
+    InvokeDynamic {
+        bootstrap_method_attr_idx: u16,
+        name_and_type_idx: u16,
+    },
+    // entry of the type not supported by this implementation (e.g. CONSTANT_Dynamic)
+    Skipped {
+        tag: u8,
+    },
     Unsupported,
 }
 
@@ -199,20 +162,29 @@ fn parse_cp_info(bs: &mut ByteSeq) -> Result<CPInfo, Box<dyn std::error::Error +
             name_idx: bs.read_u16(),
             descriptor_idx: bs.read_u16(),
         },
+        // CONSTANT_MethodHandle
+        15 => CPInfo::MethodHandle {
+            reference_kind: bs.read_u8(),
+            reference_idx: bs.read_u16(),
+        },
+        // CONSTANT_MethodType
+        16 => CPInfo::MethodType {
+            descriptor_idx: bs.read_u16(),
+        },
+        // CONSTANT_InvokeDynamic
+        18 => CPInfo::InvokeDynamic {
+            bootstrap_method_attr_idx: bs.read_u16(),
+            name_and_type_idx: bs.read_u16(),
+        },
         // skip unsupported cp info type
-        16 | 19 | 20 => skip_unsupported_cp_info(bs, tag, 2),
-        15 => skip_unsupported_cp_info(bs, tag, 3),
-        17 | 18 => skip_unsupported_cp_info(bs, tag, 4),
-        _ => {
-            eprintln!("invalid cp info tag");
-            unreachable!()
-        }
+        19 | 20 => skip_unsupported_cp_info(bs, tag, 2),
+        17 => skip_unsupported_cp_info(bs, tag, 4),
+        _ => Err(format!("invalid constant pool tag: {tag}"))?,
     };
     Ok(parsed)
 }
 
 fn skip_unsupported_cp_info(bs: &mut ByteSeq, tag: u8, n: usize) -> CPInfo {
-    eprintln!("skipping unsupported constant pool info type: {}", tag);
     bs.skip(n);
-    CPInfo::Unsupported
+    CPInfo::Skipped { tag }
 }
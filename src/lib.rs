@@ -0,0 +1,20 @@
+//! A toy implementation of a minimum subset of JVM.
+//!
+//! ```no_run
+//! use kafa::{Outcome, VMBuilder, Value};
+//!
+//! let mut vm = VMBuilder::new().classpath(["classes"]).build();
+//! let res = vm.execute("MakeJVM", "start", "()I", &[]);
+//! assert!(matches!(res, Ok(Outcome::Returned(Value::Int(_)))));
+//! ```
+
+mod class_file;
+mod support;
+mod vm;
+
+pub use vm::{
+    split_paths, Category, ClassHistogram, ClassRef, Collector, DiagnosticSink, DominatorTree,
+    GcStats, HeapEntry, HistogramEntry, JArray, JObject, JString, JavaArgs, JavaException,
+    JavaPrimitiveType, JavaRefType, JavaReturn, JavaType, Level, MethodRef, Outcome,
+    RecordComponent, VMBuilder, VMError, VMResult, Value, Verification, VM,
+};
@@ -1,16 +1,19 @@
-#![allow(dead_code)]
+use std::{env, process::ExitCode};
 
-mod class_file;
-mod support;
-mod vm;
+use kafa::{split_paths, Category, JavaException, VMBuilder};
 
-use std::env;
+const ENV_KEY_CLASSPATH: &str = "KAFA_CLASSPATH";
+const ENV_KEY_LIBRARY_PATH: &str = "KAFA_LIBRARY_PATH";
 
-use vm::{VMResult, Value, VM};
+const USAGE: &str =
+    "usage: kafa [-verbose[:class|link|init|interpreter|gc,...]] <main class> [args...]";
 
-const ENV_KEY_CLASSPATH: &str = "KAFA_CLASSPATH";
+fn main() -> ExitCode {
+    let Some((verbose, main_class, args)) = parse_args(env::args().skip(1)) else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
 
-fn main() {
     let Ok(cp) = env::var_os(ENV_KEY_CLASSPATH).map_or_else(
         || {
             eprintln!("environment variable {ENV_KEY_CLASSPATH} is not set; fallback to $pwd");
@@ -19,28 +22,50 @@ fn main() {
         |cp| Ok(cp.into()),
     ) else {
         eprintln!("failed to determine classpath. abort");
-        return;
+        return ExitCode::FAILURE;
     };
-    println!("classpath: {cp:?}");
-
-    let mut vm = VM::new(&cp);
+    let library_path = env::var_os(ENV_KEY_LIBRARY_PATH).unwrap_or_default();
 
-    print_result(vm.execute("MakeJVM", "start", "()I", &[]));
-    print_result(vm.execute("MakeJVM", "start2", "()I", &[]));
-    print_result(vm.execute("MakeJVM", "start3", "()Z", &[]));
+    let builder = VMBuilder::new()
+        .classpath(split_paths(&cp))
+        .library_path(split_paths(&library_path));
+    let mut vm = verbose.iter().fold(builder, |b, &c| b.verbose(c)).build();
 
-    print_result(vm.execute("StaticFieldsSample", "start", "()I", &[]));
-
-    print_result(vm.execute("loader/RuntimeClassLoadingSample", "start", "()I", &[]));
+    // the main class may be given by the binary name (e.g. `pkg.Main`)
+    let main_class = main_class.replace('.', "/");
+    match vm.call_static::<_, ()>(&main_class, "main", (args,)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            match err.downcast::<JavaException>() {
+                Ok(exc) => {
+                    eprintln!("Exception in thread \"main\" {exc}");
+                    for m in &exc.stack_trace {
+                        eprintln!("\tat {m}");
+                    }
+                }
+                Err(err) => eprintln!("failed to execute: {err}"),
+            }
+            ExitCode::FAILURE
+        }
+    }
 }
 
-fn print_result(res: VMResult<Value>) {
-    match res {
-        Ok(v) => {
-            println!("return value: {v:?}");
-        }
-        Err(e) => {
-            println!("failed to execute: {e}");
+// parse options like `-verbose:class,init` followed by the main class and its arguments. `-verbose` enables all categories
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Option<(Vec<Category>, String, Vec<String>)> {
+    let mut categories = Vec::new();
+    loop {
+        let arg = args.next()?;
+        match arg.strip_prefix("-verbose") {
+            Some("") => categories.extend(Category::ALL),
+            Some(names) => {
+                for name in names.strip_prefix(':')?.split(',') {
+                    categories.push(Category::from_name(name)?);
+                }
+            }
+            None if arg.starts_with('-') => return None,
+            None => return Some((categories, arg, args.collect())),
         }
     }
 }
@@ -32,6 +32,14 @@ impl ByteSeq {
         self.i
     }
 
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
     pub fn read_u8(&mut self) -> u8 {
         let b = self.buf[self.i];
         self.i += 1;
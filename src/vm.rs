@@ -1,70 +1,1973 @@
 mod class;
 mod class_loader;
+mod diagnostics;
 mod error;
+mod exception;
 mod frame;
+mod gc;
+mod handle;
 mod heap;
+mod hprof;
+mod inspect;
 mod instruction;
+mod java_type;
+mod jni;
 mod method_area;
+mod native;
+mod object_methods;
+mod options;
 mod thread;
 mod value;
+mod verifier;
 
-use std::ffi::{OsStr, OsString};
+use std::{ffi::OsStr, io::Write, mem, path::Path, rc::Rc};
 
-use class::MethodSignature;
-pub use class_loader::ClassLoader;
-pub use error::VMResult;
-use frame::Frame;
-use heap::Heap;
+pub use class::RecordComponent;
+use class::{Class, MethodCodeSpec, MethodSignature};
+pub use diagnostics::{Category, DiagnosticSink, Level};
+pub use error::{JavaException, VMError, VMResult};
+pub use handle::{ClassRef, JArray, JObject, JString, MethodRef};
+pub use heap::GcStats;
+use heap::{Heap, RefValue};
+pub use inspect::{ClassHistogram, DominatorTree, HeapEntry, HistogramEntry};
+pub use java_type::{JavaArgs, JavaPrimitiveType, JavaRefType, JavaReturn, JavaType};
 use method_area::MethodArea;
+use native::{NativeEnv, NativeFn, NativeRegistry};
+use options::VMConfig;
+pub use options::{split_paths, Collector, VMBuilder, Verification};
 use thread::Thread;
+use value::MutValue;
 pub use value::Value;
 
+/// How an invocation of a Java method completed.
+#[derive(Debug)]
+pub enum Outcome {
+    /// The method returned a value of a primitive type, or null.
+    Returned(Value),
+    /// The method returned a (non-null) reference, which is kept alive from GC by the handle.
+    ReturnedObject(JObject),
+    /// The method returned without a value (its return type is void).
+    ReturnedVoid,
+    /// The method completed abruptly by the exception, with the stack trace at the point it was thrown (innermost first).
+    Threw(JObject, Vec<String>),
+}
+
 pub struct VM {
     thread: Thread,
-    classpath: OsString,
+    config: Rc<VMConfig>,
+    // classes and objects are shared by all executions until the VM is reset
+    meth_area: MethodArea,
+    heap: Heap,
 }
 
 impl VM {
+    /// Creates a VM with the default configuration, whose classpath is `classpath` (paths separated by `;`).
+    ///
+    /// Use [`VMBuilder`] to configure the VM further.
     pub fn new<P>(classpath: &P) -> VM
     where
-        P: AsRef<OsStr>,
+        P: AsRef<OsStr> + ?Sized,
     {
-        VM {
-            thread: Thread::new(),
-            classpath: OsString::from(classpath),
+        VMBuilder::new()
+            .classpath(options::split_paths(classpath))
+            .build()
+    }
+
+    /// Discards all loaded classes (including their static fields) and objects.
+    ///
+    /// Handles and references obtained before reset are invalidated; operations on them fail after that.
+    /// Native methods registered by [`VM::register_native`] and loaded native libraries remain registered.
+    pub fn reset(&mut self) {
+        let mut natives = mem::replace(self.meth_area.natives_mut(), NativeRegistry::new());
+        natives.jni.clear_references();
+        self.thread = Thread::with_max_depth(self.config.max_frame_depth);
+        self.meth_area = MethodArea::with_natives(self.config.clone(), natives);
+        // the heap is cleared rather than replaced, so that references from before reset are not confused with new ones
+        self.heap.clear();
+    }
+
+    /// Frees objects that are no longer reachable, and returns the statistics of the collection.
+    ///
+    /// GC also runs automatically as objects are allocated. Objects referred by handles (e.g. [`JObject`]) are kept alive,
+    /// while raw references in [`Value`]s held by the host may be freed or moved; they are detected as invalid after that.
+    /// With [`Collector::Generational`], this collects the old generation as well as the nursery.
+    ///
+    /// Referents of weak and phantom references that are no longer strongly reachable are cleared (soft ones are cleared only when the heap is exhausted).
+    /// Before returning, the cleared references are enqueued to their queues, actions registered to `Cleaner`s are run, and unreachable objects are finalized.
+    /// Finalized objects are freed by the next collection, unless they are resurrected.
+    pub fn gc(&mut self) -> GcStats {
+        let stats = gc::collect(&mut self.thread, &self.meth_area, &mut self.heap, true);
+        self.thread
+            .process_pending(&mut self.meth_area, &mut self.heap);
+        stats
+    }
+
+    /// Writes the heap dump in the HPROF binary format, which can be analyzed by tools like Eclipse MAT and VisualVM.
+    ///
+    /// Objects referred by the frames, static fields, native code and handles of the host are recorded as GC roots.
+    pub fn dump_heap<W: Write>(&mut self, out: W) -> VMResult<()> {
+        hprof::dump(out, &self.thread, &self.meth_area, &self.heap)
+    }
+
+    /// Iterates over the objects and arrays on the heap.
+    ///
+    /// Unreachable ones remain until they are collected; call [`VM::gc`] beforehand to inspect live ones only.
+    /// References in the entries are raw ones, which become invalid once GC moves or frees the values.
+    pub fn heap_entries(&self) -> impl Iterator<Item = HeapEntry> + '_ {
+        inspect::entries(&self.heap)
+    }
+
+    /// Numbers of instances and bytes on the heap per class, like `jmap -histo`.
+    pub fn class_histogram(&self) -> ClassHistogram {
+        inspect::histogram(&self.heap)
+    }
+
+    /// Computes the dominator tree of the objects reachable from the GC roots, which gives retained sizes of the objects.
+    pub fn dominator_tree(&self) -> DominatorTree {
+        inspect::dominator_tree(&self.heap, gc::roots(&self.thread, &self.meth_area))
+    }
+
+    /// Renders the object graph reachable from the reference in the DOT language of Graphviz.
+    pub fn object_graph_dot(&self, from: Value) -> VMResult<String> {
+        match from {
+            Value::Reference(r) if r != 0 => inspect::object_graph_dot(&self.heap, r),
+            _ => Err(format!("not a non-null reference: {from:?}"))?,
         }
     }
 
+    /// Value of the system property, which can be set by [`VMBuilder::system_property`].
+    pub fn system_property(&self, key: &str) -> Option<&str> {
+        self.config.properties.get(key).map(String::as_str)
+    }
+
+    /// Loads the class (if not loaded yet).
+    pub fn find_class(&mut self, class_name: &str) -> VMResult<ClassRef> {
+        self.meth_area.resolve_class(class_name).map(ClassRef)
+    }
+
+    /// Resolves the method declared in the class or its superclasses/superinterfaces.
+    pub fn resolve_method(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        method_desc: &str,
+    ) -> VMResult<MethodRef> {
+        let sig = MethodSignature::new_with_raw_descriptor(method_name, method_desc);
+        let cls = self.meth_area.resolve_class(class_name)?;
+        let (class, method) = if cls.access_flags.is_interface() {
+            self.meth_area.resolve_interface_method(class_name, &sig)?
+        } else {
+            self.meth_area.resolve_class_method(class_name, &sig)?
+        };
+        Ok(MethodRef { class, method })
+    }
+
+    /// Invokes the static method, and returns how it completed.
+    ///
+    /// Arguments must match the parameter types of the method; values of boolean/byte/char/short parameters are passed as int.
+    /// References returned from methods are held by handles, so they remain valid across invocations and collections.
+    pub fn invoke_static(&mut self, meth: &MethodRef, args: &[Value]) -> VMResult<Outcome> {
+        if !meth.is_static() {
+            return Err(format!(
+                "{}.{} is not a static method",
+                meth.class_name(),
+                meth.method.signature
+            ))?;
+        }
+        let args = self.args_for_method(meth.class_name(), &meth.method.signature, args)?;
+
+        // initialize the class that declares the method. <clinit> may run GC, so the arguments are held meanwhile
+        let cls = meth.class.clone();
+        let (init, args) = self.with_values_held(&args, |vm| {
+            cls.initialize(&mut vm.thread, &mut vm.meth_area, &mut vm.heap)
+        })?;
+        let res = init.and_then(|_| {
+            self.thread.invoke_method(
+                &mut self.meth_area,
+                &mut self.heap,
+                meth.class.clone(),
+                meth.method.clone(),
+                &args,
+            )
+        });
+        self.outcome_of(res)
+    }
+
+    /// Executes the static method.
     pub fn execute(
         &mut self,
         class_name: &str,
         method_name: &str,
         method_desc: &str,
         args: &[Value],
-    ) -> VMResult<Value> {
-        println!("executing {class_name}.{method_name}:{method_desc} with args: {args:?}");
+    ) -> VMResult<Outcome> {
+        self.config.report(
+            Level::Info,
+            Category::Interpreter,
+            format_args!("executing {class_name}.{method_name}:{method_desc} with args: {args:?}"),
+        );
 
-        let cls_loader = ClassLoader::new(&self.classpath);
-        let mut meth_area = MethodArea::new(cls_loader);
-        let mut heap = Heap::new();
+        let meth = self.resolve_method(class_name, method_name, method_desc)?;
+        self.invoke_static(&meth, args)
+    }
 
-        // initialize class
-        let init_cls = meth_area.resolve_class(class_name)?;
-        init_cls.initialize(&mut self.thread, &mut meth_area, &mut heap)?;
+    /// Creates an instance of the class, and initializes it by the constructor whose descriptor is `ctor_desc`.
+    ///
+    /// Returns the created object as [`Outcome::ReturnedObject`] if the constructor completes normally.
+    pub fn new_instance(
+        &mut self,
+        class_name: &str,
+        ctor_desc: &str,
+        args: &[Value],
+    ) -> VMResult<Outcome> {
+        let sig = MethodSignature::new_with_raw_descriptor("<init>", ctor_desc);
+        let args = self.args_for_method(class_name, &sig, args)?;
 
-        // execute bootstrap method
-        let mut bs_frame = Frame::new_empty();
-        for arg in args {
-            bs_frame.push_operand(*arg);
+        match self.construct(class_name, &sig, &args) {
+            Ok(obj) => Ok(Outcome::ReturnedObject(obj)),
+            Err(err) => self.outcome_of(Err(err)),
         }
-        self.thread.push_frame(bs_frame);
+    }
 
-        let sig = MethodSignature::new_with_raw_descriptor(method_name, method_desc);
+    // same as `new` followed by `invokespecial <init>`. the created object is held by the handle returned
+    fn construct(
+        &mut self,
+        class_name: &str,
+        ctor_sig: &MethodSignature,
+        args: &[Value],
+    ) -> VMResult<JObject> {
+        let cls = self.meth_area.resolve_class(class_name)?;
+        if cls.access_flags.is_interface() || cls.access_flags.is_abstract() {
+            Err(JavaException::instantiation_error(
+                class_name.replace('/', "."),
+            ))?;
+        }
+        // constructors are not inherited
+        let Some(ctor) = cls.lookup_instance_method(ctor_sig) else {
+            return Err(JavaException::no_such_method_error(
+                method_area::method_name(class_name, ctor_sig),
+            ))?;
+        };
+        // <clinit> may run GC, so the arguments are held while the class is initialized
+        let (init, args) = self.with_values_held(args, |vm| {
+            cls.clone()
+                .initialize(&mut vm.thread, &mut vm.meth_area, &mut vm.heap)
+        })?;
+        init?;
+
+        let Value::Reference(r) = self.heap.alloc_object(cls.clone(), &mut self.meth_area)? else {
+            unreachable!()
+        };
+        // the object may be moved by GC while the constructor runs
+        let obj = JObject(self.heap.new_handle(r)?);
+        let mut all_args = Vec::with_capacity(args.len() + 1);
+        all_args.push(Value::Reference(r));
+        all_args.extend(args);
         self.thread
-            .exec_bootstrap_method(&mut meth_area, &mut heap, class_name, &sig)?;
+            .invoke_method(&mut self.meth_area, &mut self.heap, cls, ctor, &all_args)?;
+        Ok(obj)
+    }
+
+    /// Invokes the instance method on the receiver, like `invokevirtual` does.
+    ///
+    /// The method is resolved in the class of the receiver, then the method to be invoked is selected.
+    pub fn invoke_virtual(
+        &mut self,
+        receiver: &JObject,
+        method_name: &str,
+        method_desc: &str,
+        args: &[Value],
+    ) -> VMResult<Outcome> {
+        let sig = MethodSignature::new_with_raw_descriptor(method_name, method_desc);
+        let rt_cls = self.receiver_class(receiver)?;
+        let args = self.args_for_method(&rt_cls.name, &sig, args)?;
+
+        let res = self.thread.invoke_virtual(
+            &mut self.meth_area,
+            &mut self.heap,
+            Value::Reference(receiver.r()),
+            &sig,
+            &args,
+        );
+        self.outcome_of(res)
+    }
+
+    /// Invokes the method of the interface on the receiver, like `invokeinterface` does.
+    pub fn invoke_interface(
+        &mut self,
+        receiver: &JObject,
+        iface_name: &str,
+        method_name: &str,
+        method_desc: &str,
+        args: &[Value],
+    ) -> VMResult<Outcome> {
+        let sig = MethodSignature::new_with_raw_descriptor(method_name, method_desc);
+        self.receiver_class(receiver)?;
+        let args = self.args_for_method(iface_name, &sig, args)?;
+
+        let res = self.thread.invoke_interface(
+            &mut self.meth_area,
+            &mut self.heap,
+            Value::Reference(receiver.r()),
+            iface_name,
+            &sig,
+            &args,
+        );
+        self.outcome_of(res)
+    }
+
+    fn receiver_class(&mut self, receiver: &JObject) -> VMResult<Rc<Class>> {
+        match self.heap.get(receiver.r()) {
+            Some(RefValue::Object(obj)) => Ok(obj.get_class()),
+            Some(RefValue::Array(arr)) => {
+                let desc = arr.descriptor();
+                self.meth_area.resolve_class(desc.as_str())
+            }
+            _ => Err(format!("receiver is not a valid object: {receiver:?}"))?,
+        }
+    }
+
+    // hold the references among the values by handles while `f` runs, and return the result of `f` along with the values.
+    // the references in the returned values follow the referents moved by GC, which may run in `f`
+    fn with_values_held<T>(
+        &mut self,
+        vals: &[Value],
+        f: impl FnOnce(&mut VM) -> T,
+    ) -> VMResult<(T, Vec<Value>)> {
+        let handles = vals
+            .iter()
+            .map(|v| match *v {
+                Value::Reference(r) if r != 0 => self.heap.new_handle(r).map(Some),
+                _ => Ok(None),
+            })
+            .collect::<VMResult<Vec<_>>>()?;
+        let res = f(self);
+        let vals = vals
+            .iter()
+            .zip(handles)
+            .map(|(&v, h)| h.map_or(v, |h| Value::Reference(h.get())))
+            .collect();
+        Ok((res, vals))
+    }
+
+    // check the arguments against the parameter types of the method, and convert them to values passed to the method
+    fn args_for_method(
+        &mut self,
+        class_name: &str,
+        sig: &MethodSignature,
+        args: &[Value],
+    ) -> VMResult<Vec<Value>> {
+        let params = sig.descriptor.param_types();
+        if params.len() != args.len() {
+            return Err(format!(
+                "{class_name}.{sig} takes {} argument(s), but {} given",
+                params.len(),
+                args.len()
+            ))?;
+        }
+
+        params
+            .iter()
+            .zip(args)
+            .map(|(&param, &arg)| self.value_of_type(param, arg))
+            .collect()
+    }
+
+    // check that the value can be stored to a variable of the type `desc`, and convert it to the representation for the type
+    fn value_of_type(&mut self, desc: &str, v: Value) -> VMResult<Value> {
+        let converted = match (desc, v) {
+            // boolean is represented as int
+            ("Z", v) => match v.as_int() {
+                Some(b @ (0 | 1)) => Some(Value::Int(b)),
+                _ => None,
+            },
+            ("B", v) => v.as_int().map(|i| Value::Int(i as i8 as i32)),
+            ("C", v) => v.as_int().map(|i| Value::Int(i as u16 as i32)),
+            ("S", v) => v.as_int().map(|i| Value::Int(i as i16 as i32)),
+            ("I", v) => v.as_int().map(Value::Int),
+            ("J", Value::Long(_)) | ("F", Value::Float(_)) | ("D", Value::Double(_)) => Some(v),
+            (_, Value::Reference(0)) if desc.starts_with(['L', '[']) => Some(v),
+            (_, Value::Reference(r)) if desc.starts_with(['L', '[']) => {
+                let target = heap::component_class_name(desc).unwrap_or(desc);
+                let Some(rv) = self.heap.get_ref(r) else {
+                    return Err(format!("reference is not valid: {r}"))?;
+                };
+                rv.is_instance_of(target, &mut self.meth_area)?.then_some(v)
+            }
+            _ => None,
+        };
+        Ok(converted.ok_or_else(|| format!("value {v:?} does not match the type {desc}"))?)
+    }
+
+    // convert the result of an invocation into the outcome. exceptions escaped from Java world are reported as Outcome::Threw
+    fn outcome_of(&mut self, res: VMResult<Option<Value>>) -> VMResult<Outcome> {
+        let err = match res {
+            Ok(Some(Value::Reference(r))) if r != 0 => {
+                return Ok(Outcome::ReturnedObject(JObject(self.heap.new_handle(r)?)))
+            }
+            Ok(Some(v)) => return Ok(Outcome::Returned(v)),
+            Ok(None) => return Ok(Outcome::ReturnedVoid),
+            Err(err) => err,
+        };
+        let mut exc = err.downcast::<JavaException>()?;
+        let Ok(r) = exception::materialize(
+            &mut exc,
+            &mut self.thread,
+            &mut self.meth_area,
+            &mut self.heap,
+        ) else {
+            return Err(exc)?;
+        };
+        Ok(Outcome::Threw(
+            JObject(self.heap.new_handle(r)?),
+            exc.stack_trace,
+        ))
+    }
+
+    /// Gets the value of the static field, initializing the class if necessary.
+    pub fn get_static_field<T: JavaType>(&mut self, class_name: &str, name: &str) -> VMResult<T> {
+        let (desc, f) = self.static_field(class_name, name)?;
+        if !T::accepts(&desc) {
+            return Err(format!(
+                "field {name} of type {desc} can't be read as {}",
+                T::descriptor()
+            ))?;
+        }
+        T::from_value(f.get(), self)
+    }
+
+    /// Sets the value to the static field, initializing the class if necessary.
+    pub fn set_static_field<T: JavaType>(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        v: T,
+    ) -> VMResult<()> {
+        let (desc, f) = self.static_field(class_name, name)?;
+        let v = v.into_value(self)?;
+        f.put(self.value_of_type(&desc, v)?);
+        Ok(())
+    }
+
+    // descriptor and the storage of the static field
+    fn static_field(&mut self, class_name: &str, name: &str) -> VMResult<(String, Rc<MutValue>)> {
+        let cls = self.meth_area.resolve_class(class_name)?;
+        cls.initialize(&mut self.thread, &mut self.meth_area, &mut self.heap)?;
+
+        let (decl_cls, f) = self.meth_area.resolve_static_field(class_name, name)?;
+        let Some(desc) = decl_cls.static_field_descriptor(name) else {
+            unreachable!()
+        };
+        Ok((desc.to_string(), f))
+    }
+
+    /// Creates a `java.lang.String` object.
+    pub fn new_string(&mut self, s: &str) -> VMResult<JString> {
+        let Value::Reference(r) = self.heap.alloc_string(s, &mut self.meth_area)? else {
+            unreachable!()
+        };
+        Ok(JString(self.heap.new_handle(r)?))
+    }
+
+    /// Creates an array whose elements are of the type `T`, filled with default values.
+    pub fn new_array<T: JavaType>(&mut self, len: usize) -> VMResult<JArray<T>> {
+        // too long arrays are rejected by the heap
+        let len = i32::try_from(len).unwrap_or(i32::MAX);
+        let Value::Reference(r) = self.heap.alloc_array(len, &T::descriptor())? else {
+            unreachable!()
+        };
+        Ok(JArray::new(self.heap.new_handle(r)?))
+    }
+
+    /// Calls the static method with typed arguments, and converts its return value to the Rust type.
+    ///
+    /// The method to call is chosen from the overloads by the types of arguments and return value
+    /// (e.g. `call_static::<(i32, i64), bool>` calls the method whose descriptor is `(IJ)Z`).
+    /// It fails without executing anything if there is no method compatible with the types.
+    pub fn call_static<A, R>(&mut self, class_name: &str, method_name: &str, args: A) -> VMResult<R>
+    where
+        A: JavaArgs,
+        R: JavaReturn,
+    {
+        let meth = self.find_static_method::<A, R>(class_name, method_name)?;
+        let arg_vals = args.into_values(self)?;
+        match self.invoke_static(&meth, &arg_vals)? {
+            Outcome::Returned(v) => R::from_return_value(Some(v), self),
+            Outcome::ReturnedObject(obj) => {
+                R::from_return_value(Some(Value::Reference(obj.r())), self)
+            }
+            Outcome::ReturnedVoid => R::from_return_value(None, self),
+            Outcome::Threw(exc, stack_trace) => {
+                let mut exc = exception::from_object(&mut self.heap, exc.r())?;
+                exc.stack_trace = stack_trace;
+                Err(exc)?
+            }
+        }
+    }
+
+    /// Registers the closure as the implementation of the native method declared in the class.
+    ///
+    /// Arguments of the method are converted to `A` (preceded by the receiver for instance methods, e.g. `(JObject, i32)`),
+    /// and the value returned from the closure is converted to the return type of the method.
+    /// The closure can call back into Java via the `VM` passed to it, but must not reset it.
+    /// [`JavaException`]s returned from the closure are thrown in Java world.
+    pub fn register_native<A, R, F>(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        method_desc: &str,
+        f: F,
+    ) -> VMResult<()>
+    where
+        A: JavaArgs,
+        R: JavaReturn,
+        F: Fn(&mut VM, A) -> VMResult<R> + 'static,
+    {
+        let sig = MethodSignature::new_with_raw_descriptor(method_name, method_desc);
+        let meth_name = method_area::method_name(class_name, &sig);
+        let cls = self.meth_area.resolve_class(class_name)?;
+        let Some(meth) = cls.lookup_method(&sig) else {
+            return Err(format!("method {meth_name} not found"))?;
+        };
+        if !matches!(meth.code_spec, MethodCodeSpec::Native) {
+            return Err(format!("method {meth_name} is not native"))?;
+        }
+
+        let mut params = sig.descriptor.param_types();
+        let this = format!("L{class_name};");
+        if !meth.access_flags.is_static() {
+            params.insert(0, &this);
+        }
+        if !A::accepts(&params) || !R::accepts(sig.descriptor.return_type()) {
+            return Err(format!(
+                "closure of ({}){} is not compatible with {meth_name}",
+                A::descriptors().concat(),
+                R::descriptor()
+            ))?;
+        }
+
+        let native: NativeFn = Rc::new(move |env: &mut NativeEnv, args: &[Value]| {
+            VM::with_native_env(env, |vm| {
+                let args = A::from_values(args, vm)?;
+                f(vm, args)?.into_return_value(vm)
+            })
+        });
+        self.meth_area
+            .natives_mut()
+            .register(class_name, method_name, method_desc, native);
+        Ok(())
+    }
+
+    /// Loads the native library at the path (like `System.load`), and calls `JNI_OnLoad` in it if exists.
+    ///
+    /// Native methods are linked to `Java_<class>_<method>` functions exported from loaded libraries,
+    /// or to functions registered by `RegisterNatives`.
+    pub fn load_library<P: AsRef<Path>>(&mut self, path: P) -> VMResult<()> {
+        let mut env = NativeEnv {
+            thread: &mut self.thread,
+            meth_area: &mut self.meth_area,
+            heap: &mut self.heap,
+        };
+        jni::load_library(&mut env, path.as_ref())
+    }
+
+    // lend the runtime data areas in the native environment to a VM while `f` runs.
+    // they are given back by the guard even if `f` panics, so the environment is never left with the placeholders
+    fn with_native_env<T>(env: &mut NativeEnv, f: impl FnOnce(&mut VM) -> T) -> T {
+        struct Lent<'a, 'b> {
+            env: &'a mut NativeEnv<'b>,
+            vm: VM,
+        }
+        impl Drop for Lent<'_, '_> {
+            fn drop(&mut self) {
+                mem::swap(self.env.thread, &mut self.vm.thread);
+                mem::swap(self.env.meth_area, &mut self.vm.meth_area);
+                mem::swap(self.env.heap, &mut self.vm.heap);
+            }
+        }
+
+        let config = env.meth_area.config().clone();
+        let mut vm = VM {
+            thread: Thread::new(),
+            config: config.clone(),
+            meth_area: MethodArea::placeholder(config),
+            heap: Heap::placeholder(),
+        };
+        mem::swap(env.thread, &mut vm.thread);
+        mem::swap(env.meth_area, &mut vm.meth_area);
+        mem::swap(env.heap, &mut vm.heap);
+        let mut lent = Lent { env, vm };
+        f(&mut lent.vm)
+    }
+
+    // find the static method compatible with the types of args and return value, from the class and its superclasses
+    fn find_static_method<A, R>(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+    ) -> VMResult<MethodRef>
+    where
+        A: JavaArgs,
+        R: JavaReturn,
+    {
+        let mut candidates = Vec::new();
+        let mut compatibles = Vec::new();
+
+        let mut next_cls = Some(class_name.to_string());
+        while let Some(cls_name) = next_cls {
+            let cls = self.meth_area.resolve_class(&cls_name)?;
+            for m in cls.static_methods_named(method_name) {
+                let desc = &m.signature.descriptor;
+                if A::accepts(&desc.param_types()) && R::accepts(desc.return_type()) {
+                    compatibles.push(MethodRef {
+                        class: cls.clone(),
+                        method: m.clone(),
+                    });
+                }
+                candidates.push(desc.to_string());
+            }
+            // methods in a subclass hide ones in superclasses
+            if !compatibles.is_empty() {
+                break;
+            }
+            next_cls = cls.super_class.clone();
+        }
+
+        let expected = format!("({}){}", A::descriptors().concat(), R::descriptor());
+        match compatibles.len() {
+            0 if candidates.is_empty() => Err(format!(
+                "static method {class_name}.{method_name} not found"
+            ))?,
+            0 => {
+                candidates.sort();
+                Err(format!(
+                    "static method {class_name}.{method_name} is not compatible with {expected} (candidates: {})",
+                    candidates.join(", ")
+                ))?
+            }
+            1 => Ok(compatibles.remove(0)),
+            _ => Err(format!(
+                "call to static method {class_name}.{method_name} with {expected} is ambiguous"
+            ))?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::{Cell, RefCell},
+        collections::HashMap,
+        path::PathBuf,
+        process::Command,
+    };
+
+    use super::*;
+
+    // tests running Java code need the class files of java.base (e.g. extracted by `jimage extract`) in the directory
+    const ENV_KEY_JDK_CLASSES: &str = "KAFA_TEST_JDK_CLASSES";
+    // native libraries for tests are built with jni.h of the JDK
+    const ENV_KEY_JAVA_HOME: &str = "JAVA_HOME";
+
+    fn test_vm(builder: VMBuilder) -> VM {
+        let Some(jdk) = std::env::var_os(ENV_KEY_JDK_CLASSES) else {
+            panic!("{ENV_KEY_JDK_CLASSES} is not set");
+        };
+        builder.boot_classpath([jdk]).classpath(["classes"]).build()
+    }
+
+    // build the library of JniSample from classes/jni/jnisample.c, and return the directory containing it
+    fn build_jni_sample() -> PathBuf {
+        let Some(java_home) = std::env::var_os(ENV_KEY_JAVA_HOME) else {
+            panic!("{ENV_KEY_JAVA_HOME} is not set");
+        };
+        let include = Path::new(&java_home).join("include");
+        let out_dir = std::env::temp_dir().join(format!("kafa-test-{}", std::process::id()));
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let status = Command::new("cc")
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(out_dir.join("libjnisample.so"))
+            .arg("-I")
+            .arg(&include)
+            .arg("-I")
+            .arg(include.join("linux"))
+            .arg("classes/jni/jnisample.c")
+            .status()
+            .expect("failed to run cc");
+        assert!(status.success(), "failed to build libjnisample.so");
+        out_dir
+    }
+
+    #[test]
+    fn test_handles_after_reset() {
+        let mut vm = VMBuilder::new().build();
+        let old = JObject::from(vm.new_array::<i32>(3).unwrap());
+        vm.reset();
+
+        // the new array is placed at the slot of the old one, but the handle of the old one is detected as stale
+        let new = JObject::from(vm.new_array::<i32>(3).unwrap());
+        assert_ne!(old.r(), new.r());
+        assert!(old.class_name(&vm).is_err());
+        assert_eq!(new.class_name(&vm).unwrap(), "[I");
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_exception_thrown_by_vm() {
+        let mut vm = test_vm(VMBuilder::new());
+        let res = vm.call_static::<_, i32>("OutcomeSample", "initCause", (1,));
+        assert_eq!(res.unwrap(), 1);
+    }
+
+    // sink of the output of the VM, whose contents can be taken by the test
+    #[derive(Clone, Default)]
+    struct Sink(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Sink {
+        fn take_string(&self) -> String {
+            String::from_utf8(self.0.take()).unwrap()
+        }
+    }
+
+    // class name and message of the exception thrown by the invocation
+    fn thrown(vm: &mut VM, res: VMResult<Outcome>) -> (String, Option<String>) {
+        let Ok(Outcome::Threw(exc, _)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        let exc = exception::from_object(&mut vm.heap, exc.r()).unwrap();
+        (exc.class_name, exc.message)
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_execute() {
+        let mut vm = test_vm(VMBuilder::new());
+        let mut exec = |cls: &str, meth: &str, desc: &str| match vm.execute(cls, meth, desc, &[]) {
+            Ok(Outcome::Returned(v)) => v,
+            res => panic!("unexpected outcome: {res:?}"),
+        };
+        assert_eq!(exec("MakeJVM", "start", "()I"), Value::Int(55));
+        assert_eq!(exec("MakeJVM", "start2", "()I"), Value::Int(110));
+        assert_eq!(exec("MakeJVM", "start3", "()Z"), Value::Int(1));
+        assert_eq!(
+            exec("StaticFieldsSample", "start", "()I"),
+            Value::Int(425276)
+        );
+        // loader.Adder is loaded while the method runs
+        assert_eq!(
+            exec("loader/RuntimeClassLoadingSample", "start", "()I"),
+            Value::Int(579)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_state_across_executions() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "StatefulSample";
+        assert_eq!(vm.call_static::<_, i32>(cls, "increment", ()).unwrap(), 1);
+        assert_eq!(vm.call_static::<_, i32>(cls, "increment", ()).unwrap(), 2);
+
+        // static fields are initialized again after reset
+        vm.reset();
+        assert_eq!(vm.call_static::<_, i32>(cls, "increment", ()).unwrap(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_outcomes() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "OutcomeSample";
+        let res = vm.execute(
+            cls,
+            "main",
+            "([Ljava/lang/String;)V",
+            &[Value::Reference(0)],
+        );
+        assert!(matches!(res, Ok(Outcome::ReturnedVoid)), "{res:?}");
+        assert_eq!(vm.call_static::<_, i32>(cls, "counter", ()).unwrap(), 1);
+
+        // long and double arguments occupy two slots, and booleans and chars are passed as they are
+        let args = [
+            Value::Long(2),
+            Value::Int(3),
+            Value::Double(0.5),
+            Value::Int(1),
+            Value::Char(1),
+        ];
+        let res = vm.execute(cls, "mix", "(JIDZC)D", &args);
+        assert!(
+            matches!(res, Ok(Outcome::Returned(Value::Double(d))) if d == 7.5),
+            "{res:?}"
+        );
+
+        let res = vm.execute(cls, "divide", "(II)I", &[Value::Int(1), Value::Int(0)]);
+        let Ok(Outcome::Threw(_, stack_trace)) = &res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        assert_eq!(
+            stack_trace,
+            &[
+                "OutcomeSample.helper:(II)I (pc: 2)",
+                "OutcomeSample.divide:(II)I (pc: 2)"
+            ]
+        );
+        assert_eq!(
+            thrown(&mut vm, res),
+            (
+                "java/lang/ArithmeticException".to_string(),
+                Some("/ by zero".to_string())
+            )
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_native_methods() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "NativeSample";
+        // System.arraycopy, including an overlapping copy and a partial copy before ArrayStoreException
+        assert_eq!(vm.call_static::<_, i32>(cls, "start", ()).unwrap(), 2405);
+        assert_eq!(vm.call_static::<_, i32>(cls, "arrayStore", ()).unwrap(), 2);
+
+        // natives without implementations are reported with the symbols looked up
+        let res = vm.execute(cls, "callMissing", "()I", &[]);
+        assert_eq!(
+            thrown(&mut vm, res),
+            (
+                "java/lang/UnsatisfiedLinkError".to_string(),
+                Some("'NativeSample.missing(I)I' (symbol: Java_NativeSample_missing)".to_string())
+            )
+        );
+        let res = vm.execute(cls, "callOverloaded", "()I", &[]);
+        assert_eq!(
+            thrown(&mut vm, res),
+            (
+                "java/lang/UnsatisfiedLinkError".to_string(),
+                Some("'NativeSample.overloaded(Ljava/lang/String;)I' (symbol: Java_NativeSample_overloaded__Ljava_lang_String_2)".to_string())
+            )
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_host_objects() {
+        let mut vm = test_vm(VMBuilder::new());
+        let res = vm.new_instance("plugin/Scaler", "(I)V", &[Value::Int(3)]);
+        let Ok(Outcome::ReturnedObject(scaler)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        let int_of = |res: VMResult<Outcome>| match res {
+            Ok(Outcome::Returned(Value::Int(n))) => n,
+            res => panic!("unexpected outcome: {res:?}"),
+        };
+        let iface = "plugin/Plugin";
+        let res = vm.invoke_interface(&scaler, iface, "apply", "(I)I", &[Value::Int(5)]);
+        assert_eq!(int_of(res), 15);
+        // default method of the interface
+        let res = vm.invoke_interface(&scaler, iface, "applyTwice", "(I)I", &[Value::Int(5)]);
+        assert_eq!(int_of(res), 45);
+        let res = vm.invoke_virtual(&scaler, "calls", "()I", &[]);
+        assert_eq!(int_of(res), 3);
+
+        let res = vm.invoke_virtual(&scaler, "apply", "(Z)I", &[Value::Int(1)]);
+        assert_eq!(
+            thrown(&mut vm, res),
+            (
+                "java/lang/NoSuchMethodError".to_string(),
+                Some("plugin.Scaler.apply(Z)I".to_string())
+            )
+        );
+        // exceptions thrown by constructors and instantiation of abstract classes
+        let res = vm.new_instance("plugin/Scaler", "(II)V", &[Value::Int(1), Value::Int(0)]);
+        assert_eq!(
+            thrown(&mut vm, res).0,
+            "java/lang/ArithmeticException".to_string()
+        );
+        let res = vm.new_instance("plugin/AbstractPlugin", "()V", &[]);
+        assert_eq!(
+            thrown(&mut vm, res),
+            (
+                "java/lang/InstantiationError".to_string(),
+                Some("plugin.AbstractPlugin".to_string())
+            )
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_typed_calls() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "TypedCallSample";
+        assert!(vm
+            .call_static::<_, bool>(cls, "inRange", (3, 10i64))
+            .unwrap());
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "length", ("kafa".to_string(),))
+                .unwrap(),
+            4
+        );
+        assert_eq!(
+            vm.call_static::<_, String>(cls, "nameOrDefault", (Some(String::new()),))
+                .unwrap(),
+            "nobody"
+        );
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "sum", (vec![1, 2, 3, 4],))
+                .unwrap(),
+            10
+        );
+        assert_eq!(
+            vm.call_static::<_, Vec<f64>>(cls, "scale", (vec![1.0, 2.5], 2.0))
+                .unwrap(),
+            [2.0, 5.0]
+        );
+        assert_eq!(
+            vm.call_static::<_, Vec<String>>(cls, "split", ("ab".to_string(),))
+                .unwrap(),
+            ["ab", "ab"]
+        );
+        // the overload is chosen by the types of the arguments
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "overloaded", (21i64,))
+                .unwrap(),
+            42
+        );
+        assert_eq!(
+            vm.call_static::<_, Option<String>>(cls, "nullName", ())
+                .unwrap(),
+            None
+        );
+
+        // arguments and return values of incompatible types are rejected before execution
+        let err = vm
+            .call_static::<_, i32>(cls, "overloaded", (1.0f32,))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "static method TypedCallSample.overloaded is not compatible with (F)I (candidates: (I)I, (J)I)"
+        );
+        let err = vm
+            .call_static::<_, String>(cls, "length", ("kafa".to_string(),))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "static method TypedCallSample.length is not compatible with (Ljava/lang/String;)Ljava/lang/String; (candidates: (Ljava/lang/String;)I)"
+        );
+        let err = vm
+            .execute(cls, "overloaded", "(I)I", &[Value::Long(1)])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "value Long(1) does not match the type I");
+        // null is converted only to Option
+        let err = vm
+            .call_static::<_, String>(cls, "nullName", ())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "unexpected null reference");
+        // null passed from the host is dereferenced in Java
+        let err = vm
+            .call_static::<_, String>(cls, "nameOrDefault", (None::<String>,))
+            .unwrap_err();
+        let exc = err.downcast::<JavaException>().unwrap();
+        assert_eq!(exc.class_name, "java/lang/NullPointerException");
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_handles() {
+        // survivors of the nursery are moved by GC
+        let collector = Collector::Generational {
+            nursery_size: 64 * 1024,
+        };
+        let mut vm = test_vm(VMBuilder::new().collector(collector));
+        let cls = "HandleSample";
+        let res = vm.new_instance(cls, "(I)V", &[Value::Int(3)]);
+        let Ok(Outcome::ReturnedObject(obj)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        obj.set_field(&mut vm, "y", 100i64).unwrap();
+        obj.set_field(&mut vm, "name", "kafa".to_string()).unwrap();
+        let data = vm.new_array::<i32>(4).unwrap();
+        data.as_mut_slice(&mut vm)
+            .unwrap()
+            .copy_from_slice(&[1, 2, 3, 4]);
+        obj.set_field(&mut vm, "data", data).unwrap();
+        let ws = vm
+            .call_static::<_, JArray<f64>>(cls, "weights", (4,))
+            .unwrap();
+
+        // the handles follow the objects moved by GC
+        let before = (obj.r(), ws.r());
+        vm.gc();
+        assert_ne!(obj.r(), before.0, "the object is not moved");
+        assert_ne!(ws.r(), before.1, "the array is not moved");
+        assert_eq!(obj.get_field::<i32>(&mut vm, "x").unwrap(), 3);
+        assert_eq!(obj.get_field::<i64>(&mut vm, "y").unwrap(), 100);
+        assert_eq!(obj.get_field::<String>(&mut vm, "name").unwrap(), "kafa");
+        let data = obj.get_field::<JArray<i32>>(&mut vm, "data").unwrap();
+        assert_eq!(data.to_vec(&mut vm).unwrap(), [1, 2, 3, 4]);
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "sumData", (obj,)).unwrap(),
+            30
+        );
+        for w in ws.as_mut_slice(&mut vm).unwrap() {
+            *w *= 2.0;
+        }
+        assert_eq!(ws.as_slice(&vm).unwrap(), [0.0, 1.0, 2.0, 3.0]);
+
+        // static fields, and arrays of strings
+        assert_eq!(vm.get_static_field::<i32>(cls, "counter").unwrap(), 10);
+        vm.set_static_field(cls, "label", "updated".to_string())
+            .unwrap();
+        let names = vm
+            .call_static::<_, JArray<JString>>(cls, "names", ())
+            .unwrap();
+        let names = (0..names.len(&vm).unwrap())
+            .map(|i| names.get(&mut vm, i).unwrap().to_string(&mut vm).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["foo", "updated"]);
+
+        // fields hidden by subclasses, and values of mismatched types
+        let res = vm.new_instance("HandleSample$Sub", "()V", &[]);
+        let Ok(Outcome::ReturnedObject(sub)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        assert_eq!(sub.class_name(&vm).unwrap(), "HandleSample$Sub");
+        assert_eq!(sub.get_field::<i32>(&mut vm, "x").unwrap(), 2);
+        let err = sub.set_field(&mut vm, "x", 1.5f64).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value Double(1.5) does not match the type I"
+        );
+
+        // objects are freed once their handles are dropped
+        let r = ws.r();
+        drop(ws);
+        vm.gc();
+        assert!(vm.heap.new_handle(r).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_builder_options() {
+        let stdout = Sink::default();
+        let mut vm = test_vm(
+            VMBuilder::new()
+                .system_property("app.name", "kafa")
+                .max_frame_depth(64)
+                .verbose(Category::ClassLoading)
+                .stdout(stdout.clone()),
+        );
+        let cls = "OptionsSample";
+        // "kafa".length() * 100 + "default".length()
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "propertyLength", ()).unwrap(),
+            407
+        );
+        // maxDepth() and recurse() itself take 2 frames
+        assert_eq!(vm.call_static::<_, i32>(cls, "maxDepth", ()).unwrap(), 62);
+        // diagnostic messages go to the stdout sink unless the diagnostic sink is set
+        let written = stdout.take_string();
+        assert!(
+            written.contains("[info][class] loaded OptionsSample from classes\n"),
+            "{written}"
+        );
+
+        // messages only of the enabled categories are reported, and nothing is written to the stdout
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let r = reported.clone();
+        let stdout = Sink::default();
+        let mut vm = test_vm(
+            VMBuilder::new()
+                .system_property("app.name", "kafa")
+                .verbose(Category::Init)
+                .diagnostic_sink(move |_, category, msg: &str| {
+                    r.borrow_mut().push(format!("{category} {msg}"))
+                })
+                .stdout(stdout.clone()),
+        );
+        vm.call_static::<_, i32>(cls, "propertyLength", ()).unwrap();
+        assert!(reported
+            .borrow()
+            .contains(&"init initializing OptionsSample".to_string()));
+        assert!(reported.borrow().iter().all(|m| m.starts_with("init ")));
+        assert!(stdout.take_string().is_empty());
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_out_of_memory() {
+        let mut vm = test_vm(VMBuilder::new().max_heap_size(16 * 1024 * 1024));
+        let cls = "OomSample";
+        let mut error_of = |n: i32| {
+            let err = vm
+                .call_static::<_, i32>(cls, "longArray", (n,))
+                .unwrap_err();
+            let exc = err.downcast::<JavaException>().unwrap();
+            (exc.class_name, exc.message.unwrap_or_default())
+        };
+        // rejected before allocating anything on the host
+        assert_eq!(
+            error_of(i32::MAX),
+            (
+                "java/lang/OutOfMemoryError".to_string(),
+                "Requested array size exceeds VM limit".to_string()
+            )
+        );
+        // 32 MiB exceeds the limit of the heap
+        assert_eq!(
+            error_of(4 * 1024 * 1024),
+            (
+                "java/lang/OutOfMemoryError".to_string(),
+                "Java heap space".to_string()
+            )
+        );
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "longArray", (1024,)).unwrap(),
+            1024
+        );
+
+        let mut call = |meth: &str| vm.call_static::<_, i32>(cls, meth, ()).unwrap();
+        assert_eq!(call("hugeArray"), -1);
+        // the arrays held until the heap is exhausted are freed after OutOfMemoryError is caught
+        let exhausted = call("exhaust");
+        assert!((1..16).contains(&exhausted), "{exhausted}");
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "churn", (64,)).unwrap(),
+            64 * 1024
+        );
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "negativeArray", (-1,))
+                .unwrap(),
+            -2
+        );
+    }
+
+    // summary of a heap dump in the HPROF format, read independently of the writer
+    #[derive(Default)]
+    struct HprofSummary {
+        instances: HashMap<String, usize>,
+        // (basic type, length) of arrays of primitives
+        prim_arrays: Vec<(u8, usize)>,
+        roots: usize,
+    }
+
+    fn parse_hprof(dump: &[u8]) -> HprofSummary {
+        struct Reader<'a>(&'a [u8]);
+
+        impl<'a> Reader<'a> {
+            fn take(&mut self, n: usize) -> &'a [u8] {
+                let (head, tail) = self.0.split_at(n);
+                self.0 = tail;
+                head
+            }
+            fn u1(&mut self) -> u8 {
+                self.take(1)[0]
+            }
+            fn u2(&mut self) -> usize {
+                u16::from_be_bytes(self.take(2).try_into().unwrap()) as usize
+            }
+            fn u4(&mut self) -> usize {
+                u32::from_be_bytes(self.take(4).try_into().unwrap()) as usize
+            }
+            fn id(&mut self) -> u64 {
+                u64::from_be_bytes(self.take(8).try_into().unwrap())
+            }
+            // skip the value of the basic type (2: object, 4: boolean, 5: char, 6: float, 7: double, 8: byte, 9: short, 10: int, 11: long)
+            fn skip_value(&mut self, ty: u8) {
+                let size = match ty {
+                    4 | 8 => 1,
+                    5 | 9 => 2,
+                    6 | 10 => 4,
+                    2 | 7 | 11 => 8,
+                    _ => panic!("unknown basic type: {ty}"),
+                };
+                self.take(size);
+            }
+        }
+
+        let mut r = Reader(dump);
+        assert_eq!(r.take(19), b"JAVA PROFILE 1.0.2\0");
+        assert_eq!(r.u4(), 8, "size of ids");
+        r.take(8);
+
+        let mut names = HashMap::new();
+        let mut class_names = HashMap::new();
+        let mut summary = HprofSummary::default();
+        let mut ended = false;
+        while !r.0.is_empty() {
+            assert!(!ended, "records after HEAP DUMP END");
+            let tag = r.u1();
+            r.u4();
+            let len = r.u4();
+            let mut body = Reader(r.take(len));
+            match tag {
+                // UTF8
+                0x01 => {
+                    let id = body.id();
+                    names.insert(id, String::from_utf8(body.0.to_vec()).unwrap());
+                }
+                // LOAD CLASS
+                0x02 => {
+                    body.u4();
+                    let cls = body.id();
+                    body.u4();
+                    class_names.insert(cls, names[&body.id()].clone());
+                }
+                // HEAP DUMP SEGMENT
+                0x1C => {
+                    while !body.0.is_empty() {
+                        match body.u1() {
+                            // ROOT UNKNOWN, ROOT STICKY CLASS
+                            0xFF | 0x05 => _ = body.id(),
+                            // ROOT JNI GLOBAL
+                            0x01 => _ = body.take(16),
+                            // ROOT JAVA FRAME
+                            0x03 => _ = body.take(16),
+                            // ROOT NATIVE STACK
+                            0x04 => _ = body.take(12),
+                            // CLASS DUMP
+                            0x20 => {
+                                assert!(class_names.contains_key(&body.id()));
+                                body.take(4 + 8 * 6 + 4);
+                                for _ in 0..body.u2() {
+                                    body.u2();
+                                    let ty = body.u1();
+                                    body.skip_value(ty);
+                                }
+                                for _ in 0..body.u2() {
+                                    body.id();
+                                    let ty = body.u1();
+                                    body.skip_value(ty);
+                                }
+                                for _ in 0..body.u2() {
+                                    body.take(9);
+                                }
+                                continue;
+                            }
+                            // INSTANCE DUMP
+                            0x21 => {
+                                body.take(12);
+                                let cls = &class_names[&body.id()];
+                                let len = body.u4();
+                                body.take(len);
+                                *summary.instances.entry(cls.clone()).or_default() += 1;
+                                continue;
+                            }
+                            // OBJECT ARRAY DUMP
+                            0x22 => {
+                                body.take(12);
+                                let len = body.u4();
+                                assert!(class_names.contains_key(&body.id()));
+                                body.take(len * 8);
+                                continue;
+                            }
+                            // PRIMITIVE ARRAY DUMP
+                            0x23 => {
+                                body.take(12);
+                                let len = body.u4();
+                                let ty = body.u1();
+                                (0..len).for_each(|_| body.skip_value(ty));
+                                summary.prim_arrays.push((ty, len));
+                                continue;
+                            }
+                            sub => panic!("unknown sub-record: {sub:#x}"),
+                        }
+                        summary.roots += 1;
+                    }
+                }
+                // HEAP DUMP END
+                0x2C => ended = true,
+                _ => {}
+            }
+        }
+        assert!(ended, "no HEAP DUMP END");
+        summary
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_heap_dump() {
+        let mut vm = test_vm(VMBuilder::new());
+        let res = vm.new_instance("HandleSample", "(I)V", &[Value::Int(3)]);
+        let Ok(Outcome::ReturnedObject(obj)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        obj.set_field(&mut vm, "name", "kafa".to_string()).unwrap();
+        let _longs = vm.new_array::<i64>(5).unwrap();
+
+        let mut dump = Vec::new();
+        vm.dump_heap(&mut dump).unwrap();
+        let summary = parse_hprof(&dump);
+        assert_eq!(summary.instances.get("HandleSample"), Some(&1));
+        assert!(summary.instances.get("java/lang/String") >= Some(&1));
+        assert!(summary.prim_arrays.contains(&(11, 5)));
+        // at least the objects held by the handles
+        assert!(summary.roots >= 2);
+
+        // the heap is dumped when OutOfMemoryError is thrown, with the arrays that exhausted it
+        let path = std::env::temp_dir().join(format!("kafa-test-{}.hprof", std::process::id()));
+        let mut vm = test_vm(
+            VMBuilder::new()
+                .max_heap_size(16 * 1024 * 1024)
+                .heap_dump_on_out_of_memory(&path),
+        );
+        let held = vm
+            .call_static::<_, i32>("OomSample", "exhaust", ())
+            .unwrap();
+        let dump = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let summary = parse_hprof(&dump);
+        let buffers = summary
+            .prim_arrays
+            .iter()
+            .filter(|&&(ty, len)| ty == 8 && len == 1024 * 1024)
+            .count();
+        assert_eq!(buffers, held as usize);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_heap_inspection() {
+        let mut vm = test_vm(VMBuilder::new());
+        let objs = (0..5)
+            .map(
+                |i| match vm.new_instance("HandleSample", "(I)V", &[Value::Int(i)]) {
+                    Ok(Outcome::ReturnedObject(obj)) => obj,
+                    res => panic!("unexpected outcome: {res:?}"),
+                },
+            )
+            .collect::<Vec<_>>();
+        objs[0]
+            .set_field(&mut vm, "name", "kafa".to_string())
+            .unwrap();
+        let longs = JObject::from(vm.new_array::<i64>(100).unwrap());
+        vm.gc();
+
+        let entries = vm
+            .heap_entries()
+            .map(|e| (e.reference, e))
+            .collect::<HashMap<_, _>>();
+        let histo = vm.class_histogram();
+        let entry = |name: &str| {
+            histo
+                .entries
+                .iter()
+                .find(|e| e.class_name == name)
+                .unwrap_or_else(|| panic!("no entry of {name}"))
+        };
+        let obj_size = entries[&objs[0].r()].shallow_size;
+        assert_eq!(entry("HandleSample").instances, 5);
+        assert_eq!(entry("HandleSample").bytes, 5 * obj_size);
+        let longs_size = entries[&longs.r()].shallow_size;
+        assert!(longs_size >= 100 * 8);
+        assert_eq!(entry("[J").instances, 1);
+        assert_eq!(entry("[J").bytes, longs_size);
+        assert_eq!(histo.total_instances(), entries.len());
+        assert_eq!(
+            histo.total_bytes(),
+            entries.values().map(|e| e.shallow_size).sum::<usize>()
+        );
+
+        // the name is only reachable through the object, which retains it
+        let name = entries[&objs[0].r()].references[0];
+        assert_eq!(entries[&name].class_name, "java/lang/String");
+        let tree = vm.dominator_tree();
+        assert_eq!(tree.immediate_dominator(name), Some(objs[0].r()));
+        assert_eq!(tree.retained_size(objs[1].r()), Some(obj_size));
+        assert!(tree.retained_size(objs[0].r()) > Some(obj_size + entries[&name].shallow_size));
+        assert_eq!(tree.retained_size(longs.r()), Some(longs_size));
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_closure_natives() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "HostServiceSample";
+
+        // the closures capture the state of the host, and can call back into Java
+        let table = HashMap::from([("apple".to_string(), 100), ("banana".to_string(), 20)]);
+        let lookups = Rc::new(Cell::new(0));
+        let counter = lookups.clone();
+        vm.register_native(
+            cls,
+            "lookup",
+            "(Ljava/lang/String;)I",
+            move |vm, (key,): (Option<String>,)| {
+                counter.set(counter.get() + 1);
+                let Some(key) = key else {
+                    return Err(JavaException::null_pointer_exception("key is null"))?;
+                };
+                match table.get(&key) {
+                    Some(&v) => Ok(v),
+                    None => vm.call_static::<_, i32>(cls, "fallback", (key,)),
+                }
+            },
+        )
+        .unwrap();
+        vm.register_native(cls, "weigh", "(I)I", |vm, (this, x): (JObject, i32)| {
+            let base = this.get_field::<i32>(vm, "base")?;
+            Ok(base * x)
+        })
+        .unwrap();
+
+        // 100 + 20 + "unknown".length()
+        assert_eq!(vm.call_static::<_, i32>(cls, "total", ()).unwrap(), 127);
+        assert_eq!(vm.call_static::<_, i32>(cls, "weighed", (4,)).unwrap(), 40);
+        // the exception returned by the closure is caught in Java
+        assert_eq!(vm.call_static::<_, i32>(cls, "lookupNull", ()).unwrap(), -1);
+        let err = vm
+            .call_static::<_, i32>(cls, "lookup", (None::<String>,))
+            .unwrap_err();
+        let exc = err.downcast::<JavaException>().unwrap();
+        assert_eq!(exc.class_name, "java/lang/NullPointerException");
+        assert_eq!(exc.message.as_deref(), Some("key is null"));
+        assert_eq!(lookups.get(), 5);
+
+        // the VM gets its state back even if the closure panics
+        vm.register_native(
+            cls,
+            "weigh",
+            "(I)I",
+            |_, _: (JObject, i32)| -> VMResult<i32> { panic!("weigh") },
+        )
+        .unwrap();
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            vm.call_static::<_, i32>(cls, "weighed", (4,))
+        }));
+        assert!(res.is_err());
+        assert_eq!(vm.call_static::<_, i32>(cls, "total", ()).unwrap(), 127);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_gc_while_running() {
+        for collector in [
+            Collector::MarkSweep,
+            Collector::Generational {
+                nursery_size: 256 * 1024,
+            },
+        ] {
+            let mut vm = test_vm(VMBuilder::new().collector(collector));
+            // garbage is collected while the method runs, and by System.gc(). the list kept alive survives
+            let res = vm.call_static::<_, i32>("GcSample", "churn", (100_000,));
+            assert_eq!(res.unwrap(), 104949);
+            assert!(vm.gc().freed_objects > 0);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_object_methods() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "ObjectSample";
+        let mut call = |meth: &str| vm.call_static::<_, i32>(cls, meth, ()).unwrap();
+        assert_eq!(call("sameClass"), 1);
+        assert_eq!(call("cloned"), 345);
+        assert_eq!(call("notCloneable"), 1);
+        assert_eq!(call("timedWait"), 1);
+        assert_eq!(call("cloneArrays"), 1912);
+        assert_eq!(call("arrayHash"), 1);
+        assert_eq!(
+            vm.call_static::<_, String>(cls, "className", ()).unwrap(),
+            "ObjectSample$Point"
+        );
+        assert_eq!(
+            vm.call_static::<_, String>(cls, "arrayClassName", ())
+                .unwrap(),
+            "[[Ljava.lang.String;"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_identity_hash_across_moves() {
+        // survivors of the nursery are moved by GC
+        let collector = Collector::Generational {
+            nursery_size: 64 * 1024,
+        };
+        let mut vm = test_vm(VMBuilder::new().collector(collector));
+        let cls = "ObjectSample";
+        assert_eq!(vm.call_static::<_, i32>(cls, "hashOnce", ()).unwrap(), 1);
+        vm.gc();
+        assert_eq!(vm.call_static::<_, i32>(cls, "sameHash", ()).unwrap(), 1);
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "identityMap", (100,))
+                .unwrap(),
+            100
+        );
+
+        let res = vm.new_instance("java/lang/Object", "()V", &[]);
+        let Ok(Outcome::ReturnedObject(obj)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        let hash_code = |vm: &mut VM| match vm.invoke_virtual(&obj, "hashCode", "()I", &[]) {
+            Ok(Outcome::Returned(Value::Int(h))) => h,
+            res => panic!("unexpected outcome: {res:?}"),
+        };
+        let (before, r) = (hash_code(&mut vm), obj.r());
+        vm.gc();
+        assert_ne!(obj.r(), r, "the object should be moved");
+        assert_eq!(hash_code(&mut vm), before);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_array_types() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "ArrayTypeSample";
+        let mut call = |meth: &str| vm.call_static::<_, i32>(cls, meth, ()).unwrap();
+        assert_eq!(call("store"), 1);
+        // all the checks that should hold hold, and the others do not
+        assert_eq!(call("instanceOf"), 0b1_1111_1111);
+        assert_eq!(call("cast"), 1);
+        assert_eq!(call("reflect"), 0b1_1111_1111);
+        assert_eq!(
+            vm.call_static::<_, String>(cls, "elementName", ()).unwrap(),
+            "ArrayTypeSample$Circle"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_class_initialization() {
+        let mut vm = test_vm(VMBuilder::new());
+        assert_eq!(
+            vm.call_static::<_, i32>("InitSample", "start", ()).unwrap(),
+            31
+        );
+        assert_eq!(
+            vm.get_static_field::<String>("InitSample", "GREETING")
+                .unwrap(),
+            "Hello, kafa!"
+        );
+
+        // <clinit> of InitSample$Faulty throws ArithmeticException
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "InitSample$Faulty";
+        let err = vm.get_static_field::<i32>(cls, "x").unwrap_err();
+        let exc = err.downcast::<JavaException>().unwrap();
+        assert_eq!(exc.class_name, "java/lang/ExceptionInInitializerError");
+        let cause = exc.cause.expect("no cause");
+        assert_eq!(cause.class_name, "java/lang/ArithmeticException");
+        assert_eq!(cause.message.as_deref(), Some("/ by zero"));
+
+        // the class is left in the erroneous state
+        let err = vm.get_static_field::<i32>(cls, "x").unwrap_err();
+        let exc = err.downcast::<JavaException>().unwrap();
+        assert_eq!(exc.class_name, "java/lang/NoClassDefFoundError");
+        assert_eq!(
+            exc.message.as_deref(),
+            Some("Could not initialize class InitSample$Faulty")
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_returned_object_survives_gc() {
+        // survivors of the nursery are moved by GC
+        let collector = Collector::Generational {
+            nursery_size: 64 * 1024,
+        };
+        let mut vm = test_vm(VMBuilder::new().collector(collector));
+        let meth = vm
+            .resolve_method("StatefulSample", "create", "(I)LStatefulSample;")
+            .unwrap();
+        let res = vm.invoke_static(&meth, &[Value::Int(42)]).unwrap();
+        let Outcome::ReturnedObject(obj) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        let before = obj.r();
+        vm.gc();
+        assert_ne!(obj.r(), before, "the object is not moved");
+
+        let arg = obj.into_value(&mut vm).unwrap();
+        let res = vm.execute("StatefulSample", "valueOf", "(LStatefulSample;)I", &[arg]);
+        assert!(matches!(res, Ok(Outcome::Returned(Value::Int(42)))));
+    }
+
+    // <clinit> of HeavyInitSample runs GC, which may free or move the holder passed to it
+    fn check_args_survive_class_initialization(collector: Collector) {
+        let mut vm = test_vm(VMBuilder::new().collector(collector));
+        let res = vm.new_instance("HeavyInitSample$Holder", "(I)V", &[Value::Int(42)]);
+        let Ok(Outcome::ReturnedObject(holder)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        let res = vm.call_static::<_, i32>("HeavyInitSample", "valueOf", (holder,));
+        assert_eq!(res.unwrap(), 42);
+
+        let mut vm = test_vm(VMBuilder::new().collector(collector));
+        let res = vm.new_instance("HeavyInitSample$Holder", "(I)V", &[Value::Int(42)]);
+        let Ok(Outcome::ReturnedObject(holder)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        let arg = holder.into_value(&mut vm).unwrap();
+        let res = vm.new_instance("HeavyInitSample", "(LHeavyInitSample$Holder;)V", &[arg]);
+        let Ok(Outcome::ReturnedObject(obj)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        let holder = obj.get_field::<JObject>(&mut vm, "holder").unwrap();
+        assert_eq!(holder.get_field::<i32>(&mut vm, "x").unwrap(), 42);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_args_survive_gc_in_class_initialization() {
+        check_args_survive_class_initialization(Collector::MarkSweep);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_args_survive_moving_gc_in_class_initialization() {
+        check_args_survive_class_initialization(Collector::Generational {
+            nursery_size: 64 * 1024,
+        });
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_new_instance_survives_gc() {
+        let collector = Collector::Generational {
+            nursery_size: 64 * 1024,
+        };
+        let mut vm = test_vm(VMBuilder::new().collector(collector));
+        let res = vm.new_instance("HandleSample", "(I)V", &[Value::Int(3)]);
+        let Ok(Outcome::ReturnedObject(obj)) = res else {
+            panic!("unexpected outcome: {res:?}");
+        };
+        vm.gc();
+        assert_eq!(obj.get_field::<i32>(&mut vm, "x").unwrap(), 3);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_aastore() {
+        let mut vm = test_vm(VMBuilder::new());
+        let mut store = |len: i32, idx: i32, is_string: bool| {
+            vm.call_static::<_, i32>("ArrayTypeSample", "storeElement", (len, idx, is_string))
+                .map_err(|e| e.downcast::<JavaException>().unwrap().class_name)
+        };
+        assert_eq!(store(2, 1, true), Ok(2));
+        assert_eq!(
+            store(-1, 0, true),
+            Err("java/lang/NullPointerException".to_string())
+        );
+        // the index is checked before the type of the value
+        for idx in [-1, 2] {
+            assert_eq!(
+                store(2, idx, false),
+                Err("java/lang/ArrayIndexOutOfBoundsException".to_string())
+            );
+        }
+        assert_eq!(
+            store(2, 1, false),
+            Err("java/lang/ArrayStoreException".to_string())
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_invoke_on_array() {
+        let mut vm = test_vm(VMBuilder::new());
+        let arr = JObject::from(vm.new_array::<i32>(3).unwrap());
+
+        // methods of java.lang.Object are invoked on arrays, like ones on objects
+        let res = vm.invoke_virtual(&arr, "hashCode", "()I", &[]);
+        assert!(
+            matches!(res, Ok(Outcome::Returned(Value::Int(_)))),
+            "{res:?}"
+        );
+        let arg = arr.clone().into_value(&mut vm).unwrap();
+        let res = vm.invoke_virtual(&arr, "equals", "(Ljava/lang/Object;)Z", &[arg]);
+        assert!(
+            matches!(res, Ok(Outcome::Returned(Value::Int(1)))),
+            "{res:?}"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_invoke_interface() {
+        let mut vm = test_vm(VMBuilder::new());
+        let s = JObject::from(vm.new_string("abc").unwrap());
+        let res = vm.invoke_interface(&s, "java/lang/CharSequence", "length", "()I", &[]);
+        assert!(
+            matches!(res, Ok(Outcome::Returned(Value::Int(3)))),
+            "{res:?}"
+        );
+
+        // the receiver is checked before the method is selected, so this is not an AbstractMethodError
+        let res = vm.invoke_interface(&s, "java/lang/Runnable", "run", "()V", &[]);
+        assert_eq!(
+            thrown(&mut vm, res),
+            (
+                "java/lang/IncompatibleClassChangeError".to_string(),
+                Some("Class java.lang.String does not implement the requested interface java.lang.Runnable".to_string())
+            )
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_references() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "RefSample";
+        vm.call_static::<_, ()>(cls, "setUp", ()).unwrap();
+        assert_eq!(vm.get_static_field::<i32>(cls, "cleaned").unwrap(), 0);
+        assert_eq!(vm.get_static_field::<i32>(cls, "finalized").unwrap(), 0);
+
+        let stats = vm.gc();
+        assert_eq!(stats.finalizable_objects, 1);
+
+        let res = vm.call_static::<_, i32>(cls, "check", ()).unwrap();
+        // weak and phantom references to unreachable objects are cleared, others are not
+        assert_eq!(res % 100, 0b1111);
+        // the cleared weak and phantom references are enqueued to the queue
+        assert_eq!(res / 100 % 10, 2);
+        // the action registered to the cleaner has run, and finalize() has been called
+        assert_eq!(vm.get_static_field::<i32>(cls, "cleaned").unwrap(), 1);
+        assert_eq!(vm.get_static_field::<i32>(cls, "finalized").unwrap(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_sealed_class() {
+        let mut vm = test_vm(VMBuilder::new());
+        assert_eq!(
+            vm.call_static::<_, i32>("linkage/PermittedShape", "sides", ())
+                .unwrap(),
+            4
+        );
+
+        // NotPermitted was compiled before SealedShape became sealed
+        let err = vm
+            .call_static::<_, i32>("linkage/NotPermitted", "sides", ())
+            .unwrap_err();
+        let exc = err.downcast::<JavaException>().unwrap();
+        assert_eq!(exc.class_name, "java/lang/IncompatibleClassChangeError");
+        assert_eq!(
+            exc.message.as_deref(),
+            Some("class linkage/NotPermitted cannot inherit from sealed class linkage/SealedShape")
+        );
+
+        // sealed types in java.base permit classes in other packages of the module (e.g. ConstantDesc permits String),
+        // even if the JDK classes are on the application classpath
+        assert!(vm.find_class("java/lang/String").is_ok());
+        let jdk = std::env::var_os(ENV_KEY_JDK_CLASSES).unwrap();
+        let mut app_vm = VMBuilder::new().classpath([jdk]).build();
+        assert!(app_vm.find_class("java/lang/String").is_ok());
+
+        // Outsider is permitted, but in a different package from SealedShape even though it is public
+        let err = vm
+            .call_static::<_, i32>("another/Outsider", "sides", ())
+            .unwrap_err();
+        let exc = err.downcast::<JavaException>().unwrap();
+        assert_eq!(exc.class_name, "java/lang/IncompatibleClassChangeError");
+        assert_eq!(
+            exc.message.as_deref(),
+            Some("class another/Outsider is in a different package from its sealed super type linkage/SealedShape")
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_broken_class_hierarchy() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "linkage/LinkageSample";
+        assert_eq!(vm.call_static::<_, i32>(cls, "start", ()).unwrap(), 7);
+
+        // classes in the hierarchy were recompiled after LinkageSample
+        let mut error_of = |meth: &str| {
+            let err = vm.call_static::<_, ()>(cls, meth, ()).unwrap_err();
+            let exc = err.downcast::<JavaException>().unwrap();
+            (exc.class_name, exc.message.unwrap_or_default())
+        };
+        assert_eq!(
+            error_of("newCircular"),
+            (
+                "java/lang/ClassCircularityError".to_string(),
+                "linkage/Circular1".to_string()
+            )
+        );
+        assert_eq!(
+            error_of("newExtendsInterface"),
+            (
+                "java/lang/IncompatibleClassChangeError".to_string(),
+                "class linkage/ExtendsInterface has interface linkage/NotAClass as super class"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            error_of("newImplementsClass"),
+            (
+                "java/lang/IncompatibleClassChangeError".to_string(),
+                "class linkage/ImplementsClass can not implement linkage/NotAnInterface, because it is not an interface"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_method_linkage_errors() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "linkage/MethodLinkageSample";
+        assert_eq!(vm.call_static::<_, i32>(cls, "start", ()).unwrap(), 63);
+
+        // classes declaring the methods were recompiled after MethodLinkageSample
+        let mut error_of = |meth: &str| {
+            let err = vm.call_static::<_, i32>(cls, meth, ()).unwrap_err();
+            let exc = err.downcast::<JavaException>().unwrap();
+            (exc.class_name, exc.message.unwrap_or_default())
+        };
+        assert_eq!(
+            error_of("greet"),
+            (
+                "java/lang/AbstractMethodError".to_string(),
+                "Receiver class linkage.GreeterImpl does not define or inherit an implementation of the resolved method linkage.Greeter.greet()I"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            error_of("conflictingDefaults"),
+            (
+                "java/lang/IncompatibleClassChangeError".to_string(),
+                "Conflicting default methods: linkage.DefaultA.m()I linkage.DefaultB.m()I"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            error_of("callStatic"),
+            (
+                "java/lang/IncompatibleClassChangeError".to_string(),
+                "Expected static method linkage.Mismatch.s()I".to_string()
+            )
+        );
+        assert_eq!(
+            error_of("callInstance"),
+            (
+                "java/lang/IncompatibleClassChangeError".to_string(),
+                "Expecting non-static method linkage.Mismatch.i()I".to_string()
+            )
+        );
+        // the message has the full descriptor of the method
+        assert_eq!(
+            error_of("callRemoved"),
+            (
+                "java/lang/NoSuchMethodError".to_string(),
+                "linkage.Removed.gone()I".to_string()
+            )
+        );
+        assert_eq!(
+            error_of("greetStranger"),
+            (
+                "java/lang/IncompatibleClassChangeError".to_string(),
+                "Class linkage.Stranger does not implement the requested interface linkage.Greeter"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_static_interface_method() {
+        let mut vm = test_vm(VMBuilder::new());
+        let res = vm.execute("InterfaceSample", "twice", "(I)I", &[Value::Int(21)]);
+        assert!(matches!(res, Ok(Outcome::Returned(Value::Int(42)))));
+        let res = vm.call_static::<_, i32>("InterfaceSample", "twice", (4,));
+        assert_eq!(res.unwrap(), 8);
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES"]
+    fn test_record_with_array_component() {
+        let mut vm = test_vm(VMBuilder::new());
+        let cls = "RecordSample";
+        assert_eq!(vm.call_static::<_, i32>(cls, "start", ()).unwrap(), 31);
+
+        // methods of java.lang.Object are invoked on the array component
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "taggedHashCode", ()).unwrap(),
+            1
+        );
+        let s = vm
+            .call_static::<_, String>(cls, "taggedToString", ())
+            .unwrap();
+        assert!(
+            s.starts_with("Tagged[xs=[I@") && s.ends_with(", s=a]"),
+            "unexpected string: {s}"
+        );
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "taggedEquals", ()).unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES, and JAVA_HOME to build the native library"]
+    fn test_jni_natives() {
+        let lib_dir = build_jni_sample();
+        let mut vm = test_vm(VMBuilder::new().library_path([lib_dir]));
+        let cls = "JniSample";
+
+        // arguments of various types, callbacks, local references and array elements
+        assert_eq!(vm.call_static::<_, i32>(cls, "start", ()).unwrap(), 247);
+        assert_eq!(
+            vm.call_static::<_, String>(cls, "greeting", ()).unwrap(),
+            "Hello, kafa!"
+        );
+        // registered by JNI_OnLoad
+        assert_eq!(vm.call_static::<_, f64>(cls, "averageOf", ()).unwrap(), 2.5);
+        // classes are passed as java.lang.Class objects
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "classObjects", ()).unwrap(),
+            0b111
+        );
+        // empty buffers are released independently
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "pinEmptyBuffers", ())
+                .unwrap(),
+            1
+        );
+
+        // exceptions thrown by native code are pending until it returns
+        assert_eq!(
+            vm.call_static::<_, i32>(cls, "catchFailure", ()).unwrap(),
+            4
+        );
+        let err = vm
+            .call_static::<_, ()>(cls, "fail", ("oops".to_string(),))
+            .unwrap_err();
+        let exc = err.downcast::<JavaException>().unwrap();
+        assert_eq!(exc.class_name, "java/lang/IllegalStateException");
+        assert_eq!(exc.message.as_deref(), Some("oops"));
+    }
+
+    #[test]
+    #[ignore = "needs the JDK classes in KAFA_TEST_JDK_CLASSES, and JAVA_HOME to build the native library"]
+    fn test_jni_exception_describe() {
+        let lib_dir = build_jni_sample();
+        let sink = Sink::default();
+        let mut vm = test_vm(
+            VMBuilder::new()
+                .library_path([lib_dir])
+                .stderr(sink.clone()),
+        );
 
-        let res = self.thread.current_frame().pop_operand();
-        self.thread.pop_frame();
-        Ok(res)
+        // the described exception is written to the sink of the VM and cleared
+        vm.call_static::<_, ()>("JniSample", "describe", ("oops".to_string(),))
+            .unwrap();
+        let written = sink.take_string();
+        assert!(written.starts_with("Exception in thread \"main\" "));
+        assert!(written.contains("IllegalStateException: oops"));
     }
 }
@@ -1,11 +1,17 @@
 use std::{cell::Cell, collections::HashMap, hash::Hash, rc::Rc};
 
 use crate::class_file::{
-    CPInfo, ClassAccessFlags, ConstantPool, FieldInfo, MethodAccessFlags, MethodComponents,
+    BootstrapMethod, CPInfo, ClassAccessFlags, ConstantPool, ExceptionTableEntry, FieldInfo,
+    MethodAccessFlags, MethodComponents,
 };
 
 use super::{
-    error::VMResult, heap::Heap, method_area::MethodArea, thread::Thread, value::MutValue,
+    diagnostics::{Category, Level},
+    error::{JavaException, VMResult},
+    heap::{component_class_name, Heap},
+    method_area::MethodArea,
+    thread::Thread,
+    value::{MutValue, Value},
 };
 
 pub struct Class {
@@ -18,11 +24,26 @@ pub struct Class {
     pub interfaces: Vec<String>,
 
     static_fields: HashMap<String, Rc<MutValue>>,
+    static_fields_info: Vec<FieldInfo>,
+    static_field_consts: Vec<(String, ConstantValue)>,
     static_methods: HashMap<MethodSignature, Rc<Method>>,
 
     inst_fields_info: Vec<FieldInfo>,
     inst_methods: HashMap<MethodSignature, Rc<Method>>,
 
+    bootstrap_methods: Vec<BootstrapMethod>,
+    permitted_subclasses: Option<Vec<String>>,
+    record_components: Option<Vec<RecordComponent>>,
+
+    // kind of java.lang.ref.Reference the class extends, if any. GC treats referents of its instances specially
+    pub reference_kind: Option<ReferenceKind>,
+    // whether instances have to be finalized before being freed (i.e. the class or its superclass overrides Object.finalize())
+    pub has_finalizer: bool,
+
+    // descriptor of the component type, if the class is an array class (e.g. "I" for "[I", "[Ljava/lang/String;" for "[[Ljava/lang/String;")
+    pub component_type: Option<String>,
+    pub defining_loader: DefiningLoader,
+
     init_state: Cell<ClassInitState>,
 }
 
@@ -31,16 +52,19 @@ impl Class {
         let rtcp = RunTimeConstantPool::from_class_file_cp(cls_file.constant_pool)?;
 
         let mut static_fields = HashMap::new();
+        let mut static_fields_info = Vec::new();
+        let mut static_field_consts = Vec::new();
         let mut inst_fields_info = Vec::new();
         for f in cls_file.fields.into_iter() {
             if f.access_flags.is_static() {
-                let fv = match f.get_const_val() {
-                    // TODO: strictly speaking, this should be done within "initialization" process described in JVM spec 5.5.
-                    Some(cp_info) => MutValue::from_cp_info(cp_info),
-                    None => MutValue::default_of_type(&f.descriptor),
-                };
-                let fv = Rc::new(fv);
-                static_fields.insert(f.name, fv);
+                // constant values are assigned to the fields during initialization (cf. JVM spec 5.5, step 6)
+                if let Some(cp_info) = f.get_const_val() {
+                    let cv = ConstantValue::from_cp_info(cp_info, &rtcp)?;
+                    static_field_consts.push((f.name.clone(), cv));
+                }
+                let fv = Rc::new(MutValue::default_of_type(&f.descriptor));
+                static_fields.insert(f.name.clone(), fv);
+                static_fields_info.push(f);
             } else {
                 inst_fields_info.push(f)
             }
@@ -71,6 +95,7 @@ impl Class {
                             max_stack: ca.max_stack,
                             max_locals: ca.max_locals,
                             code: ca.code,
+                            exception_table: ca.exception_table,
                         },
                         None => {
                             return Err("non-abstract & non-native methods must have Code attr")?
@@ -92,6 +117,16 @@ impl Class {
             }
         }
 
+        let record_components = cls_file.record_components.map(|comps| {
+            comps
+                .into_iter()
+                .map(|c| RecordComponent {
+                    name: c.name,
+                    descriptor: FieldDescriptor(c.descriptor),
+                })
+                .collect()
+        });
+
         let cls = Class {
             name: cls_file.this_class,
             const_pool: rtcp,
@@ -99,14 +134,45 @@ impl Class {
             super_class: cls_file.super_class,
             interfaces: cls_file.interfaces,
             static_fields,
+            static_fields_info,
+            static_field_consts,
             static_methods,
             inst_fields_info,
             inst_methods,
+            bootstrap_methods: cls_file.bootstrap_methods,
+            permitted_subclasses: cls_file.permitted_subclasses,
+            record_components,
+            reference_kind: None,
+            has_finalizer: false,
+            component_type: None,
+            defining_loader: DefiningLoader::Boot,
             init_state: Cell::new(ClassInitState::BeforeInit),
         };
         Ok(cls)
     }
 
+    // array classes are created by the VM rather than loaded from class files (cf. JVM spec 5.3.3.).
+    // they extend Object and implement Cloneable and Serializable without declaring any members, and need no initialization.
+    // `component_flags` is the access flags of the component class (PUBLIC for primitive types)
+    pub fn array_class(
+        name: &str,
+        component_flags: ClassAccessFlags,
+        defining_loader: DefiningLoader,
+    ) -> Class {
+        Class {
+            name: name.to_string(),
+            access_flags: (component_flags & ClassAccessFlags::PUBLIC)
+                | ClassAccessFlags::FINAL
+                | ClassAccessFlags::ABSTRACT,
+            super_class: Some("java/lang/Object".to_string()),
+            interfaces: ARRAY_INTERFACES.iter().map(|i| i.to_string()).collect(),
+            component_type: Some(name[1..].to_string()),
+            defining_loader,
+            init_state: Cell::new(ClassInitState::Succeeded),
+            ..Class::dummy()
+        }
+    }
+
     pub fn dummy() -> Class {
         Class {
             name: "dummy".to_string(),
@@ -115,14 +181,60 @@ impl Class {
             super_class: None,
             interfaces: Vec::new(),
             static_fields: HashMap::new(),
+            static_fields_info: Vec::new(),
+            static_field_consts: Vec::new(),
             static_methods: HashMap::new(),
             inst_fields_info: Vec::new(),
             inst_methods: HashMap::new(),
+            bootstrap_methods: Vec::new(),
+            permitted_subclasses: None,
+            record_components: None,
+            reference_kind: None,
+            has_finalizer: false,
+            component_type: None,
+            defining_loader: DefiningLoader::Boot,
             init_state: Cell::new(ClassInitState::BeforeInit),
         }
     }
 }
 
+// interfaces implemented by all array classes
+pub const ARRAY_INTERFACES: [&str; 2] = ["java/lang/Cloneable", "java/io/Serializable"];
+
+// class loader which defined the class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefiningLoader {
+    // classes on the boot classpath (and arrays of primitives)
+    Boot,
+    // classes on the application classpath
+    App,
+}
+
+// value of ConstantValue attribute of a static field
+enum ConstantValue {
+    Primitive(Value),
+    String(String),
+}
+
+impl ConstantValue {
+    fn from_cp_info(cp_info: &CPInfo, rtcp: &RunTimeConstantPool) -> VMResult<Self> {
+        let cv = match cp_info {
+            CPInfo::Integer(i) => ConstantValue::Primitive(Value::Int(*i)),
+            CPInfo::Float(f) => ConstantValue::Primitive(Value::Float(*f)),
+            CPInfo::Long(l) => ConstantValue::Primitive(Value::Long(*l)),
+            CPInfo::Double(d) => ConstantValue::Primitive(Value::Double(*d)),
+            CPInfo::String { string_idx } => {
+                let RunTimeCPInfo::Utf8(s) = rtcp.get_info(*string_idx) else {
+                    return Err("string constant must refer to an utf8 constant")?;
+                };
+                ConstantValue::String(s.clone())
+            }
+            _ => return Err("not a constant value")?,
+        };
+        Ok(cv)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 enum ClassInitState {
     BeforeInit,
@@ -131,8 +243,28 @@ enum ClassInitState {
     Failed,
 }
 
+// strength of references held by subclasses of java.lang.ref.Reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    Soft,
+    Weak,
+    Phantom,
+}
+
+impl ReferenceKind {
+    pub fn of_class(class_name: &str) -> Option<ReferenceKind> {
+        match class_name {
+            "java/lang/ref/SoftReference" => Some(ReferenceKind::Soft),
+            "java/lang/ref/WeakReference" => Some(ReferenceKind::Weak),
+            "java/lang/ref/PhantomReference" => Some(ReferenceKind::Phantom),
+            _ => None,
+        }
+    }
+}
+
 impl Class {
     // executes class initialization steps described in JVM spec 5.5.
+    // there is only one thread, so the steps for synchronization are omitted.
     pub(in crate::vm) fn initialize(
         self: Rc<Self>,
         thread: &mut Thread,
@@ -141,28 +273,82 @@ impl Class {
     ) -> VMResult<()> {
         use ClassInitState::*;
 
-        let BeforeInit = self.init_state.get() else {
-            return Ok(());
-        };
+        match self.init_state.get() {
+            BeforeInit => {}
+            // recursive request for initialization, or already initialized (step 3, 4)
+            InProgress | Succeeded => return Ok(()),
+            // the class failed to initialize before (step 5)
+            Failed => {
+                Err(JavaException::no_class_def_found_error(format!(
+                    "Could not initialize class {}",
+                    self.name.replace('/', ".")
+                )))?;
+            }
+        }
         self.init_state.set(InProgress);
 
+        // assign constant values to static fields (step 6)
+        if let Err(err) = self.assign_constant_values(meth_area, heap) {
+            self.init_state.set(Failed);
+            return Err(err);
+        }
+
         // initialize superclass & superinterfaces that declare non-abstract & non-static methods, recursively (step 7)
         if !self.access_flags.is_interface() {
-            for sc in self.superclasses_to_be_initialized(meth_area)? {
-                sc.initialize(thread, meth_area, heap)?;
+            let res = self
+                .superclasses_to_be_initialized(meth_area)
+                .and_then(|scs| {
+                    scs.into_iter()
+                        .try_for_each(|sc| sc.initialize(thread, meth_area, heap))
+                });
+            if let Err(err) = res {
+                self.init_state.set(Failed);
+                return Err(err);
             }
         }
 
         // execute <clinit> of the class/interface (step 9)
-        thread
-            .exec_class_initialization(meth_area, heap, self.clone())
-            .inspect(|_| {
-                self.init_state.set(Succeeded);
-            })
-            .inspect_err(|err| {
-                eprintln!("failed to initialize class '{}': {err}", &self.name);
-                self.init_state.set(Failed);
-            })
+        meth_area.config().report(
+            Level::Info,
+            Category::Init,
+            format_args!("initializing {}", self.name.replace('/', ".")),
+        );
+        let Err(err) = thread.exec_class_initialization(meth_area, heap, self.clone()) else {
+            self.init_state.set(Succeeded);
+            return Ok(());
+        };
+        self.init_state.set(Failed);
+        meth_area.config().report(
+            Level::Warning,
+            Category::Init,
+            format_args!(
+                "failed to initialize {}: {err}",
+                self.name.replace('/', ".")
+            ),
+        );
+
+        // exceptions other than Errors are wrapped in ExceptionInInitializerError (step 11)
+        match err.downcast::<JavaException>() {
+            Ok(exc) if !is_error_class(meth_area, &exc.class_name) => {
+                Err(JavaException::exception_in_initializer_error(*exc))?
+            }
+            Ok(exc) => Err(exc),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn assign_constant_values(&self, meth_area: &mut MethodArea, heap: &mut Heap) -> VMResult<()> {
+        for (name, cv) in self.static_field_consts.iter() {
+            let v = match cv {
+                ConstantValue::Primitive(v) => *v,
+                ConstantValue::String(s) => heap.intern_string(s, meth_area)?,
+            };
+            let Some(f) = self.static_fields.get(name) else {
+                unreachable!()
+            };
+            f.put(v);
+        }
+        Ok(())
     }
 
     fn superclasses_to_be_initialized(
@@ -190,11 +376,24 @@ impl Class {
     }
 }
 
+// whether the class is a subclass of java.lang.Error? classes that can't be loaded are not regarded as Errors
+fn is_error_class(meth_area: &mut MethodArea, cls_name: &str) -> bool {
+    meth_area.resolve_class(cls_name).is_ok()
+        && meth_area.is_subclass_of(cls_name, "java/lang/Error")
+}
+
 impl Class {
     pub fn lookup_static_field(&self, name: &str) -> Option<Rc<MutValue>> {
         self.static_fields.get(name).cloned()
     }
 
+    pub fn static_field_descriptor(&self, name: &str) -> Option<&str> {
+        self.static_fields_info
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.descriptor.as_str())
+    }
+
     pub fn lookup_static_method(&self, signature: &MethodSignature) -> Option<Rc<Method>> {
         self.static_methods.get(signature).cloned()
     }
@@ -203,6 +402,81 @@ impl Class {
         self.inst_methods.get(signature).cloned()
     }
 
+    // static methods declared in the class with the name (all overloads)
+    pub fn static_methods_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Rc<Method>> {
+        self.static_methods
+            .iter()
+            .filter(move |(sig, _)| sig.name == name)
+            .map(|(_, m)| m)
+    }
+
+    // native methods declared in the class with the name, whether they are static or not
+    pub fn methods(&self) -> impl Iterator<Item = &Rc<Method>> {
+        self.static_methods
+            .values()
+            .chain(self.inst_methods.values())
+    }
+
+    pub fn native_methods_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Rc<Method>> {
+        self.static_methods
+            .iter()
+            .chain(self.inst_methods.iter())
+            .filter(move |(sig, m)| {
+                sig.name == name && matches!(m.code_spec, MethodCodeSpec::Native)
+            })
+            .map(|(_, m)| m)
+    }
+
+    // lookup the method declared in the class, whether it is static or not
+    pub fn lookup_method(&self, signature: &MethodSignature) -> Option<Rc<Method>> {
+        self.static_methods
+            .get(signature)
+            .or_else(|| self.inst_methods.get(signature))
+            .cloned()
+    }
+
+    // replace the implementation of the method declared in the class with a native one
+    pub fn make_native(&mut self, signature: &MethodSignature) {
+        let methods = match self.static_methods.contains_key(signature) {
+            true => &mut self.static_methods,
+            false => &mut self.inst_methods,
+        };
+        if let Some(m) = methods.get_mut(signature) {
+            *m = Rc::new(Method {
+                access_flags: m.access_flags.clone() | MethodAccessFlags::NATIVE,
+                signature: m.signature.clone(),
+                code_spec: MethodCodeSpec::Native,
+            });
+        }
+    }
+
+    pub fn static_fields(&self) -> impl Iterator<Item = &MutValue> {
+        self.static_fields.values().map(|f| f.as_ref())
+    }
+
+    // static fields along with their info, in the order of declaration
+    pub fn static_fields_with_info(&self) -> impl Iterator<Item = (&FieldInfo, &MutValue)> {
+        self.static_fields_info
+            .iter()
+            .filter_map(|f| self.static_fields.get(&f.name).map(|v| (f, v.as_ref())))
+    }
+
+    // whether the class declares finalize() doing something, which overrides the empty one of java.lang.Object
+    pub fn declares_finalizer(&self) -> bool {
+        let sig = MethodSignature::new_with_raw_descriptor("finalize", "()V");
+        match self.inst_methods.get(&sig).map(|m| &m.code_spec) {
+            Some(MethodCodeSpec::Java { code, .. }) => code.as_slice() != [0xB1], // not just `return`
+            Some(MethodCodeSpec::Native) => true,
+            _ => false,
+        }
+    }
+
     pub fn instance_fields(&self) -> impl Iterator<Item = &FieldInfo> {
         self.inst_fields_info.iter()
     }
@@ -210,6 +484,59 @@ impl Class {
     pub fn get_cp_info(&self, idx: u16) -> &RunTimeCPInfo {
         self.const_pool.get_info(idx)
     }
+
+    pub fn skipped_cp_tags(&self) -> impl Iterator<Item = u8> + '_ {
+        self.const_pool.skipped_tags()
+    }
+
+    pub fn get_bootstrap_method(&self, idx: u16) -> Option<&BootstrapMethod> {
+        self.bootstrap_methods.get(idx as usize)
+    }
+}
+
+impl Class {
+    pub fn is_sealed(&self) -> bool {
+        self.permitted_subclasses.is_some()
+    }
+
+    // returns None if the class is not sealed
+    pub fn permitted_subclasses(&self) -> Option<&[String]> {
+        self.permitted_subclasses.as_deref()
+    }
+
+    pub fn is_record(&self) -> bool {
+        self.record_components.is_some()
+    }
+
+    // returns None if the class is not a record class
+    pub fn record_components(&self) -> Option<&[RecordComponent]> {
+        self.record_components.as_deref()
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.component_type.is_some()
+    }
+
+    // field descriptor of the type of the class (e.g. "Ljava/lang/String;", "[I")
+    pub fn descriptor(&self) -> String {
+        match self.is_array() {
+            true => self.name.clone(),
+            false => format!("L{};", self.name),
+        }
+    }
+
+    // the binary name of the package the class belongs to ("" for the unnamed package).
+    // array classes belong to the package of their element class (java/lang for arrays of primitives)
+    pub fn package_name(&self) -> &str {
+        let name = match self.is_array() {
+            true => match component_class_name(self.name.trim_start_matches('[')) {
+                Some(elem_name) => elem_name,
+                None => return "java/lang",
+            },
+            false => &self.name,
+        };
+        name.rsplit_once('/').map_or("", |(pkg, _)| pkg)
+    }
 }
 
 // Class is uniquely identified by its name (really?)
@@ -227,7 +554,7 @@ impl Hash for Class {
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct FieldDescriptor(String);
 
 impl FieldDescriptor {
@@ -246,6 +573,23 @@ impl std::fmt::Display for FieldDescriptor {
     }
 }
 
+/// A component of a record class.
+#[derive(Clone, Debug)]
+pub struct RecordComponent {
+    name: String,
+    descriptor: FieldDescriptor,
+}
+
+impl RecordComponent {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn descriptor(&self) -> &str {
+        self.descriptor.as_str()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Default)]
 pub struct MethodDescriptor(String);
 
@@ -254,6 +598,36 @@ impl MethodDescriptor {
         Self(raw)
     }
 
+    // descriptor of the return type ("V" for void)
+    pub fn return_type(&self) -> &str {
+        self.0.rsplit_once(')').map_or("", |(_, ret)| ret)
+    }
+
+    // descriptors of the parameter types
+    pub fn param_types(&self) -> Vec<&str> {
+        let params = self.0[1..].split_once(')').map_or("", |(ps, _)| ps);
+        let mut res = Vec::new();
+        let mut start = 0;
+        let mut chars = params.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                // prefix for array type
+                '[' => continue,
+                // ref type continues until ';'
+                'L' => {
+                    let Some((end, _)) = chars.by_ref().find(|(_, c)| *c == ';') else {
+                        break;
+                    };
+                    res.push(&params[start..=end]);
+                }
+                // primitive type
+                _ => res.push(&params[start..=i]),
+            }
+            start = chars.offset();
+        }
+        res
+    }
+
     fn num_args(&self) -> usize {
         assert!(!self.0.is_empty());
 
@@ -315,6 +689,28 @@ mod test_method_descriptor {
             assert_eq!(desc.num_args(), exp);
         }
     }
+
+    #[test]
+    fn test_param_types() {
+        let tests: Vec<(&str, Vec<&str>)> = vec![
+            ("()V", vec![]),
+            ("(ISB)V", vec!["I", "S", "B"]),
+            (
+                "(Ljava/lang/String;ILjava/lang/String;)V",
+                vec!["Ljava/lang/String;", "I", "Ljava/lang/String;"],
+            ),
+            ("([[I[I)V", vec!["[[I", "[I"]),
+            (
+                "(I[[Ljava/lang/String;J)V",
+                vec!["I", "[[Ljava/lang/String;", "J"],
+            ),
+        ];
+
+        for (input, exp) in tests {
+            let desc = MethodDescriptor(input.to_string());
+            assert_eq!(desc.param_types(), exp);
+        }
+    }
 }
 
 impl std::fmt::Display for MethodDescriptor {
@@ -365,6 +761,7 @@ pub enum MethodCodeSpec {
         max_stack: u16,
         max_locals: u16,
         code: Vec<u8>,
+        exception_table: Vec<ExceptionTableEntry>,
     },
     Native,
     Abstract,
@@ -381,9 +778,16 @@ impl Method {
     pub fn num_args(&self) -> usize {
         self.signature.descriptor.num_args()
     }
+
+    pub fn returns_void(&self) -> bool {
+        self.signature.descriptor.return_type() == "V"
+    }
 }
 
 pub struct RunTimeConstantPool(Vec<RunTimeCPInfo>);
+
+// some entries hold information not used by the interpreter for now, but kept for completeness
+#[allow(dead_code)]
 pub enum RunTimeCPInfo {
     Utf8(String),
     Integer(i32),
@@ -413,6 +817,21 @@ pub enum RunTimeCPInfo {
         name: String,
         descriptor: String, // format: (<param type>*)<return type>
     },
+    MethodHandle {
+        reference_kind: u8,
+        class_name: String,
+        name: String,
+        descriptor: String, // field descriptor or method descriptor, depending on reference_kind
+    },
+    MethodType(MethodDescriptor),
+    InvokeDynamic {
+        bootstrap_method_attr_idx: u16,
+        name: String,
+        descriptor: MethodDescriptor,
+    },
+    Skipped {
+        tag: u8,
+    },
     Unsupported,
 }
 
@@ -453,6 +872,18 @@ impl RunTimeConstantPool {
                     name: cp.get_utf8(*name_idx).to_string(),
                     descriptor: cp.get_utf8(*descriptor_idx).to_string(),
                 }),
+                CPInfo::MethodHandle {
+                    reference_kind,
+                    reference_idx,
+                } => resolve_method_handle(&cp, *reference_kind, *reference_idx),
+                CPInfo::MethodType { descriptor_idx } => Ok(MethodType(MethodDescriptor(
+                    cp.get_utf8(*descriptor_idx).to_string(),
+                ))),
+                CPInfo::InvokeDynamic {
+                    bootstrap_method_attr_idx,
+                    name_and_type_idx,
+                } => resolve_invoke_dynamic(&cp, *bootstrap_method_attr_idx, *name_and_type_idx),
+                CPInfo::Skipped { tag } => Ok(Skipped { tag: *tag }),
                 CPInfo::Unsupported => Ok(Unsupported),
             })
             .collect::<Result<Vec<_>, _>>()?;
@@ -468,6 +899,14 @@ impl RunTimeConstantPool {
         assert!(0 < idx && idx <= self.0.len() as u16);
         &self.0[idx as usize - 1]
     }
+
+    // tags of entries skipped while parsing, since their types are not supported
+    pub fn skipped_tags(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.iter().filter_map(|info| match info {
+            RunTimeCPInfo::Skipped { tag } => Some(*tag),
+            _ => None,
+        })
+    }
 }
 
 fn resolve_fieldref(cp: &ConstantPool, cls_idx: u16, nt_idx: u16) -> VMResult<RunTimeCPInfo> {
@@ -518,6 +957,54 @@ fn resolve_interface_methodref(
     Ok(meth)
 }
 
+fn resolve_method_handle(cp: &ConstantPool, kind: u8, ref_idx: u16) -> VMResult<RunTimeCPInfo> {
+    let (CPInfo::Fieldref {
+        class_idx,
+        name_and_type_idx,
+    }
+    | CPInfo::Methodref {
+        class_idx,
+        name_and_type_idx,
+    }
+    | CPInfo::InterfaceMethodref {
+        class_idx,
+        name_and_type_idx,
+    }) = cp.get_info(ref_idx)
+    else {
+        return Err("reference of method handle must be a field/method ref".into());
+    };
+    let ConstPoolRef {
+        class_name,
+        name,
+        descriptor,
+    } = resolve_const_pool_ref(cp, *class_idx, *name_and_type_idx)?;
+
+    let mh = RunTimeCPInfo::MethodHandle {
+        reference_kind: kind,
+        class_name,
+        name,
+        descriptor,
+    };
+    Ok(mh)
+}
+
+fn resolve_invoke_dynamic(cp: &ConstantPool, bsm_idx: u16, nt_idx: u16) -> VMResult<RunTimeCPInfo> {
+    let &CPInfo::NameAndType {
+        name_idx,
+        descriptor_idx,
+    } = cp.get_info(nt_idx)
+    else {
+        return Err("failed to resolve CPInfo::NameAndType".into());
+    };
+
+    let indy = RunTimeCPInfo::InvokeDynamic {
+        bootstrap_method_attr_idx: bsm_idx,
+        name: cp.get_utf8(name_idx).to_string(),
+        descriptor: MethodDescriptor(cp.get_utf8(descriptor_idx).to_string()),
+    };
+    Ok(indy)
+}
+
 struct ConstPoolRef {
     class_name: String,
     name: String,
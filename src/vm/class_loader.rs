@@ -1,8 +1,6 @@
 use std::{
-    ffi::OsStr,
     fs::File,
     io::ErrorKind,
-    os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
 };
 
@@ -10,31 +8,42 @@ use zip::{result::ZipError, ZipArchive};
 
 use crate::class_file::ClassFile;
 
-use super::{class::Class, error::VMResult};
+use super::{
+    class::Class,
+    error::{JavaException, VMResult},
+};
 
 pub struct ClassLoader {
+    // entries of the boot classpath come first
     classpath: Vec<PathBuf>,
+    num_boot_entries: usize,
 }
 
 impl ClassLoader {
-    pub fn new<P>(classpath: &P) -> ClassLoader
-    where
-        P: AsRef<OsStr>,
-    {
+    pub fn new(boot_classpath: &[PathBuf], classpath: &[PathBuf]) -> ClassLoader {
         ClassLoader {
-            classpath: split_classpath(classpath),
+            classpath: [boot_classpath, classpath].concat(),
+            num_boot_entries: boot_classpath.len(),
         }
     }
+
+    // whether the path is an entry of the boot classpath
+    pub fn is_boot_entry(&self, path: &Path) -> bool {
+        self.classpath[..self.num_boot_entries]
+            .iter()
+            .any(|p| p == path)
+    }
 }
 
 impl ClassLoader {
-    pub fn load(&self, name: &str) -> VMResult<Class> {
+    // load the class, and return it along with the classpath entry it is loaded from
+    pub fn load(&self, name: &str) -> VMResult<(Class, &Path)> {
         for cp in self.classpath.iter() {
             match cp.extension() {
                 Some(ext) if (ext == "jar" || ext == "zip") => match self.load_from_jar(cp, name) {
                     Ok(Some(cls)) => {
                         if cls.name == name {
-                            return Ok(cls);
+                            return Ok((cls, cp));
                         } else {
                             return Err("specified binary name and actual class name (this_class) doesn't match")?;
                         }
@@ -45,7 +54,7 @@ impl ClassLoader {
                 None => match self.load_from_class(cp, name) {
                     Ok(Some(cls)) => {
                         if cls.name == name {
-                            return Ok(cls);
+                            return Ok((cls, cp));
                         } else {
                             return Err("specified binary name and actual class name (this_class) doesn't match")?;
                         }
@@ -56,7 +65,7 @@ impl ClassLoader {
                 _ => continue, // skip paths other than jar/zip file or directory-ish path
             }
         }
-        return Err("class '{name}' not found in classpath")?;
+        Err(JavaException::no_class_def_found_error(name))?
     }
 
     fn load_from_class(&self, cp: &Path, cls_name: &str) -> VMResult<Option<Class>> {
@@ -92,22 +101,3 @@ impl ClassLoader {
         }
     }
 }
-
-// the logic is borrowed from std::env::split_paths
-fn split_classpath<P>(cp: &P) -> Vec<PathBuf>
-where
-    P: AsRef<OsStr>,
-{
-    fn bytes_to_path(b: &[u8]) -> PathBuf {
-        PathBuf::from(<OsStr as OsStrExt>::from_bytes(b))
-    }
-    fn is_separator(b: &u8) -> bool {
-        *b == b';'
-    }
-
-    cp.as_ref()
-        .as_bytes()
-        .split(is_separator as fn(&u8) -> bool)
-        .map(bytes_to_path as fn(&[u8]) -> PathBuf)
-        .collect::<Vec<_>>()
-}
@@ -0,0 +1,139 @@
+use std::fmt;
+
+/// Severity of diagnostic messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warning,
+    Info,
+    Debug,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Part of the VM which diagnostic messages come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Loading and parsing of class files.
+    ClassLoading,
+    /// Resolution of superclasses and verification.
+    Linking,
+    /// Initialization of classes.
+    Init,
+    /// Execution of methods, including exceptions thrown.
+    Interpreter,
+    /// Garbage collection (like `-verbose:gc`).
+    Gc,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::ClassLoading,
+        Category::Linking,
+        Category::Init,
+        Category::Interpreter,
+        Category::Gc,
+    ];
+
+    /// Name of the category, as used in `-verbose:<name>` of the CLI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::ClassLoading => "class",
+            Category::Linking => "link",
+            Category::Init => "init",
+            Category::Interpreter => "interpreter",
+            Category::Gc => "gc",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Category> {
+        Category::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Receiver of diagnostic messages from the VM.
+///
+/// Closures of the form `Fn(Level, Category, &str)` can be used as sinks.
+pub trait DiagnosticSink {
+    fn report(&self, level: Level, category: Category, message: &str);
+}
+
+impl<F> DiagnosticSink for F
+where
+    F: Fn(Level, Category, &str),
+{
+    fn report(&self, level: Level, category: Category, message: &str) {
+        self(level, category, message)
+    }
+}
+
+// filter of diagnostic messages. messages are dropped unless their category is enabled
+pub struct Diagnostics {
+    pub sink: Option<Box<dyn DiagnosticSink>>,
+    pub level: Level,
+    pub categories: Vec<Category>,
+}
+
+impl Diagnostics {
+    pub fn enabled(&self, level: Level, category: Category) -> bool {
+        level <= self.level && self.categories.contains(&category)
+    }
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Diagnostics {
+            sink: None,
+            level: Level::Info,
+            categories: Vec::new(),
+        }
+    }
+}
+
+// default format of messages written to the stdout sink of the VM
+pub fn format_message(level: Level, category: Category, message: &str) -> String {
+    format!("[{level}][{category}] {message}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_enabled() {
+        let diag = Diagnostics {
+            categories: vec![Category::ClassLoading, Category::Init],
+            ..Default::default()
+        };
+        assert!(diag.enabled(Level::Info, Category::ClassLoading));
+        assert!(diag.enabled(Level::Error, Category::Init));
+        assert!(!diag.enabled(Level::Debug, Category::ClassLoading));
+        assert!(!diag.enabled(Level::Error, Category::Interpreter));
+
+        assert!(!Diagnostics::default().enabled(Level::Error, Category::Linking));
+    }
+
+    #[test]
+    fn test_category_name() {
+        for c in Category::ALL {
+            assert_eq!(Category::from_name(c.name()), Some(c));
+        }
+        assert_eq!(Category::from_name("jit"), None);
+    }
+}
@@ -1,3 +1,121 @@
-type VMError = Box<dyn std::error::Error>;
+pub type VMError = Box<dyn std::error::Error>;
 
 pub type VMResult<T> = Result<T, VMError>;
+
+/// An exception (or error) thrown in Java world.
+///
+/// It is raised either by Java code (`athrow`) or by the VM itself (e.g. linkage errors).
+/// In the latter case, the exception object is created on the heap lazily, when the exception is about to be caught.
+#[derive(Debug)]
+pub struct JavaException {
+    /// Binary name of the exception class (e.g. `"java/lang/IncompatibleClassChangeError"`).
+    pub class_name: String,
+    /// Detail message of the exception.
+    pub message: Option<String>,
+    /// Exception that caused this one (e.g. the one thrown by `<clinit>` for `ExceptionInInitializerError`).
+    pub cause: Option<Box<JavaException>>,
+    // reference to the exception object on the heap (None if the object has not been created yet)
+    pub(crate) obj_ref: Option<usize>,
+    /// Methods that were being executed when the exception was thrown (innermost first).
+    pub stack_trace: Vec<String>,
+}
+
+impl JavaException {
+    pub fn new(class_name: &str, message: impl Into<String>) -> Self {
+        JavaException {
+            class_name: class_name.to_string(),
+            message: Some(message.into()),
+            cause: None,
+            obj_ref: None,
+            stack_trace: Vec::new(),
+        }
+    }
+
+    // exception which is thrown by Java code, and already exists on the heap
+    pub(crate) fn from_object(class_name: &str, message: Option<String>, obj_ref: usize) -> Self {
+        JavaException {
+            class_name: class_name.to_string(),
+            message,
+            cause: None,
+            obj_ref: Some(obj_ref),
+            stack_trace: Vec::new(),
+        }
+    }
+
+    pub fn exception_in_initializer_error(cause: JavaException) -> Self {
+        JavaException {
+            class_name: "java/lang/ExceptionInInitializerError".to_string(),
+            message: None,
+            cause: Some(Box::new(cause)),
+            obj_ref: None,
+            stack_trace: Vec::new(),
+        }
+    }
+}
+
+macro_rules! java_exceptions {
+    ($($ctor_name:ident => $cls_name:literal$(,)?)*) => {
+        impl JavaException {
+            $(
+                pub fn $ctor_name(message: impl Into<String>) -> Self {
+                    Self::new($cls_name, message)
+                }
+            )*
+        }
+    };
+}
+
+java_exceptions! {
+    abstract_method_error => "java/lang/AbstractMethodError",
+    arithmetic_exception => "java/lang/ArithmeticException",
+    array_index_out_of_bounds_exception => "java/lang/ArrayIndexOutOfBoundsException",
+    array_store_exception => "java/lang/ArrayStoreException",
+    class_cast_exception => "java/lang/ClassCastException",
+    class_circularity_error => "java/lang/ClassCircularityError",
+    clone_not_supported_exception => "java/lang/CloneNotSupportedException",
+    illegal_argument_exception => "java/lang/IllegalArgumentException",
+    incompatible_class_change_error => "java/lang/IncompatibleClassChangeError",
+    instantiation_error => "java/lang/InstantiationError",
+    instantiation_exception => "java/lang/InstantiationException",
+    negative_array_size_exception => "java/lang/NegativeArraySizeException",
+    no_class_def_found_error => "java/lang/NoClassDefFoundError",
+    no_such_field_error => "java/lang/NoSuchFieldError",
+    no_such_method_error => "java/lang/NoSuchMethodError",
+    null_pointer_exception => "java/lang/NullPointerException",
+    out_of_memory_error => "java/lang/OutOfMemoryError",
+    stack_overflow_error => "java/lang/StackOverflowError",
+    unsatisfied_link_error => "java/lang/UnsatisfiedLinkError",
+    verify_error => "java/lang/VerifyError",
+}
+
+impl std::fmt::Display for JavaException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.class_name.replace('/', "."))?;
+        if let Some(msg) = &self.message {
+            write!(f, ": {msg}")?;
+        }
+        if let Some(cause) = &self.cause {
+            write!(f, " (caused by {cause})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for JavaException {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let exc = JavaException::arithmetic_exception("/ by zero");
+        assert_eq!(exc.to_string(), "java.lang.ArithmeticException: / by zero");
+
+        let eiie = JavaException::exception_in_initializer_error(exc);
+        assert_eq!(
+            eiie.to_string(),
+            "java.lang.ExceptionInInitializerError (caused by java.lang.ArithmeticException: / by zero)"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+// bridges exceptions in Rust world (JavaException) and exception objects in Java world.
+
+use super::{
+    error::{JavaException, VMResult},
+    heap::{Heap, RefValue},
+    method_area::MethodArea,
+    thread::Thread,
+    value::Value,
+};
+
+const THROWABLE_CLASS: &str = "java/lang/Throwable";
+
+// create the object of the exception on the heap if it has not been created yet, and returns the reference to it.
+// constructors of the exception class are not executed; the fields of Throwable are set like its field initializers
+// and the constructor taking the message (and the cause) do.
+pub fn materialize(
+    exc: &mut JavaException,
+    thread: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+) -> VMResult<usize> {
+    if let Some(r) = exc.obj_ref {
+        return Ok(r);
+    }
+
+    // the cause may be moved by GC while the class of the exception is initialized
+    let cause = match &mut exc.cause {
+        Some(cause) => {
+            let r = materialize(cause, thread, meth_area, heap)?;
+            Some(heap.new_handle(r)?)
+        }
+        None => None,
+    };
+    // the stack may be exhausted when StackOverflowError is thrown
+    let cls = meth_area.resolve_class(&exc.class_name)?;
+    thread.with_reserved_frames(|thread| cls.clone().initialize(thread, meth_area, heap))?;
+    let sentinel = |meth_area: &mut MethodArea, name| {
+        meth_area
+            .resolve_static_field(THROWABLE_CLASS, name)
+            .map_or(Value::Reference(0), |(_, f)| f.get())
+    };
+    let stack_trace = sentinel(meth_area, "UNASSIGNED_STACK");
+    let suppressed = sentinel(meth_area, "SUPPRESSED_SENTINEL");
+
+    let Value::Reference(r) = heap.alloc_object(cls, meth_area)? else {
+        unreachable!()
+    };
+    let msg = match &exc.message {
+        Some(msg) => heap.alloc_string(msg, meth_area)?,
+        None => Value::Reference(0),
+    };
+    // the cause referring to the exception itself means that it has not been initialized yet
+    let cause = Value::Reference(cause.map_or(r, |h| h.get()));
+    // the depth of the stack is recorded like fillInStackTrace does
+    let depth = Value::Int(exc.stack_trace.len() as i32);
+
+    let Some(RefValue::Object(obj)) = heap.get(r) else {
+        unreachable!()
+    };
+    for (name, v) in [
+        ("detailMessage", msg),
+        ("cause", cause),
+        ("stackTrace", stack_trace),
+        ("suppressedExceptions", suppressed),
+        ("depth", depth),
+    ] {
+        let Some(f) = obj.get_field(THROWABLE_CLASS, name) else {
+            return Err(format!(
+                "{} is not a subclass of java.lang.Throwable",
+                exc.class_name
+            ))?;
+        };
+        f.put(v);
+    }
+
+    exc.obj_ref = Some(r);
+    Ok(r)
+}
+
+// make JavaException from the exception object thrown by Java code
+pub fn from_object(heap: &mut Heap, r: usize) -> VMResult<JavaException> {
+    let Some(RefValue::Object(obj)) = heap.get(r) else {
+        return Err("referent is not a object")?;
+    };
+    let cls_name = obj.get_class().name.clone();
+
+    let message = match obj
+        .get_field(THROWABLE_CLASS, "detailMessage")
+        .map(|f| f.get())
+    {
+        Some(Value::Reference(s)) if s != 0 => heap.get_string(s).ok(),
+        _ => None,
+    };
+    Ok(JavaException::from_object(&cls_name, message, r))
+}
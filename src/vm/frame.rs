@@ -2,9 +2,12 @@ use std::rc::Rc;
 
 use super::{
     class::{Class, Method, MethodCodeSpec, MethodSignature, RunTimeCPInfo},
+    error::{JavaException, VMResult},
+    heap::Forwarding,
+    method_area::method_name,
     value::Value,
 };
-use crate::support::ByteSeq;
+use crate::{class_file::ExceptionTableEntry, support::ByteSeq};
 
 pub struct Frame {
     locals: Vec<Option<Value>>,
@@ -13,28 +16,39 @@ pub struct Frame {
     meth_sig: MethodSignature,
     code: ByteSeq,
     pc: u32,
+    exception_table: Vec<ExceptionTableEntry>,
 }
 
 impl Frame {
-    pub fn new(class: Rc<Class>, method: Rc<Method>) -> Frame {
-        let MethodCodeSpec::Java {
-            max_stack,
-            max_locals,
-            code,
-        } = &method.code_spec
-        else {
-            unreachable!("method doesn't have code to be executed on JVM frame");
+    pub fn new(class: Rc<Class>, method: Rc<Method>) -> VMResult<Frame> {
+        let (max_stack, max_locals, code, exception_table) = match &method.code_spec {
+            MethodCodeSpec::Java {
+                max_stack,
+                max_locals,
+                code,
+                exception_table,
+            } => (max_stack, max_locals, code, exception_table),
+            MethodCodeSpec::Abstract => Err(JavaException::abstract_method_error(method_name(
+                &class.name,
+                &method.signature,
+            )))?,
+            // ネイティブメソッドはフレームを作らずに実行される (cf. Thread::invoke_from_current_frame)
+            MethodCodeSpec::Native => Err(format!(
+                "native method {} can't be executed in a frame",
+                method_name(&class.name, &method.signature)
+            ))?,
         };
         let code_reader = ByteSeq::new(code.as_slice()).unwrap();
 
-        Frame {
+        Ok(Frame {
             locals: vec![Option::default(); *max_locals as usize],
             op_stack: Vec::with_capacity(*max_stack as usize),
             class,
             meth_sig: method.signature.clone(),
             code: code_reader,
             pc: 0,
-        }
+            exception_table: exception_table.clone(),
+        })
     }
 
     pub fn new_empty() -> Frame {
@@ -45,6 +59,7 @@ impl Frame {
             meth_sig: Default::default(),
             code: ByteSeq::new(vec![].as_slice()).unwrap(),
             pc: 0,
+            exception_table: Vec::new(),
         }
     }
 }
@@ -57,6 +72,10 @@ impl Frame {
     }
 
     fn set_locals(&mut self, idx: usize, vs: &[Option<Value>]) {
+        if self.locals.len() < idx + vs.len() {
+            // the frame without code (e.g. bootstrap frame) has no room for locals in advance
+            self.locals.resize(idx + vs.len(), None);
+        }
         self.locals[idx..(vs.len() + idx)].copy_from_slice(vs);
     }
 
@@ -64,6 +83,7 @@ impl Frame {
         self.locals[idx].expect("local not exist")
     }
 
+    #[cfg(test)]
     fn get_locals(&self) -> &[Option<Value>] {
         &self.locals
     }
@@ -111,6 +131,23 @@ impl Frame {
         self.op_stack.push(v)
     }
 
+    // values in the local variables and the operand stack, which are roots of GC
+    pub fn values(&self) -> impl Iterator<Item = Value> + '_ {
+        self.locals
+            .iter()
+            .flatten()
+            .chain(self.op_stack.iter())
+            .copied()
+    }
+
+    // update references moved by GC
+    pub fn forward_references(&mut self, fwd: &Forwarding) {
+        let locals = self.locals.iter_mut().flatten();
+        for v in locals.chain(self.op_stack.iter_mut()) {
+            *v = fwd.forward_value(*v);
+        }
+    }
+
     pub fn pop_operand(&mut self) -> Value {
         self.op_stack.pop().expect("stack underflow")
     }
@@ -119,6 +156,17 @@ impl Frame {
         self.op_stack.last().expect("stack underflow")
     }
 
+    // スタックトップからn個下の値を参照する (n = 0 -> スタックトップ)
+    pub fn peek_operand_at(&self, n: usize) -> &Value {
+        let len = self.op_stack.len();
+        assert!(n < len, "stack underflow");
+        &self.op_stack[len - 1 - n]
+    }
+
+    pub fn clear_operands(&mut self) {
+        self.op_stack.clear();
+    }
+
     pub fn dup_operand(&mut self) {
         let v = self.peek_operand();
         self.push_operand(*v);
@@ -137,11 +185,27 @@ impl Frame {
 
     // 呼び出すメソッドに、そのメソッドのレシーバ(`this`)とn個の引数を渡す。インスタンスメソッドの呼び出し時に利用
     //
-    // レシーバとなる値は、呼び出し元フレームのスタックからすでにpopされている前提
-    pub fn transfer_receiver_and_args(caller: &mut Self, callee: &mut Self, this: Value, n: usize) {
-        assert!(matches!(this, Value::Reference(_)));
-        callee.set_local(0, this);
-        callee.set_locals(1, caller.pop_args(n).as_slice());
+    // caller stack:    ..., this, arg1, ... , argN (stack top)
+    //                         ↓     ↓           ↓
+    // callee locals: (head) this, prm1, ... , prmN, ...
+    pub fn transfer_receiver_and_args(caller: &mut Self, callee: &mut Self, n: usize) {
+        let args = caller.pop_args(n + 1);
+        assert!(matches!(args[0], Some(Value::Reference(_))));
+        callee.set_locals(0, args.as_slice());
+    }
+
+    // スタックトップからn個の値を参照する(値は積まれた順に並び、スタックからは取り除かない)。ネイティブメソッドの呼び出し時に利用
+    pub fn peek_operands(&self, n: usize) -> Vec<Value> {
+        let len = self.op_stack.len();
+        assert!(n <= len, "stack underflow");
+        self.op_stack[len - n..].to_vec()
+    }
+
+    // スタックトップからn個の値を取り出す(取り出した値は積まれた順に並ぶ)
+    pub fn pop_operands(&mut self, n: usize) -> Vec<Value> {
+        let len = self.op_stack.len();
+        assert!(n <= len, "stack underflow");
+        self.op_stack.split_off(len - n)
     }
 
     fn pop_args(&mut self, n: usize) -> Vec<Option<Value>> {
@@ -165,12 +229,34 @@ impl Frame {
     pub fn get_cp_info(&self, idx: u16) -> &RunTimeCPInfo {
         self.class.get_cp_info(idx)
     }
+
+    pub fn current_class(&self) -> Rc<Class> {
+        self.class.clone()
+    }
+
+    /* 例外ハンドラの参照 */
+    // 現在のPCで有効な例外ハンドラを、例外テーブル中の順に列挙
+    pub fn active_exception_handlers(&self) -> impl Iterator<Item = &ExceptionTableEntry> {
+        let pc = self.pc;
+        self.exception_table
+            .iter()
+            .filter(move |e| (e.start_pc as u32) <= pc && pc < (e.end_pc as u32))
+    }
 }
 
 impl Frame {
+    pub fn method_signature(&self) -> &MethodSignature {
+        &self.meth_sig
+    }
+
     pub fn executing_method_info(&self) -> String {
         format!("{}.{}", self.class.name, self.meth_sig)
     }
+
+    // メソッドを実行しているフレームか? (`new_empty()`で作ったフレームはfalse)
+    pub fn has_code(&self) -> bool {
+        !self.code.is_empty()
+    }
 }
 
 #[cfg(test)]
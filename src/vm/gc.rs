@@ -0,0 +1,75 @@
+// garbage collection of the heap.
+// the collector runs at safepoints (between instructions) or on request.
+// roots are values in frames, static fields, java.lang.Class objects, references held by native code, interned strings and handles held by the host.
+// the generational collector moves values, so the holders of the roots are updated after the collection.
+// references whose referents are cleared, and unreachable objects to be finalized, are processed by Java code after the collection (cf. Thread::process_pending).
+
+use super::{
+    diagnostics::{Category, Level},
+    heap::{GcStats, Heap},
+    method_area::MethodArea,
+    thread::Thread,
+    value::Value,
+};
+
+// `full` requests the collection of the whole heap, rather than the nursery only
+pub fn collect(
+    thread: &mut Thread,
+    meth_area: &MethodArea,
+    heap: &mut Heap,
+    full: bool,
+) -> GcStats {
+    let roots = roots(thread, meth_area);
+    let (stats, fwd) = heap.collect(roots, full);
+    if !fwd.is_empty() {
+        thread.forward_references(&fwd);
+        for f in meth_area.static_fields().chain(meth_area.mirrors()) {
+            f.put(fwd.forward_value(f.get()));
+        }
+        meth_area.natives().jni.forward_references(&fwd);
+    }
+
+    meth_area.config().report(
+        Level::Info,
+        Category::Gc,
+        format_args!(
+            "freed {} objects ({} bytes), promoted {} objects, cleared {} references, {} objects to be finalized, {} objects ({} bytes) alive",
+            stats.freed_objects,
+            stats.freed_bytes,
+            stats.promoted_objects,
+            stats.cleared_references,
+            stats.finalizable_objects,
+            stats.live_objects,
+            stats.live_bytes
+        ),
+    );
+    stats
+}
+
+// collect garbage of the whole heap if `size` bytes can't be allocated otherwise, before OutOfMemoryError is thrown.
+// if it is not enough, soft references are cleared as the last resort.
+// GC may free or move values, so this must be called only where all the references in use are held by the roots
+pub fn make_room(thread: &mut Thread, meth_area: &MethodArea, heap: &mut Heap, size: usize) {
+    if !heap.has_room(size) {
+        collect(thread, meth_area, heap, true);
+    }
+    if !heap.has_room(size) {
+        heap.clear_soft_references();
+        collect(thread, meth_area, heap, true);
+    }
+}
+
+// roots held outside of the heap
+pub fn roots(thread: &Thread, meth_area: &MethodArea) -> Vec<usize> {
+    let mut roots = thread
+        .values()
+        .chain(meth_area.static_fields().map(|f| f.get()))
+        .chain(meth_area.mirrors().map(|m| m.get()))
+        .filter_map(|v| match v {
+            Value::Reference(r) if r != 0 => Some(r),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    roots.extend(meth_area.natives().jni.references());
+    roots
+}
@@ -0,0 +1,312 @@
+// handles of runtime data structures, exposed to the embedders of the VM
+
+use std::{marker::PhantomData, rc::Rc};
+
+use super::{
+    class::{Class, Method, MethodSignature, RecordComponent},
+    error::VMResult,
+    heap::{Handle, JavaArray, RefValue},
+    java_type::{JavaPrimitiveType, JavaType},
+    value::{MutValue, Value},
+    VM,
+};
+
+/// A class (or interface) loaded into the VM.
+#[derive(Clone)]
+pub struct ClassRef(pub(in crate::vm) Rc<Class>);
+
+impl ClassRef {
+    /// Binary name of the class (e.g. `java/lang/String`).
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    pub fn super_class(&self) -> Option<&str> {
+        self.0.super_class.as_deref()
+    }
+
+    pub fn interfaces(&self) -> &[String] {
+        &self.0.interfaces
+    }
+
+    pub fn is_interface(&self) -> bool {
+        self.0.access_flags.is_interface()
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.0.is_sealed()
+    }
+
+    /// Classes permitted to extend the class (`None` if the class is not sealed).
+    pub fn permitted_subclasses(&self) -> Option<&[String]> {
+        self.0.permitted_subclasses()
+    }
+
+    pub fn is_record(&self) -> bool {
+        self.0.is_record()
+    }
+
+    /// Components of the record class (`None` if the class is not a record class).
+    pub fn record_components(&self) -> Option<&[RecordComponent]> {
+        self.0.record_components()
+    }
+
+    /// Looks up the method declared in the class itself (inherited methods are not taken into account).
+    pub fn declared_method(&self, name: &str, descriptor: &str) -> Option<MethodRef> {
+        let sig = MethodSignature::new_with_raw_descriptor(name, descriptor);
+        self.0.lookup_method(&sig).map(|m| MethodRef {
+            class: self.0.clone(),
+            method: m,
+        })
+    }
+}
+
+/// A method, along with the class that declares it.
+#[derive(Clone)]
+pub struct MethodRef {
+    pub(in crate::vm) class: Rc<Class>,
+    pub(in crate::vm) method: Rc<Method>,
+}
+
+impl MethodRef {
+    pub fn class_name(&self) -> &str {
+        &self.class.name
+    }
+
+    pub fn name(&self) -> &str {
+        &self.method.signature.name
+    }
+
+    pub fn descriptor(&self) -> String {
+        self.method.signature.descriptor.to_string()
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.method.access_flags.is_static()
+    }
+}
+
+/// Handle of an object (or an array) on the heap of the VM.
+///
+/// It can be passed as a value of any reference type.
+/// The object is kept alive from garbage collection while any handle of it exists.
+/// Handles obtained before [`VM::reset`] are invalidated; operations on them fail after that.
+#[derive(Clone)]
+pub struct JObject(pub(in crate::vm) Handle);
+
+impl JObject {
+    pub(in crate::vm) fn r(&self) -> usize {
+        self.0.get()
+    }
+
+    /// Binary name of the class of the object (descriptor for arrays, e.g. `[I`).
+    pub fn class_name(&self, vm: &VM) -> VMResult<String> {
+        match vm.heap.get_ref(self.r()) {
+            Some(RefValue::Object(obj)) => Ok(obj.get_class().name.clone()),
+            Some(RefValue::Array(arr)) => Ok(arr.descriptor().to_string()),
+            _ => Err(format!("invalid object handle: {self:?}"))?,
+        }
+    }
+
+    /// Gets the value of the instance field.
+    ///
+    /// Fields declared in the class of the object hide ones with the same name declared in superclasses.
+    pub fn get_field<T: JavaType>(&self, vm: &mut VM, name: &str) -> VMResult<T> {
+        let (desc, v) = self.field(vm, name)?;
+        if !T::accepts(&desc) {
+            return Err(format!(
+                "field {name} of type {desc} can't be read as {}",
+                T::descriptor()
+            ))?;
+        }
+        T::from_value(v, vm)
+    }
+
+    /// Sets the value to the instance field.
+    pub fn set_field<T: JavaType>(&self, vm: &mut VM, name: &str, v: T) -> VMResult<()> {
+        let (desc, _) = self.field(vm, name)?;
+        let v = v.into_value(vm)?;
+        let v = vm.value_of_type(&desc, v)?;
+
+        let (_, f) = self.find_field(vm, name)?;
+        f.put(v);
+        Ok(())
+    }
+
+    // descriptor and current value of the field
+    fn field(&self, vm: &mut VM, name: &str) -> VMResult<(String, Value)> {
+        let (desc, f) = self.find_field(vm, name)?;
+        Ok((desc, f.get()))
+    }
+
+    fn find_field<'a>(&self, vm: &'a mut VM, name: &str) -> VMResult<(String, &'a MutValue)> {
+        let Some(RefValue::Object(obj)) = vm.heap.get_ref(self.r()) else {
+            return Err(format!("{self:?} is not a valid handle of an object"))?;
+        };
+        match obj.find_field_by_name(name, &mut vm.meth_area)? {
+            Some(f) => Ok(f),
+            None => Err(format!(
+                "field {name} not found in {}",
+                obj.get_class().name
+            ))?,
+        }
+    }
+
+    /// Casts the handle to the handle of `java.lang.String`.
+    pub fn as_string(self, vm: &mut VM) -> VMResult<JString> {
+        JString::from_value(Value::Reference(self.r()), vm)
+    }
+
+    /// Casts the handle to the handle of an array whose elements are of the type `T`.
+    pub fn as_array<T: JavaType>(self, vm: &mut VM) -> VMResult<JArray<T>> {
+        JArray::from_value(Value::Reference(self.r()), vm)
+    }
+}
+
+/// Handle of a `java.lang.String` object.
+#[derive(Clone)]
+pub struct JString(pub(in crate::vm) Handle);
+
+impl JString {
+    pub(in crate::vm) fn r(&self) -> usize {
+        self.0.get()
+    }
+
+    /// Copies the content of the string.
+    pub fn to_string(&self, vm: &mut VM) -> VMResult<String> {
+        vm.heap.get_string(self.r())
+    }
+}
+
+impl From<JString> for JObject {
+    fn from(s: JString) -> Self {
+        JObject(s.0)
+    }
+}
+
+/// Handle of an array whose elements are of the type `T`.
+pub struct JArray<T> {
+    pub(in crate::vm) handle: Handle,
+    pub(in crate::vm) _elem: PhantomData<fn() -> T>,
+}
+
+impl<T> JArray<T> {
+    pub(in crate::vm) fn new(handle: Handle) -> Self {
+        JArray {
+            handle,
+            _elem: PhantomData,
+        }
+    }
+
+    pub(in crate::vm) fn r(&self) -> usize {
+        self.handle.get()
+    }
+
+    fn array<'a>(&self, vm: &'a VM) -> VMResult<&'a dyn JavaArray> {
+        let Some(RefValue::Array(arr)) = vm.heap.get_ref(self.r()) else {
+            return Err(format!("invalid array handle: {}", self.r()))?;
+        };
+        Ok(arr.as_ref())
+    }
+
+    fn array_mut<'a>(&self, vm: &'a mut VM) -> VMResult<&'a mut dyn JavaArray> {
+        let Some(RefValue::Array(arr)) = vm.heap.get(self.r()) else {
+            return Err(format!("invalid array handle: {}", self.r()))?;
+        };
+        Ok(arr.as_mut())
+    }
+
+    pub fn len(&self, vm: &VM) -> VMResult<usize> {
+        Ok(self.array(vm)?.len() as usize)
+    }
+
+    pub fn is_empty(&self, vm: &VM) -> VMResult<bool> {
+        Ok(self.len(vm)? == 0)
+    }
+}
+
+impl<T: JavaType> JArray<T> {
+    /// Gets the element at the index.
+    pub fn get(&self, vm: &mut VM, idx: usize) -> VMResult<T> {
+        let arr = self.array(vm)?;
+        let Some(v) = arr.get(idx as u32) else {
+            return Err(format!(
+                "index {idx} out of bounds for length {}",
+                arr.len()
+            ))?;
+        };
+        T::from_value(v, vm)
+    }
+
+    /// Sets the element at the index.
+    pub fn set(&self, vm: &mut VM, idx: usize, v: T) -> VMResult<()> {
+        let len = self.len(vm)?;
+        if idx >= len {
+            return Err(format!("index {idx} out of bounds for length {len}"))?;
+        }
+        let v = v.into_value(vm)?;
+        let desc = self.array(vm)?.descriptor().to_string();
+        let v = vm.value_of_type(&desc[1..], v)?;
+
+        self.array_mut(vm)?.put(idx as u32, v);
+        Ok(())
+    }
+
+    /// Copies all the elements.
+    pub fn to_vec(&self, vm: &mut VM) -> VMResult<Vec<T>> {
+        Vec::from_value(Value::Reference(self.r()), vm)
+    }
+}
+
+impl<T: JavaPrimitiveType> JArray<T> {
+    /// Borrows the elements as a slice, without copying.
+    pub fn as_slice<'a>(&self, vm: &'a VM) -> VMResult<&'a [T]> {
+        Ok(self
+            .array(vm)?
+            .as_slice()
+            .ok_or("unexpected type of array elements")?)
+    }
+
+    /// Borrows the elements as a mutable slice, without copying.
+    pub fn as_mut_slice<'a>(&self, vm: &'a mut VM) -> VMResult<&'a mut [T]> {
+        Ok(self
+            .array_mut(vm)?
+            .as_mut_slice()
+            .ok_or("unexpected type of array elements")?)
+    }
+}
+
+impl<T> Clone for JArray<T> {
+    fn clone(&self) -> Self {
+        JArray::new(self.handle.clone())
+    }
+}
+
+impl<T> From<JArray<T>> for JObject {
+    fn from(a: JArray<T>) -> Self {
+        JObject(a.handle)
+    }
+}
+
+// handles are equal if they refer to the same object
+macro_rules! handle_traits {
+    ($($name:ident$(<$t:ident>)?),*) => {
+        $(
+            impl$(<$t>)? PartialEq for $name$(<$t>)? {
+                fn eq(&self, other: &Self) -> bool {
+                    self.r() == other.r()
+                }
+            }
+
+            impl$(<$t>)? Eq for $name$(<$t>)? {}
+
+            impl$(<$t>)? std::fmt::Debug for $name$(<$t>)? {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.debug_tuple(stringify!($name)).field(&self.r()).finish()
+                }
+            }
+        )*
+    };
+}
+
+handle_traits!(JObject, JString, JArray<T>);
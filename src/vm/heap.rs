@@ -1,90 +1,986 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    any::Any,
+    cell::Cell,
+    collections::HashMap,
+    mem,
+    rc::{Rc, Weak},
+};
 
 use super::{
-    class::{Class, FieldDescriptor},
+    class::{Class, FieldDescriptor, ReferenceKind},
+    error::{JavaException, VMResult},
     method_area::MethodArea,
+    options::Collector,
     value::{MutValue, Value},
 };
 
-pub struct Heap(Vec<RefValue>);
+pub struct Heap {
+    // the old generation and the nursery, indexed by OLD and YOUNG.
+    // the mark & sweep collector allocates all values in the old generation
+    spaces: [Space; 2],
+    collector: Collector,
+    // content of string -> reference to the interned java.lang.String object
+    interned_strings: HashMap<String, usize>,
+    // references held by the host through handles (e.g. JObject), which are roots of GC while they are alive.
+    // they follow the referents moved by GC
+    handles: Vec<Weak<Cell<usize>>>,
+    // objects to be finalized when they become unreachable
+    finalizable: Vec<usize>,
+    // references whose referents have been cleared by GC, waiting to be enqueued, and unreachable objects waiting to be finalized.
+    // they are roots of GC until they are taken
+    pending_references: Vec<usize>,
+    pending_finalization: Vec<usize>,
+    // whether the next collection clears soft references, as the last resort before OutOfMemoryError
+    clear_soft_references: bool,
+    // total size of allocated values in bytes, and its limit
+    allocated: usize,
+    max_size: usize,
+    // GC is requested when the allocated size exceeds it, or the nursery is full
+    gc_threshold: usize,
+    nursery_full: bool,
+    // state of the generator of identity hash codes
+    hash_state: u32,
+}
+
+const OLD: usize = 0;
+const YOUNG: usize = 1;
+
+// contiguous region of slots. values are placed at free slots if any, otherwise at the bump pointer (`top`).
+// slots at or above `top` are free
+#[derive(Default)]
+struct Space {
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+    top: usize,
+    // total size of values in the space
+    size: usize,
+}
+
+#[derive(Default)]
+struct Slot {
+    // incremented when the value leaves the slot (freed or moved), so that references to the value can be detected as stale.
+    // thus the generation of a free slot has never been used by references
+    gen: u32,
+    size: usize,
+    // RefValue::Null if the slot is free (except for the slot of null)
+    value: RefValue,
+}
+
+impl Space {
+    // returns the index and the generation of the slot where the value is placed
+    fn place(&mut self, rv: RefValue, size: usize) -> (usize, u32) {
+        let idx = match self.free_slots.pop() {
+            Some(idx) => idx,
+            None => {
+                if self.top == self.slots.len() {
+                    self.slots.push(Slot::default());
+                }
+                self.top += 1;
+                self.top - 1
+            }
+        };
+        self.put(idx, rv, size)
+    }
+
+    fn put(&mut self, idx: usize, rv: RefValue, size: usize) -> (usize, u32) {
+        let slot = &mut self.slots[idx];
+        slot.size = size;
+        slot.value = rv;
+        self.size += size;
+        (idx, slot.gen)
+    }
+
+    // take the value out of the slot, leaving the slot free
+    fn take(&mut self, idx: usize) -> (RefValue, usize) {
+        let slot = &mut self.slots[idx];
+        slot.gen = slot.gen.wrapping_add(1);
+        self.size -= slot.size;
+        (mem::take(&mut slot.value), slot.size)
+    }
+
+    // values in the slots below the bump pointer, along with their indices
+    fn values(&self) -> impl Iterator<Item = (usize, &RefValue)> {
+        self.slots[..self.top]
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| !matches!(s.value, RefValue::Null))
+            .map(|(idx, s)| (idx, &s.value))
+    }
+}
+
+// seed of the xorshift generator of identity hash codes
+const HASH_SEED: u32 = 0x2545_F491;
+
+// initial allocated size that triggers GC
+const INITIAL_GC_THRESHOLD: usize = 4 * 1024 * 1024;
 
 impl Heap {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_options(super::options::DEFAULT_MAX_HEAP_SIZE, Collector::default())
+    }
+
+    pub fn with_options(max_size: usize, collector: Collector) -> Self {
         // Value::Reference(0) -> null
-        Heap(vec![RefValue::Null])
+        let old = Space {
+            slots: vec![Slot::default()],
+            top: 1,
+            ..Default::default()
+        };
+        Heap {
+            spaces: [old, Space::default()],
+            collector,
+            max_size,
+            ..Self::placeholder()
+        }
+    }
+
+    // heap without even the slot of null, which allocates nothing. it stands in for the heap in use while the latter is lent
+    pub fn placeholder() -> Self {
+        Heap {
+            spaces: Default::default(),
+            collector: Collector::default(),
+            interned_strings: HashMap::new(),
+            handles: Vec::new(),
+            finalizable: Vec::new(),
+            pending_references: Vec::new(),
+            pending_finalization: Vec::new(),
+            clear_soft_references: false,
+            allocated: 0,
+            max_size: 0,
+            gc_threshold: INITIAL_GC_THRESHOLD,
+            nursery_full: false,
+            hash_state: HASH_SEED,
+        }
+    }
+}
+
+// a reference is the index of the slot and the space of the slot, combined with the generation of the slot in the upper bits.
+// null is the reference to the slot 0 of the old generation of generation 0.
+// the generation takes the upper 32 bits, so references need 64-bit usize.
+#[cfg(not(target_pointer_width = "64"))]
+compile_error!("kafa supports only 64-bit targets, as references are encoded in 64 bits");
+
+const GEN_SHIFT: u32 = 32;
+const SPACE_SHIFT: u32 = 31;
+const INDEX_MASK: usize = (1 << SPACE_SHIFT) - 1;
+
+fn encode_ref(space: usize, idx: usize, gen: u32) -> usize {
+    ((gen as usize) << GEN_SHIFT) | (space << SPACE_SHIFT) | idx
+}
+
+fn decode_ref(r: usize) -> (usize, usize, u32) {
+    let space = (r >> SPACE_SHIFT) & 1;
+    (space, r & INDEX_MASK, (r >> GEN_SHIFT) as u32)
+}
+
+// sizes of values on the heap, following the layout of 64-bit HotSpot with compressed oops:
+// a header, followed by fields or elements, aligned to 8 bytes
+const OBJECT_HEADER_SIZE: usize = 12;
+const ARRAY_HEADER_SIZE: usize = 16;
+const ALIGNMENT: usize = 8;
+
+// arrays can't be longer than this, regardless of the heap size
+const MAX_ARRAY_LENGTH: i32 = i32::MAX - 2;
+
+fn field_size(desc: &str) -> usize {
+    match desc {
+        "Z" | "B" => 1,
+        "C" | "S" => 2,
+        "J" | "D" => 8,
+        // int, float and references
+        _ => 4,
+    }
+}
+
+fn aligned(size: usize) -> usize {
+    size.div_ceil(ALIGNMENT) * ALIGNMENT
+}
+
+// size of instances of the class, including fields declared in its superclasses
+pub fn instance_size(cls: &Rc<Class>, meth_area: &MethodArea) -> VMResult<usize> {
+    let mut classes = meth_area.collect_all_superclasses(&cls.name)?;
+    classes.push(cls.clone());
+    let fields_size = classes
+        .iter()
+        .flat_map(|c| c.instance_fields())
+        .map(|f| field_size(&f.descriptor))
+        .sum::<usize>();
+    Ok(aligned(OBJECT_HEADER_SIZE + fields_size))
+}
+
+// size of the array. the length is checked here, before elements are allocated on the host
+pub fn array_size(len: i32, item_desc: &str) -> VMResult<usize> {
+    if len < 0 {
+        Err(JavaException::negative_array_size_exception(
+            len.to_string(),
+        ))?;
+    }
+    if len > MAX_ARRAY_LENGTH {
+        Err(JavaException::out_of_memory_error(
+            "Requested array size exceeds VM limit",
+        ))?;
     }
+    Ok(aligned(
+        ARRAY_HEADER_SIZE + len as usize * field_size(item_desc),
+    ))
 }
 
 impl Heap {
-    pub fn alloc_object(&mut self, class: Rc<Class>, meth_area: &mut MethodArea) -> Value {
-        let obj = Object::new(class, meth_area);
-        self.alloc_ref_val(RefValue::Object(obj))
+    pub fn alloc_object(
+        &mut self,
+        class: Rc<Class>,
+        meth_area: &mut MethodArea,
+    ) -> VMResult<Value> {
+        let size = instance_size(&class, meth_area)?;
+        self.reserve(size)?;
+        let obj = Object::new(class, meth_area)?;
+        Ok(self.alloc_instance(obj, size))
     }
 
-    pub fn alloc_array(&mut self, len: u32, item_desc: &str) -> Value {
-        let arr = new_array_of_type(len, item_desc);
-        self.alloc_ref_val(RefValue::Array(arr))
+    // shallow copy of the object, which has its own identity
+    pub fn clone_object(&mut self, r: usize) -> VMResult<Value> {
+        let size = self.size_of(r);
+        let copy = match self.get_ref(r) {
+            Some(RefValue::Object(obj)) => RefValue::Object(obj.shallow_clone()),
+            Some(RefValue::Array(arr)) => RefValue::Array(arr.shallow_clone()),
+            _ => return Err("referent not found on heap")?,
+        };
+        self.reserve(size)?;
+        match copy {
+            RefValue::Object(obj) => Ok(self.alloc_instance(obj, size)),
+            rv => Ok(self.alloc_ref_val(rv, size)),
+        }
     }
 
-    fn alloc_ref_val(&mut self, rv: RefValue) -> Value {
-        self.0.push(rv);
+    fn alloc_instance(&mut self, obj: Object, size: usize) -> Value {
+        let has_finalizer = obj.class.has_finalizer;
+        let v = self.alloc_ref_val(RefValue::Object(obj), size);
+        if let (true, Value::Reference(r)) = (has_finalizer, v) {
+            self.finalizable.push(r);
+        }
+        v
+    }
 
-        // reference to allocated value = index of the value in underlying vec
-        Value::Reference(self.0.len() - 1)
+    pub fn alloc_array(&mut self, len: i32, item_desc: &str) -> VMResult<Value> {
+        let size = array_size(len, item_desc)?;
+        self.reserve(size)?;
+        let arr = new_array_of_type(len as u32, item_desc);
+        Ok(self.alloc_ref_val(RefValue::Array(arr), size))
+    }
+
+    // whether `size` bytes can be allocated without exceeding the limit
+    pub fn has_room(&self, size: usize) -> bool {
+        self.allocated + size <= self.max_size
+    }
+
+    fn reserve(&mut self, size: usize) -> VMResult<()> {
+        if !self.has_room(size) {
+            Err(JavaException::out_of_memory_error("Java heap space"))?;
+        }
+        self.allocated += size;
+        Ok(())
+    }
+
+    // run `f` ignoring the limit of the heap size.
+    // objects of exceptions are created in it, so that OutOfMemoryError can be thrown even if the heap is exhausted
+    pub fn without_limit<T>(&mut self, f: impl FnOnce(&mut Heap) -> T) -> T {
+        let max_size = mem::replace(&mut self.max_size, usize::MAX);
+        let res = f(self);
+        self.max_size = max_size;
+        res
+    }
+
+    fn alloc_ref_val(&mut self, rv: RefValue, size: usize) -> Value {
+        let space = match self.collector {
+            Collector::MarkSweep => OLD,
+            Collector::Generational { nursery_size } => {
+                if self.spaces[YOUNG].size + size <= nursery_size {
+                    YOUNG
+                } else {
+                    // values larger than the nursery are allocated in the old generation directly.
+                    // otherwise the nursery is full, and values go to the old generation until the next GC
+                    self.nursery_full |= size <= nursery_size;
+                    OLD
+                }
+            }
+        };
+        let (idx, gen) = self.spaces[space].place(rv, size);
+        Value::Reference(encode_ref(space, idx, gen))
     }
 }
 
 impl Heap {
+    fn slot(&self, r: usize) -> Option<&Slot> {
+        let (space, idx, gen) = decode_ref(r);
+        self.spaces[space].slots.get(idx).filter(|s| s.gen == gen)
+    }
+
+    // get the value referenced by `r`. returns None if the reference is invalid or stale (i.e. the value has been freed or moved)
     pub fn get(&mut self, r: usize) -> Option<&mut RefValue> {
-        self.0.get_mut(r)
+        let (space, idx, gen) = decode_ref(r);
+        self.spaces[space]
+            .slots
+            .get_mut(idx)
+            .filter(|s| s.gen == gen)
+            .map(|s| &mut s.value)
+    }
+
+    pub fn get_ref(&self, r: usize) -> Option<&RefValue> {
+        self.slot(r).map(|s| &s.value)
+    }
+
+    // all the values on the heap (including unreachable ones not collected yet), along with references to them and their sizes
+    pub fn entries(&self) -> impl Iterator<Item = (usize, &RefValue, usize)> {
+        self.spaces.iter().enumerate().flat_map(|(space, s)| {
+            s.values().map(move |(idx, rv)| {
+                let slot = &s.slots[idx];
+                (encode_ref(space, idx, slot.gen), rv, slot.size)
+            })
+        })
+    }
+
+    // identity hash code of the value. it is assigned at the first request, and kept even if GC moves the value
+    pub fn identity_hash_code(&mut self, r: usize) -> Option<i32> {
+        let hash = self.get_ref(r)?.header()?.hash.get();
+        if hash != 0 {
+            return Some(hash);
+        }
+        let hash = self.next_hash();
+        self.get_ref(r)?.header()?.hash.set(hash);
+        Some(hash)
+    }
+
+    // next non-zero 31-bit value of the xorshift generator, like HotSpot
+    fn next_hash(&mut self) -> i32 {
+        loop {
+            let mut x = self.hash_state;
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            self.hash_state = x;
+            let hash = (x & 0x7FFF_FFFF) as i32;
+            if hash != 0 {
+                return hash;
+            }
+        }
+    }
+
+    // size of the value referenced by `r` in bytes, or 0 if the reference is invalid or stale
+    pub fn size_of(&self, r: usize) -> usize {
+        self.slot(r).map(|s| s.size).unwrap_or(0)
+    }
+
+    // references held by the host through handles
+    pub fn handle_references(&self) -> impl Iterator<Item = usize> + '_ {
+        self.handles
+            .iter()
+            .filter_map(|h| h.upgrade())
+            .map(|h| h.get())
+    }
+
+    pub fn interned_string_references(&self) -> impl Iterator<Item = usize> + '_ {
+        self.interned_strings.values().copied()
+    }
+
+    // create a handle of the reference for the host, which keeps the referent alive while it exists
+    pub fn new_handle(&mut self, r: usize) -> VMResult<Handle> {
+        if r == 0 || matches!(self.get_ref(r), None | Some(RefValue::Null)) {
+            Err(format!("invalid or stale reference: {r:#x}"))?;
+        }
+        let h = Rc::new(Cell::new(r));
+        self.handles.push(Rc::downgrade(&h));
+        Ok(Handle(h))
+    }
+}
+
+// reference to a value on the heap held by the host.
+// the reference is shared with the heap, so that it can be traced (and updated when the value is moved) by GC
+#[derive(Clone)]
+pub struct Handle(Rc<Cell<usize>>);
+
+impl Handle {
+    pub fn get(&self) -> usize {
+        self.0.get()
+    }
+}
+
+/// Statistics of a garbage collection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// Number of objects (and arrays) freed.
+    pub freed_objects: usize,
+    pub freed_bytes: usize,
+    /// Number of objects copied from the nursery to the old generation by [`Collector::Generational`].
+    pub promoted_objects: usize,
+    /// Number of references (e.g. `WeakReference`) whose referents were cleared.
+    pub cleared_references: usize,
+    /// Number of unreachable objects kept alive to be finalized.
+    pub finalizable_objects: usize,
+    /// Number of objects (and arrays) that survived.
+    pub live_objects: usize,
+    pub live_bytes: usize,
+}
+
+// new locations of the values moved by GC. holders of references must follow them
+#[derive(Default)]
+pub struct Forwarding(HashMap<usize, usize>);
+
+impl Forwarding {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn forward(&self, r: usize) -> usize {
+        self.0.get(&r).copied().unwrap_or(r)
+    }
+
+    pub fn forward_value(&self, v: Value) -> Value {
+        match v {
+            Value::Reference(r) => Value::Reference(self.forward(r)),
+            v => v,
+        }
+    }
+
+    // compose with the forwarding by the later collection
+    fn then(mut self, later: Forwarding) -> Forwarding {
+        for r in self.0.values_mut() {
+            *r = later.forward(*r);
+        }
+        self.0.extend(later.0);
+        self
+    }
+}
+
+impl Heap {
+    pub fn gc_requested(&self) -> bool {
+        self.allocated > self.gc_threshold || self.nursery_full
+    }
+
+    // let the next collection clear referents of all soft references
+    pub fn clear_soft_references(&mut self) {
+        self.clear_soft_references = true;
+    }
+
+    // whether there are references to be enqueued or objects to be finalized
+    pub fn has_pending(&self) -> bool {
+        !self.pending_references.is_empty() || !self.pending_finalization.is_empty()
+    }
+
+    pub fn pop_pending_reference(&mut self) -> Option<usize> {
+        self.pending_references.pop()
+    }
+
+    pub fn pop_pending_finalization(&mut self) -> Option<usize> {
+        self.pending_finalization.pop()
+    }
+
+    pub fn pending_references(&self) -> impl Iterator<Item = usize> + '_ {
+        self.pending_references
+            .iter()
+            .chain(&self.pending_finalization)
+            .copied()
+    }
+
+    // free values unreachable from the roots. interned strings and values held by handles are also roots.
+    // the generational collector collects the nursery only, unless `full` is set or the old generation has grown enough.
+    pub fn collect(
+        &mut self,
+        roots: impl IntoIterator<Item = usize>,
+        full: bool,
+    ) -> (GcStats, Forwarding) {
+        self.handles.retain(|h| h.strong_count() > 0);
+
+        let mut roots = roots.into_iter().collect::<Vec<_>>();
+        roots.extend(self.interned_string_references());
+        roots.extend(self.handle_references());
+        roots.extend(self.pending_references());
+
+        let mut stats = GcStats::default();
+        let fwd = match self.collector {
+            Collector::MarkSweep => {
+                self.sweep(&roots, &mut stats);
+                Forwarding::default()
+            }
+            Collector::Generational { .. } => {
+                // finalizable objects survive minor GCs, and are finalized by major ones
+                let nursery_roots = roots.iter().chain(&self.finalizable).copied();
+                let mut fwd = self.copy_nursery(&nursery_roots.collect::<Vec<_>>(), &mut stats);
+                self.forward_references(&fwd);
+                if full || self.allocated - stats.freed_bytes > self.gc_threshold {
+                    let roots = roots.iter().map(|&r| fwd.forward(r)).collect::<Vec<_>>();
+                    let compacted = self.compact(&roots, &mut stats);
+                    self.forward_references(&compacted);
+                    fwd = fwd.then(compacted);
+                }
+                fwd
+            }
+        };
+
+        for space in &self.spaces {
+            stats.live_objects += space.values().count();
+            stats.live_bytes += space.size;
+        }
+        self.allocated -= stats.freed_bytes;
+        self.gc_threshold = INITIAL_GC_THRESHOLD.max(self.allocated * 2);
+        self.nursery_full = false;
+        (stats, fwd)
+    }
+
+    // free all the values, and release the handles held by the host.
+    // the generations of the slots are kept, so that references to the freed values (including ones held by the handles) are detected as stale
+    pub fn clear(&mut self) {
+        for space in &mut self.spaces {
+            let idxs = space.values().map(|(idx, _)| idx).collect::<Vec<_>>();
+            for idx in idxs {
+                space.take(idx);
+            }
+            // slots below the bump pointer have been used, so their generations have been incremented
+            space.free_slots.clear();
+        }
+        // the slot 0 of the old generation is for null
+        self.spaces[OLD].top = 1;
+        self.spaces[YOUNG].top = 0;
+
+        self.interned_strings.clear();
+        self.handles.clear();
+        self.finalizable.clear();
+        self.pending_references.clear();
+        self.pending_finalization.clear();
+        self.clear_soft_references = false;
+        self.allocated = 0;
+        self.gc_threshold = INITIAL_GC_THRESHOLD;
+        self.nursery_full = false;
+    }
+
+    // update references held by the heap itself to values moved by GC
+    fn forward_references(&mut self, fwd: &Forwarding) {
+        for r in self.interned_strings.values_mut() {
+            *r = fwd.forward(*r);
+        }
+        for h in self.handles.iter().filter_map(|h| h.upgrade()) {
+            h.set(fwd.forward(h.get()));
+        }
+        for r in self
+            .finalizable
+            .iter_mut()
+            .chain(&mut self.pending_references)
+            .chain(&mut self.pending_finalization)
+        {
+            *r = fwd.forward(*r);
+        }
+    }
+
+    // mark values in the old generation reachable from the roots. the nursery must be empty.
+    // referents only weakly reachable are cleared, and unreachable finalizable objects are marked again to be finalized
+    fn mark(&mut self, roots: &[usize], stats: &mut GcStats) -> Vec<bool> {
+        let mut marked = vec![false; self.spaces[OLD].slots.len()];
+        marked[0] = true;
+        let clear_soft = mem::take(&mut self.clear_soft_references);
+        let mut discovered = Vec::new();
+        self.trace(roots.to_vec(), &mut marked, &mut discovered, clear_soft);
+
+        // soft & weak references are cleared before finalization, while phantom ones are cleared after that
+        use ReferenceKind::*;
+        self.clear_references(&marked, &mut discovered, &[Soft, Weak], stats);
+        let (unreachable, finalizable) = self
+            .finalizable
+            .iter()
+            .partition::<Vec<_>, _>(|&&r| !is_marked(&marked, r));
+        self.finalizable = finalizable;
+        stats.finalizable_objects += unreachable.len();
+        self.trace(
+            unreachable.clone(),
+            &mut marked,
+            &mut discovered,
+            clear_soft,
+        );
+        self.pending_finalization.extend(unreachable);
+        self.clear_references(&marked, &mut discovered, &[Soft, Weak, Phantom], stats);
+        marked
+    }
+
+    // mark values reachable from the worklist, except via referents of references. such references are discovered
+    fn trace(
+        &self,
+        mut worklist: Vec<usize>,
+        marked: &mut [bool],
+        discovered: &mut Vec<usize>,
+        clear_soft: bool,
+    ) {
+        while let Some(r) = worklist.pop() {
+            let (space, idx, _) = decode_ref(r);
+            if space != OLD || marked.get(idx).is_none_or(|&m| m) {
+                continue;
+            }
+            let Some(slot) = self.slot(r) else {
+                continue;
+            };
+            marked[idx] = true;
+            match &slot.value {
+                // referents of soft references are kept unless the heap is exhausted
+                RefValue::Object(obj)
+                    if obj
+                        .class
+                        .reference_kind
+                        .is_some_and(|k| k != ReferenceKind::Soft || clear_soft) =>
+                {
+                    discovered.push(r);
+                    obj.fields
+                        .iter()
+                        .filter(|(id, _)| !id.is_referent())
+                        .for_each(|(_, v)| match v.get() {
+                            Value::Reference(r) if r != 0 => worklist.push(r),
+                            _ => {}
+                        });
+                }
+                rv => rv.for_each_reference(|r| worklist.push(r)),
+            }
+        }
+    }
+
+    // clear the referents of the discovered references of `kinds` if they are not marked, and make the references pending.
+    // references of `kinds` are removed from `discovered`
+    fn clear_references(
+        &mut self,
+        marked: &[bool],
+        discovered: &mut Vec<usize>,
+        kinds: &[ReferenceKind],
+        stats: &mut GcStats,
+    ) {
+        let mut cleared = Vec::new();
+        discovered.retain(|&r| {
+            let Some(RefValue::Object(obj)) = self.slot(r).map(|s| &s.value) else {
+                return false;
+            };
+            if !obj.class.reference_kind.is_some_and(|k| kinds.contains(&k)) {
+                return true;
+            }
+            if let Some(referent) = obj.get_field(REFERENCE_CLASS, "referent") {
+                if let Value::Reference(x @ 1..) = referent.get() {
+                    if !is_marked(marked, x) {
+                        referent.put(Value::Reference(0));
+                        cleared.push(r);
+                    }
+                }
+            }
+            false
+        });
+        stats.cleared_references += cleared.len();
+        self.pending_references.extend(cleared);
+    }
+
+    // mark & sweep: free unmarked values in place
+    fn sweep(&mut self, roots: &[usize], stats: &mut GcStats) {
+        let marked = self.mark(roots, stats);
+        let old = &mut self.spaces[OLD];
+        for (idx, &live) in marked.iter().enumerate().take(old.top).skip(1) {
+            if live || matches!(old.slots[idx].value, RefValue::Null) {
+                continue;
+            }
+            let (_, size) = old.take(idx);
+            stats.freed_objects += 1;
+            stats.freed_bytes += size;
+            old.free_slots.push(idx);
+        }
+    }
+
+    // minor GC: copy values in the nursery reachable from the roots to the old generation, then empty the nursery.
+    // there is no remembered set, so references held by all the values in the old generation are also roots
+    fn copy_nursery(&mut self, roots: &[usize], stats: &mut GcStats) -> Forwarding {
+        let mut fwd = HashMap::new();
+        let mut worklist = roots.to_vec();
+        for (_, rv) in self.spaces[OLD].values() {
+            rv.for_each_reference(|r| worklist.push(r));
+        }
+
+        while let Some(r) = worklist.pop() {
+            let (space, idx, _) = decode_ref(r);
+            if space != YOUNG || fwd.contains_key(&r) || self.slot(r).is_none() {
+                continue;
+            }
+            let (rv, size) = self.spaces[YOUNG].take(idx);
+            rv.for_each_reference(|r| worklist.push(r));
+            let (new_idx, new_gen) = self.spaces[OLD].place(rv, size);
+            fwd.insert(r, encode_ref(OLD, new_idx, new_gen));
+            stats.promoted_objects += 1;
+        }
+
+        // values left in the nursery are garbage
+        let young = &mut self.spaces[YOUNG];
+        for idx in 0..young.top {
+            if !matches!(young.slots[idx].value, RefValue::Null) {
+                let (_, size) = young.take(idx);
+                stats.freed_objects += 1;
+                stats.freed_bytes += size;
+            }
+        }
+        young.top = 0;
+
+        let fwd = Forwarding(fwd);
+        for (_, rv) in self.spaces[OLD].values() {
+            rv.update_references(|r| fwd.forward(r));
+        }
+        fwd
+    }
+
+    // major GC: slide values in the old generation reachable from the roots toward the start of the space.
+    // the nursery must be empty
+    fn compact(&mut self, roots: &[usize], stats: &mut GcStats) -> Forwarding {
+        let marked = self.mark(roots, stats);
+        let old = &mut self.spaces[OLD];
+        let mut fwd = HashMap::new();
+        let mut dest = 1;
+        for (idx, &live) in marked.iter().enumerate().take(old.top).skip(1) {
+            if matches!(old.slots[idx].value, RefValue::Null) {
+                continue;
+            }
+            if !live {
+                let (_, size) = old.take(idx);
+                stats.freed_objects += 1;
+                stats.freed_bytes += size;
+                continue;
+            }
+            if idx != dest {
+                // the value at `dest` has been freed or moved already
+                let gen = old.slots[idx].gen;
+                let (rv, size) = old.take(idx);
+                let (_, new_gen) = old.put(dest, rv, size);
+                fwd.insert(encode_ref(OLD, idx, gen), encode_ref(OLD, dest, new_gen));
+            }
+            dest += 1;
+        }
+        old.top = dest;
+        old.free_slots.clear();
+
+        let fwd = Forwarding(fwd);
+        for (_, rv) in self.spaces[OLD].values() {
+            rv.update_references(|r| fwd.forward(r));
+        }
+        fwd
+    }
+}
+
+const REFERENCE_CLASS: &str = "java/lang/ref/Reference";
+
+fn is_marked(marked: &[bool], r: usize) -> bool {
+    let (space, idx, _) = decode_ref(r);
+    space == OLD && marked.get(idx).is_some_and(|&m| m)
+}
+
+// java.lang.String support
+// (compact string representation since JDK 9: `byte[] value` + `byte coder`)
+impl Heap {
+    const STRING_CLASS: &str = "java/lang/String";
+    const CODER_LATIN1: i32 = 0;
+    const CODER_UTF16: i32 = 1;
+
+    // allocate a java.lang.String object whose content is `s`
+    pub fn alloc_string(&mut self, s: &str, meth_area: &mut MethodArea) -> VMResult<Value> {
+        let str_cls = meth_area.resolve_class(Self::STRING_CLASS)?;
+
+        let (bytes, coder) = if s.chars().all(|c| (c as u32) <= 0xFF) {
+            let bytes = s.chars().map(|c| c as u8).collect::<Vec<_>>();
+            (bytes, Self::CODER_LATIN1)
+        } else {
+            // UTF-16 chars are stored in native byte order
+            let bytes = s
+                .encode_utf16()
+                .flat_map(|c| c.to_ne_bytes())
+                .collect::<Vec<_>>();
+            (bytes, Self::CODER_UTF16)
+        };
+
+        let value @ Value::Reference(arr_ref) = self.alloc_array(bytes.len() as i32, "B")? else {
+            unreachable!()
+        };
+        let Some(RefValue::Array(arr)) = self.get(arr_ref) else {
+            unreachable!()
+        };
+        for (i, b) in bytes.into_iter().enumerate() {
+            arr.put(i as u32, Value::Int(b as i8 as i32));
+        }
+
+        let s @ Value::Reference(str_ref) = self.alloc_object(str_cls, meth_area)? else {
+            unreachable!()
+        };
+        let Some(RefValue::Object(obj)) = self.get(str_ref) else {
+            unreachable!()
+        };
+        let fields = [("value", value), ("coder", Value::Int(coder))];
+        for (name, v) in fields {
+            let Some(f) = obj.get_field(Self::STRING_CLASS, name) else {
+                return Err(format!(
+                    "unexpected layout of java.lang.String: no field '{name}'"
+                ))?;
+            };
+            f.put(v);
+        }
+        Ok(s)
+    }
+
+    // get the interned java.lang.String object whose content is `s`, allocating it if it doesn't exist yet.
+    // string literals and string constants are interned (cf. JVM spec 5.1).
+    pub fn intern_string(&mut self, s: &str, meth_area: &mut MethodArea) -> VMResult<Value> {
+        if let Some(r) = self.interned_strings.get(s) {
+            return Ok(Value::Reference(*r));
+        }
+        let v @ Value::Reference(r) = self.alloc_string(s, meth_area)? else {
+            unreachable!()
+        };
+        self.interned_strings.insert(s.to_string(), r);
+        Ok(v)
+    }
+
+    // get the content of the java.lang.String object referenced by `r`
+    pub fn get_string(&mut self, r: usize) -> VMResult<String> {
+        let Some(RefValue::Object(obj)) = self.get(r) else {
+            return Err("referent is not a object")?;
+        };
+        if obj.get_class().name != Self::STRING_CLASS {
+            return Err("referent is not a java.lang.String")?;
+        }
+        let (Some(value), Some(coder)) = (
+            obj.get_field(Self::STRING_CLASS, "value"),
+            obj.get_field(Self::STRING_CLASS, "coder"),
+        ) else {
+            return Err("unexpected layout of java.lang.String")?;
+        };
+        let Value::Reference(arr_ref) = value.get() else {
+            return Err("unexpected layout of java.lang.String")?;
+        };
+        let coder = match coder.get() {
+            Value::Int(c) => c,
+            Value::Byte(c) => c as i32, // default value of byte field
+            _ => return Err("unexpected layout of java.lang.String")?,
+        };
+
+        let Some(RefValue::Array(arr)) = self.get(arr_ref) else {
+            return Err("value of java.lang.String is not an array")?;
+        };
+        let bytes = (0..arr.len())
+            .filter_map(|i| match arr.get(i) {
+                Some(Value::Int(b)) => Some(b as u8),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let s = if coder == Self::CODER_LATIN1 {
+            bytes.into_iter().map(|b| b as char).collect()
+        } else {
+            let utf16 = bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16_lossy(&utf16)
+        };
+        Ok(s)
     }
 }
 
+#[derive(Default)]
 pub enum RefValue {
     Object(Object),
     Array(Box<dyn JavaArray>),
+    #[default]
     Null,
 }
 
 impl RefValue {
-    const SUPERTYPES_OF_ARRAY: [&str; 3] = [
-        "java/lang/Object",
-        "java/lang/Cloneable",
-        "java/io/Serializable",
-    ];
-    pub fn is_instance_of(&self, target_cls_name: &str, meth_area: &MethodArea) -> bool {
+    // non-null references held by the value, labeled with names of fields or indices of elements
+    pub fn labeled_references(&self) -> Vec<(String, usize)> {
+        let non_null = |v: Value| match v {
+            Value::Reference(r) if r != 0 => Some(r),
+            _ => None,
+        };
         match self {
-            RefValue::Object(obj) => meth_area.is_subclass_of(&obj.class.name, target_cls_name),
-            RefValue::Array(arr) => {
-                let arr_desc = arr.descriptor();
-                let mut sc = arr_desc.as_str();
-                let mut tc = target_cls_name;
-
-                // peel array prefix ('[') one by one until either of them hit non-array component
-                while sc.starts_with("[") && tc.starts_with("[") {
-                    sc = &sc[1..];
-                    tc = &tc[1..];
-                }
+            RefValue::Object(obj) => {
+                let mut refs = obj
+                    .fields
+                    .iter()
+                    .filter_map(|(id, v)| non_null(v.get()).map(|r| (id.name.clone(), r)))
+                    .collect::<Vec<_>>();
+                refs.sort();
+                refs
+            }
+            RefValue::Array(arr) => (0..arr.len())
+                .filter_map(|i| non_null(arr.get(i)?).map(|r| (format!("[{i}]"), r)))
+                .collect(),
+            RefValue::Null => Vec::new(),
+        }
+    }
 
-                // SC is array type -> TC is a supertype of any array type?
-                if sc.starts_with("[") && tc.starts_with("L") {
-                    let targ_cls_name = &tc[1..]; // remove prefix 'L'
-                    return RefValue::SUPERTYPES_OF_ARRAY
-                        .iter()
-                        .any(|st| targ_cls_name == *st);
+    pub fn header(&self) -> Option<&ObjectHeader> {
+        match self {
+            RefValue::Object(obj) => Some(&obj.header),
+            RefValue::Array(arr) => Some(arr.header()),
+            RefValue::Null => None,
+        }
+    }
+
+    // name of the class of the value. arrays are named by their descriptors (e.g. "[I")
+    pub fn class_name(&self) -> String {
+        match self {
+            RefValue::Object(obj) => obj.class.name.clone(),
+            RefValue::Array(arr) => arr.descriptor().as_str().to_string(),
+            RefValue::Null => String::new(),
+        }
+    }
+
+    // call `f` with each non-null reference held by the value
+    fn for_each_reference(&self, mut f: impl FnMut(usize)) {
+        self.update_references(|r| {
+            f(r);
+            r
+        })
+    }
+
+    // replace each non-null reference held by the value with the result of `f`
+    fn update_references(&self, mut f: impl FnMut(usize) -> usize) {
+        let mut update = |v: &MutValue| {
+            if let Value::Reference(r) = v.get() {
+                if r != 0 {
+                    v.put(Value::Reference(f(r)))
                 }
-                // TC and SC are reference types -> SC can be cast to TC?
-                if sc.starts_with("L") && tc.starts_with("L") {
-                    return meth_area.is_subclass_of(&sc[1..], &tc[1..]);
+            }
+        };
+        match self {
+            RefValue::Object(obj) => obj.fields.values().for_each(update),
+            RefValue::Array(arr) => {
+                if let Some(arr) = arr.as_any().downcast_ref::<JavaReferenceArray>() {
+                    arr.data.iter().for_each(&mut update);
                 }
-                // TC and SC are the same primitive type?
-                sc.len() == 1 && tc.len() == 1 && sc == tc
             }
-            RefValue::Null => false,
+            RefValue::Null => {}
+        }
+    }
+
+    // `target_cls_name` is a class name for classes and interfaces, or a descriptor for array types (e.g. "[Ljava/lang/String;")
+    pub fn is_instance_of(
+        &self,
+        target_cls_name: &str,
+        meth_area: &mut MethodArea,
+    ) -> VMResult<bool> {
+        match self {
+            RefValue::Object(obj) => meth_area.is_assignable(&obj.class.name, target_cls_name),
+            RefValue::Array(arr) => {
+                meth_area.is_assignable(arr.descriptor().as_str(), target_cls_name)
+            }
+            RefValue::Null => Ok(false),
         }
     }
 }
 
+// class name (or array descriptor) of the type denoted by the descriptor of array components.
+// None for primitive types
+pub fn component_class_name(comp_desc: &str) -> Option<&str> {
+    match comp_desc.strip_prefix('L') {
+        Some(cls) => cls.strip_suffix(';'),
+        None if comp_desc.starts_with('[') => Some(comp_desc),
+        None => None,
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct InstanceFieldIdent {
     class_name: String,
@@ -98,21 +994,31 @@ impl InstanceFieldIdent {
             name: name.to_string(),
         }
     }
+
+    // java.lang.ref.Reference.referent, which GC doesn't trace as a strong reference
+    fn is_referent(&self) -> bool {
+        self.class_name == REFERENCE_CLASS && self.name == "referent"
+    }
+}
+
+// header of values on the heap. it moves along with the value
+#[derive(Default)]
+pub struct ObjectHeader {
+    // identity hash code (0 if not assigned yet)
+    hash: Cell<i32>,
 }
 
 pub struct Object {
+    header: ObjectHeader,
     class: Rc<Class>,
     fields: HashMap<InstanceFieldIdent, MutValue>,
 }
 
 impl Object {
-    fn new(base_cls: Rc<Class>, meth_area: &mut MethodArea) -> Self {
+    fn new(base_cls: Rc<Class>, meth_area: &mut MethodArea) -> VMResult<Self> {
         let mut fields = HashMap::new();
 
-        let mut classes = meth_area
-            .collect_all_superclasses(&base_cls.name)
-            .inspect_err(|err| eprintln!("{}", err))
-            .unwrap();
+        let mut classes = meth_area.collect_all_superclasses(&base_cls.name)?;
         classes.push(base_cls.clone());
 
         classes.into_iter().for_each(|cls| {
@@ -123,9 +1029,18 @@ impl Object {
             }
         });
 
-        Object {
+        Ok(Object {
+            header: ObjectHeader::default(),
             class: base_cls,
             fields,
+        })
+    }
+
+    fn shallow_clone(&self) -> Object {
+        Object {
+            header: ObjectHeader::default(),
+            class: self.class.clone(),
+            fields: self.fields.clone(),
         }
     }
 
@@ -137,6 +1052,26 @@ impl Object {
         let id = InstanceFieldIdent::new(cls_name, fld_name);
         self.fields.get(&id)
     }
+
+    // find the instance field by its name, along with its descriptor.
+    // fields declared in the class of the object hide ones with the same name declared in superclasses.
+    pub fn find_field_by_name(
+        &self,
+        fld_name: &str,
+        meth_area: &mut MethodArea,
+    ) -> VMResult<Option<(String, &MutValue)>> {
+        let mut cls = self.class.clone();
+        loop {
+            if let Some(f) = cls.instance_fields().find(|f| f.name == fld_name) {
+                let desc = f.descriptor.clone();
+                return Ok(self.get_field(&cls.name, fld_name).map(|v| (desc, v)));
+            }
+            let Some(sc_name) = &cls.super_class else {
+                return Ok(None);
+            };
+            cls = meth_area.resolve_class(sc_name)?;
+        }
+    }
 }
 
 pub trait JavaArray {
@@ -144,6 +1079,27 @@ pub trait JavaArray {
     fn put(&mut self, idx: u32, v: Value);
     fn len(&self) -> u32;
     fn descriptor(&self) -> FieldDescriptor;
+    fn header(&self) -> &ObjectHeader;
+    // copy of the array with the same elements (not cloned deeply) and a fresh header
+    fn shallow_clone(&self) -> Box<dyn JavaArray>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl dyn JavaArray + '_ {
+    // borrow elements of the primitive array as a slice (None if the type of elements is not T).
+    // boolean[] can't be borrowed since its elements are packed into bits.
+    pub fn as_slice<T: 'static>(&self) -> Option<&[T]> {
+        self.as_any()
+            .downcast_ref::<JavaPrimitiveArray<T>>()
+            .map(|a| &*a.data)
+    }
+
+    pub fn as_mut_slice<T: 'static>(&mut self) -> Option<&mut [T]> {
+        self.as_any_mut()
+            .downcast_mut::<JavaPrimitiveArray<T>>()
+            .map(|a| &mut *a.data)
+    }
 }
 
 fn new_array_of_type(len: u32, item_desc: &str) -> Box<dyn JavaArray> {
@@ -165,6 +1121,7 @@ fn new_array_of_type(len: u32, item_desc: &str) -> Box<dyn JavaArray> {
 }
 
 struct JavaReferenceArray {
+    header: ObjectHeader,
     data: Box<[MutValue]>,
     l: u32,
     desc: FieldDescriptor,
@@ -178,6 +1135,7 @@ impl JavaReferenceArray {
         let data = data.into(); // Vec<MutValue> -> Box<[MutValue]]>
 
         let arr = JavaReferenceArray {
+            header: ObjectHeader::default(),
             // prepend '[' to item's descriptor
             desc: FieldDescriptor::new(format!("[{}", item_desc)),
             l: len,
@@ -203,9 +1161,31 @@ impl JavaArray for JavaReferenceArray {
     fn descriptor(&self) -> FieldDescriptor {
         self.desc.clone()
     }
+
+    fn header(&self) -> &ObjectHeader {
+        &self.header
+    }
+
+    fn shallow_clone(&self) -> Box<dyn JavaArray> {
+        Box::new(JavaReferenceArray {
+            header: ObjectHeader::default(),
+            data: self.data.clone(),
+            l: self.l,
+            desc: self.desc.clone(),
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 struct JavaPrimitiveArray<T> {
+    header: ObjectHeader,
     data: Box<[T]>,
     l: u32,
 }
@@ -218,7 +1198,11 @@ macro_rules! java_prim_array {
             #[allow(clippy::new_ret_no_self)]
             fn new(len: u32) -> Box<dyn JavaArray> {
                 let data = vec![$zero; len as usize].into();
-                Box::new(Self { data, l: len })
+                Box::new(Self {
+                    header: ObjectHeader::default(),
+                    data,
+                    l: len,
+                })
             }
         }
 
@@ -243,6 +1227,26 @@ macro_rules! java_prim_array {
             fn descriptor(&self) -> FieldDescriptor {
                 FieldDescriptor::new($desc.into())
             }
+
+            fn header(&self) -> &ObjectHeader {
+                &self.header
+            }
+
+            fn shallow_clone(&self) -> Box<dyn JavaArray> {
+                Box::new(Self {
+                    header: ObjectHeader::default(),
+                    data: self.data.clone(),
+                    l: self.l,
+                })
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
         }
     };
 }
@@ -257,6 +1261,7 @@ java_prim_array!(JavaDoubleArray, f64, 0.0, Value::Double, f64, "[D");
 
 // use compacted bitarray for boolean[]
 struct JavaBooleanArray {
+    header: ObjectHeader,
     data: Box<[u8]>,
     l: u32,
 }
@@ -267,7 +1272,11 @@ impl JavaBooleanArray {
         let n_bytes = if len == 0 { 0 } else { (len - 1) / 8 + 1 };
         let data = vec![0u8; n_bytes as usize];
         let data = data.into();
-        Box::new(Self { data, l: len })
+        Box::new(Self {
+            header: ObjectHeader::default(),
+            data,
+            l: len,
+        })
     }
 }
 
@@ -304,4 +1313,357 @@ impl JavaArray for JavaBooleanArray {
     fn descriptor(&self) -> FieldDescriptor {
         FieldDescriptor::new("[Z".into())
     }
+
+    fn header(&self) -> &ObjectHeader {
+        &self.header
+    }
+
+    fn shallow_clone(&self) -> Box<dyn JavaArray> {
+        Box::new(JavaBooleanArray {
+            header: ObjectHeader::default(),
+            data: self.data.clone(),
+            l: self.l,
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vm::options::DEFAULT_MAX_HEAP_SIZE;
+
+    #[test]
+    fn test_array_as_slice() {
+        let mut arr = new_array_of_type(3, "I");
+        arr.put(1, Value::Int(42));
+        assert_eq!(arr.as_slice::<i32>(), Some([0, 42, 0].as_slice()));
+        assert_eq!(arr.as_slice::<i64>(), None);
+
+        arr.as_mut_slice::<i32>().unwrap()[2] = 7;
+        assert_eq!(arr.get(2), Some(Value::Int(7)));
+
+        // boolean[] and arrays of references can't be borrowed
+        let bools = new_array_of_type(3, "Z");
+        assert!(bools.as_slice::<u8>().is_none());
+        let refs = new_array_of_type(3, "Ljava/lang/Object;");
+        assert!(refs.as_slice::<Value>().is_none());
+    }
+
+    #[test]
+    fn test_component_class_name() {
+        assert_eq!(
+            component_class_name("Ljava/lang/String;"),
+            Some("java/lang/String")
+        );
+        assert_eq!(component_class_name("[I"), Some("[I"));
+        assert_eq!(component_class_name("I"), None);
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut heap = Heap::new();
+        let alloc = |heap: &mut Heap, desc| match heap.alloc_array(2, desc).unwrap() {
+            Value::Reference(r) => r,
+            _ => unreachable!(),
+        };
+        // root -> child, and garbage
+        let root = alloc(&mut heap, "[I");
+        let child = alloc(&mut heap, "I");
+        let garbage = alloc(&mut heap, "I");
+        let Some(RefValue::Array(arr)) = heap.get(root) else {
+            unreachable!()
+        };
+        arr.put(0, Value::Reference(child));
+        let held = alloc(&mut heap, "J");
+        let handle = heap.new_handle(held).unwrap();
+
+        let (stats, _) = heap.collect([root], false);
+        assert_eq!(stats.freed_objects, 1);
+        assert_eq!(stats.live_objects, 3);
+        assert!(heap.get_ref(child).is_some());
+        // the reference to the freed value is stale, even if the slot is reused
+        assert!(heap.get_ref(garbage).is_none());
+        let reused = alloc(&mut heap, "I");
+        assert_ne!(reused, garbage);
+        assert_eq!(reused & INDEX_MASK, garbage & INDEX_MASK);
+        assert!(heap.get_ref(garbage).is_none());
+        assert!(heap.new_handle(garbage).is_err());
+
+        // values are freed once their handles are dropped
+        drop(handle);
+        let (stats, _) = heap.collect([], false);
+        assert_eq!(stats.freed_objects, 4);
+        assert!(heap.get_ref(held).is_none());
+        assert_eq!(heap.allocated, 0);
+    }
+
+    #[test]
+    fn test_collect_generational() {
+        let mut heap = Heap::with_options(
+            DEFAULT_MAX_HEAP_SIZE,
+            Collector::Generational { nursery_size: 1024 },
+        );
+        let alloc = |heap: &mut Heap, len, desc| match heap.alloc_array(len, desc).unwrap() {
+            Value::Reference(r) => r,
+            _ => unreachable!(),
+        };
+        // larger than the nursery -> allocated in the old generation
+        let large = alloc(&mut heap, 200, "J");
+        let root = alloc(&mut heap, 2, "[I");
+        let child = alloc(&mut heap, 2, "I");
+        let garbage = alloc(&mut heap, 2, "I");
+        let Some(RefValue::Array(arr)) = heap.get(root) else {
+            unreachable!()
+        };
+        arr.put(0, Value::Reference(child));
+        let handle = heap.new_handle(child).unwrap();
+
+        // minor GC: survivors in the nursery are promoted, and references to them are forwarded
+        let (stats, fwd) = heap.collect([root], false);
+        assert_eq!(stats.promoted_objects, 2);
+        assert_eq!(stats.freed_objects, 1);
+        assert_eq!(stats.live_objects, 3);
+        let (root, old_root) = (fwd.forward(root), root);
+        assert_ne!(root, old_root);
+        assert!(heap.get_ref(old_root).is_none());
+        assert!(heap.get_ref(garbage).is_none());
+        assert!(heap.get_ref(large).is_some());
+        let child = handle.get();
+        let Some(RefValue::Array(arr)) = heap.get_ref(root) else {
+            unreachable!()
+        };
+        assert_eq!(arr.get(0), Some(Value::Reference(child)));
+
+        // full GC: the unreachable large array is freed, and survivors slide into its place
+        let (stats, fwd) = heap.collect([root], true);
+        assert_eq!(stats.freed_objects, 1);
+        assert_eq!(stats.live_objects, 2);
+        assert!(heap.get_ref(large).is_none());
+        let (root, old_root) = (fwd.forward(root), root);
+        assert!(root & INDEX_MASK < old_root & INDEX_MASK);
+        assert!(heap.get_ref(old_root).is_none());
+        assert_ne!(handle.get(), child);
+        let Some(RefValue::Array(arr)) = heap.get_ref(root) else {
+            unreachable!()
+        };
+        assert_eq!(arr.get(0), Some(Value::Reference(handle.get())));
+        assert_eq!(heap.allocated, stats.live_bytes);
+    }
+
+    #[test]
+    fn test_identity_hash_code() {
+        let mut heap = Heap::with_options(
+            DEFAULT_MAX_HEAP_SIZE,
+            Collector::Generational { nursery_size: 1024 },
+        );
+        let alloc = |heap: &mut Heap| match heap.alloc_array(2, "I").unwrap() {
+            Value::Reference(r) => r,
+            _ => unreachable!(),
+        };
+        let a = alloc(&mut heap);
+        let b = alloc(&mut heap);
+        let hash_a = heap.identity_hash_code(a).unwrap();
+        let hash_b = heap.identity_hash_code(b).unwrap();
+        assert_ne!(hash_a, 0);
+        assert_ne!(hash_a, hash_b);
+        assert_eq!(heap.identity_hash_code(a), Some(hash_a));
+        assert_eq!(heap.identity_hash_code(0), None);
+
+        // the hash code is kept while the object is promoted and compacted
+        let handle = heap.new_handle(b).unwrap();
+        heap.collect([], false);
+        assert_ne!(handle.get(), b);
+        assert_eq!(heap.identity_hash_code(handle.get()), Some(hash_b));
+        heap.collect([], true);
+        assert_eq!(heap.identity_hash_code(handle.get()), Some(hash_b));
+    }
+
+    #[test]
+    fn test_clone_array() {
+        let mut heap = Heap::new();
+        for desc in ["Z", "I", "J", "Ljava/lang/Object;"] {
+            let Value::Reference(r) = heap.alloc_array(3, desc).unwrap() else {
+                unreachable!()
+            };
+            let v = match desc {
+                "Z" | "I" => Value::Int(1),
+                "J" => Value::Long(1),
+                _ => Value::Reference(r),
+            };
+            let Some(RefValue::Array(arr)) = heap.get(r) else {
+                unreachable!()
+            };
+            arr.put(1, v);
+            let hash = heap.identity_hash_code(r).unwrap();
+
+            let Value::Reference(c) = heap.clone_object(r).unwrap() else {
+                unreachable!()
+            };
+            assert_ne!(c, r);
+            assert_ne!(heap.identity_hash_code(c), Some(hash));
+            let Some(RefValue::Array(copy)) = heap.get(c) else {
+                unreachable!()
+            };
+            assert_eq!(copy.descriptor().to_string(), format!("[{desc}"));
+            assert_eq!(copy.len(), 3);
+            assert_eq!(copy.get(1), Some(v));
+            // the copy is independent of the original
+            copy.put(1, copy.get(0).unwrap());
+            let Some(RefValue::Array(arr)) = heap.get(r) else {
+                unreachable!()
+            };
+            assert_eq!(arr.get(1), Some(v));
+        }
+    }
+
+    #[test]
+    fn test_alloc_limit() {
+        let mut heap = Heap::with_options(1024, Collector::default());
+        let assert_exc = |res: VMResult<Value>, cls: &str, msg: &str| {
+            let err = res.unwrap_err();
+            let exc = err.downcast_ref::<JavaException>().unwrap();
+            assert_eq!(exc.class_name, cls);
+            assert_eq!(exc.message.as_deref(), Some(msg));
+        };
+        assert_exc(
+            heap.alloc_array(-1, "I"),
+            "java/lang/NegativeArraySizeException",
+            "-1",
+        );
+        // rejected regardless of the heap size
+        assert_exc(
+            heap.alloc_array(i32::MAX, "J"),
+            "java/lang/OutOfMemoryError",
+            "Requested array size exceeds VM limit",
+        );
+
+        // header (16 bytes) + elements, aligned to 8 bytes
+        assert_eq!(array_size(3, "B").unwrap(), 24);
+        assert_eq!(array_size(2, "Ljava/lang/Object;").unwrap(), 24);
+        assert_eq!(array_size(126, "J").unwrap(), 1024);
+        let Value::Reference(r) = heap.alloc_array(126, "J").unwrap() else {
+            unreachable!()
+        };
+        assert!(!heap.has_room(1));
+        assert_exc(
+            heap.alloc_array(0, "I"),
+            "java/lang/OutOfMemoryError",
+            "Java heap space",
+        );
+        assert!(heap.without_limit(|heap| heap.alloc_array(0, "I")).is_ok());
+
+        // room is made by freeing unreachable values
+        heap.collect([r], false);
+        assert!(!heap.has_room(1));
+        heap.collect([], false);
+        assert!(heap.has_room(1024));
+    }
+
+    #[test]
+    fn test_collect_references() {
+        for collector in [
+            Collector::MarkSweep,
+            Collector::Generational { nursery_size: 1024 },
+        ] {
+            let mut heap = Heap::with_options(DEFAULT_MAX_HEAP_SIZE, collector);
+            let alloc = |heap: &mut Heap| match heap.alloc_array(1, "I").unwrap() {
+                Value::Reference(r) => r,
+                _ => unreachable!(),
+            };
+            let alloc_ref = |heap: &mut Heap, kind, referent| {
+                let mut cls = Class::dummy();
+                cls.reference_kind = kind;
+                let referent_id = InstanceFieldIdent::new(REFERENCE_CLASS, "referent");
+                let obj = Object {
+                    header: ObjectHeader::default(),
+                    class: Rc::new(cls),
+                    fields: HashMap::from([(referent_id, MutValue::from_val(referent))]),
+                };
+                match heap.alloc_ref_val(RefValue::Object(obj), 16) {
+                    Value::Reference(r) => r,
+                    _ => unreachable!(),
+                }
+            };
+            let referent = |heap: &mut Heap, r| match heap.get(r) {
+                Some(RefValue::Object(obj)) => {
+                    obj.get_field(REFERENCE_CLASS, "referent").unwrap().get()
+                }
+                _ => unreachable!(),
+            };
+
+            let strong = alloc(&mut heap);
+            let weakly = alloc(&mut heap);
+            let softly = alloc(&mut heap);
+            let finalizable = alloc(&mut heap);
+            heap.finalizable.push(finalizable);
+            let weak = alloc_ref(
+                &mut heap,
+                Some(ReferenceKind::Weak),
+                Value::Reference(weakly),
+            );
+            let weak_to_strong = alloc_ref(
+                &mut heap,
+                Some(ReferenceKind::Weak),
+                Value::Reference(strong),
+            );
+            let soft = alloc_ref(
+                &mut heap,
+                Some(ReferenceKind::Soft),
+                Value::Reference(softly),
+            );
+            let phantom = alloc_ref(
+                &mut heap,
+                Some(ReferenceKind::Phantom),
+                Value::Reference(finalizable),
+            );
+            let held = alloc(&mut heap);
+            let not_ref = alloc_ref(&mut heap, None, Value::Reference(held));
+            let roots = [strong, weak, weak_to_strong, soft, phantom, not_ref];
+            let roots = roots.map(|r| heap.new_handle(r).unwrap());
+
+            // weakly reachable referents are cleared, while the finalizable one is kept to be finalized
+            let (stats, _) = heap.collect([], true);
+            assert_eq!(stats.cleared_references, 1);
+            assert_eq!(stats.finalizable_objects, 1);
+            assert_eq!(stats.freed_objects, 1);
+            let [strong, weak, weak_to_strong, soft, phantom, not_ref] =
+                roots.each_ref().map(|h| h.get());
+            assert_eq!(referent(&mut heap, weak), Value::Reference(0));
+            assert_eq!(
+                referent(&mut heap, weak_to_strong),
+                Value::Reference(strong)
+            );
+            assert_ne!(referent(&mut heap, soft), Value::Reference(0));
+            assert_ne!(referent(&mut heap, not_ref), Value::Reference(0));
+            assert_eq!(heap.pop_pending_reference(), Some(weak));
+            let Value::Reference(finalizable) = referent(&mut heap, phantom) else {
+                unreachable!()
+            };
+            assert_eq!(heap.pending_finalization, [finalizable]);
+
+            // phantom references are cleared once the objects are finalized
+            let finalized = heap.pop_pending_finalization().unwrap();
+            let (stats, _) = heap.collect([], true);
+            assert_eq!(stats.cleared_references, 1);
+            assert!(heap.get_ref(finalized).is_none());
+            assert_eq!(heap.pop_pending_reference(), Some(roots[4].get()));
+
+            // soft references are cleared on request
+            heap.clear_soft_references();
+            let (stats, _) = heap.collect([], true);
+            assert_eq!(stats.cleared_references, 1);
+            let soft = roots[3].get();
+            assert_eq!(referent(&mut heap, soft), Value::Reference(0));
+            assert_eq!(heap.pop_pending_reference(), Some(soft));
+            assert!(!heap.has_pending());
+        }
+    }
 }
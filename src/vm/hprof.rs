@@ -0,0 +1,520 @@
+// heap dump in the HPROF binary format, which can be analyzed by tools like Eclipse MAT and VisualVM.
+// cf. https://github.com/openjdk/jdk/blob/master/src/hotspot/share/services/heapDumper.cpp
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{BufWriter, Write},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    class::Class,
+    error::VMResult,
+    heap::{self, Heap, JavaArray, Object, RefValue},
+    method_area::MethodArea,
+    thread::Thread,
+    value::Value,
+};
+
+const HEADER: &[u8] = b"JAVA PROFILE 1.0.2\0";
+const ID_SIZE: u32 = 8;
+
+// tags of top-level records
+const TAG_UTF8: u8 = 0x01;
+const TAG_LOAD_CLASS: u8 = 0x02;
+const TAG_FRAME: u8 = 0x04;
+const TAG_TRACE: u8 = 0x05;
+const TAG_HEAP_DUMP_SEGMENT: u8 = 0x1C;
+const TAG_HEAP_DUMP_END: u8 = 0x2C;
+
+// tags of sub-records in heap dump segments
+const ROOT_UNKNOWN: u8 = 0xFF;
+const ROOT_JNI_GLOBAL: u8 = 0x01;
+const ROOT_JAVA_FRAME: u8 = 0x03;
+const ROOT_NATIVE_STACK: u8 = 0x04;
+const ROOT_STICKY_CLASS: u8 = 0x05;
+const CLASS_DUMP: u8 = 0x20;
+const INSTANCE_DUMP: u8 = 0x21;
+const OBJ_ARRAY_DUMP: u8 = 0x22;
+const PRIM_ARRAY_DUMP: u8 = 0x23;
+
+// basic types of fields and array elements
+const T_OBJECT: u8 = 2;
+const T_BOOLEAN: u8 = 4;
+const T_CHAR: u8 = 5;
+const T_FLOAT: u8 = 6;
+const T_DOUBLE: u8 = 7;
+const T_BYTE: u8 = 8;
+const T_SHORT: u8 = 9;
+const T_INT: u8 = 10;
+const T_LONG: u8 = 11;
+
+fn basic_type(desc: &str) -> u8 {
+    match desc.as_bytes().first() {
+        Some(b'Z') => T_BOOLEAN,
+        Some(b'C') => T_CHAR,
+        Some(b'F') => T_FLOAT,
+        Some(b'D') => T_DOUBLE,
+        Some(b'B') => T_BYTE,
+        Some(b'S') => T_SHORT,
+        Some(b'I') => T_INT,
+        Some(b'J') => T_LONG,
+        _ => T_OBJECT,
+    }
+}
+
+// the VM has only one thread
+const THREAD_SERIAL: u32 = 1;
+// allocation sites of objects are unknown, so they refer to the empty stack trace
+const EMPTY_TRACE_SERIAL: u32 = 1;
+const THREAD_TRACE_SERIAL: u32 = 2;
+
+// ids of objects are references to them. classes have no objects on the heap,
+// so they are given ids that references never take in practice (generation of 2^31 or later)
+const CLASS_ID_BASE: u64 = 1 << 63;
+
+// heap dump segments are split at this size, since the length of a record is limited to u32
+const SEGMENT_SIZE: usize = 1 << 20;
+
+// write the heap dump of the VM to `out`
+pub fn dump(out: impl Write, thread: &Thread, meth_area: &MethodArea, heap: &Heap) -> VMResult<()> {
+    let mut w = HprofWriter::new(BufWriter::new(out))?;
+
+    // classes are sorted by names, so that dumps are deterministic
+    let (array_classes, mut classes) = meth_area
+        .loaded_classes()
+        .partition::<Vec<_>, _>(|c| c.is_array());
+    classes.sort_by(|a, b| a.name.cmp(&b.name));
+    // classes of arrays of primitives are not dumped, as the arrays are dumped with their element types.
+    // arrays allocated by the host may not have their classes created
+    let is_ref_array = |desc: &str| basic_type(&desc[1..]) == T_OBJECT;
+    let array_classes = array_classes
+        .into_iter()
+        .map(|c| c.name.clone())
+        .chain(heap.entries().filter_map(|(_, rv, _)| match rv {
+            RefValue::Array(arr) => Some(arr.descriptor().as_str().to_string()),
+            _ => None,
+        }))
+        .filter(|desc| is_ref_array(desc))
+        .collect::<BTreeSet<_>>();
+
+    let names = classes
+        .iter()
+        .map(|c| c.name.as_str())
+        .chain(array_classes.iter().map(String::as_str));
+    for (i, name) in names.enumerate() {
+        let serial = i as u32 + 1;
+        let name_id = w.name_id(name)?;
+        w.class_ids
+            .insert(name.to_string(), CLASS_ID_BASE | serial as u64);
+        let mut body = Buf::default();
+        body.u4(serial)
+            .id(CLASS_ID_BASE | serial as u64)
+            .u4(EMPTY_TRACE_SERIAL)
+            .id(name_id);
+        w.record(TAG_LOAD_CLASS, &body)?;
+    }
+
+    w.stack_traces(thread)?;
+
+    w.roots(thread, meth_area, heap, &classes)?;
+    for cls in &classes {
+        w.class_dump(cls, meth_area)?;
+    }
+    let object_id = w.class_id("java/lang/Object");
+    for name in &array_classes {
+        let mut sub = Buf::default();
+        sub.u1(CLASS_DUMP)
+            .id(w.class_id(name))
+            .u4(EMPTY_TRACE_SERIAL)
+            .id(object_id);
+        // class loader, signers, protection domain and reserved ones
+        (0..5).for_each(|_| _ = sub.id(0));
+        // instance size, constant pool, static fields and instance fields
+        sub.u4(0).u2(0).u2(0).u2(0);
+        w.sub_record(&sub)?;
+    }
+    for (r, rv, _) in heap.entries() {
+        match rv {
+            RefValue::Object(obj) => w.instance_dump(r, obj, meth_area)?,
+            RefValue::Array(arr) => w.array_dump(r, arr.as_ref())?,
+            RefValue::Null => {}
+        }
+    }
+    w.flush_segment()?;
+    w.record(TAG_HEAP_DUMP_END, &Buf::default())?;
+    w.out.flush()?;
+    Ok(())
+}
+
+// big-endian buffer of record bodies
+#[derive(Default)]
+struct Buf(Vec<u8>);
+
+impl Buf {
+    fn u1(&mut self, v: u8) -> &mut Self {
+        self.0.push(v);
+        self
+    }
+
+    fn u2(&mut self, v: u16) -> &mut Self {
+        self.0.extend(v.to_be_bytes());
+        self
+    }
+
+    fn u4(&mut self, v: u32) -> &mut Self {
+        self.0.extend(v.to_be_bytes());
+        self
+    }
+
+    fn u8(&mut self, v: u64) -> &mut Self {
+        self.0.extend(v.to_be_bytes());
+        self
+    }
+
+    fn id(&mut self, id: u64) -> &mut Self {
+        self.u8(id)
+    }
+
+    // write the value of the basic type `ty`
+    fn value(&mut self, v: Value, ty: u8) -> &mut Self {
+        match (ty, v) {
+            (T_OBJECT, Value::Reference(r)) => self.id(r as u64),
+            (T_BOOLEAN | T_BYTE, v) => self.u1(v.as_int().unwrap_or(0) as u8),
+            (T_CHAR | T_SHORT, v) => self.u2(v.as_int().unwrap_or(0) as u16),
+            (T_INT, v) => self.u4(v.as_int().unwrap_or(0) as u32),
+            (T_FLOAT, Value::Float(f)) => self.u4(f.to_bits()),
+            (T_DOUBLE, Value::Double(d)) => self.u8(d.to_bits()),
+            (T_LONG, Value::Long(l)) => self.u8(l as u64),
+            (T_FLOAT, _) => self.u4(0),
+            _ => self.u8(0),
+        }
+    }
+}
+
+struct HprofWriter<W: Write> {
+    out: W,
+    // ids of UTF-8 strings used as names
+    name_ids: HashMap<String, u64>,
+    class_ids: HashMap<String, u64>,
+    segment: Buf,
+}
+
+impl<W: Write> HprofWriter<W> {
+    fn new(mut out: W) -> VMResult<Self> {
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        out.write_all(HEADER)?;
+        out.write_all(&ID_SIZE.to_be_bytes())?;
+        out.write_all(&millis.to_be_bytes())?;
+        Ok(HprofWriter {
+            out,
+            name_ids: HashMap::new(),
+            class_ids: HashMap::new(),
+            segment: Buf::default(),
+        })
+    }
+
+    fn record(&mut self, tag: u8, body: &Buf) -> VMResult<()> {
+        self.out.write_all(&[tag])?;
+        // microseconds since the time in the header
+        self.out.write_all(&0u32.to_be_bytes())?;
+        self.out.write_all(&(body.0.len() as u32).to_be_bytes())?;
+        self.out.write_all(&body.0)?;
+        Ok(())
+    }
+
+    fn sub_record(&mut self, sub: &Buf) -> VMResult<()> {
+        self.segment.0.extend_from_slice(&sub.0);
+        if self.segment.0.len() >= SEGMENT_SIZE {
+            self.flush_segment()?;
+        }
+        Ok(())
+    }
+
+    fn flush_segment(&mut self) -> VMResult<()> {
+        if self.segment.0.is_empty() {
+            return Ok(());
+        }
+        let segment = std::mem::take(&mut self.segment);
+        self.record(TAG_HEAP_DUMP_SEGMENT, &segment)
+    }
+
+    // id of the name, writing the UTF-8 record of it if it's new
+    fn name_id(&mut self, name: &str) -> VMResult<u64> {
+        if let Some(id) = self.name_ids.get(name) {
+            return Ok(*id);
+        }
+        let id = self.name_ids.len() as u64 + 1;
+        self.name_ids.insert(name.to_string(), id);
+        let mut body = Buf::default();
+        body.id(id).0.extend_from_slice(name.as_bytes());
+        self.record(TAG_UTF8, &body)?;
+        Ok(id)
+    }
+
+    fn class_id(&self, name: &str) -> u64 {
+        self.class_ids.get(name).copied().unwrap_or(0)
+    }
+
+    fn class_serial(&self, name: &str) -> u32 {
+        (self.class_id(name) & !CLASS_ID_BASE) as u32
+    }
+
+    // the empty stack trace, and the one of the thread whose frames are numbered from the innermost
+    fn stack_traces(&mut self, thread: &Thread) -> VMResult<()> {
+        let mut body = Buf::default();
+        body.u4(EMPTY_TRACE_SERIAL).u4(THREAD_SERIAL).u4(0);
+        self.record(TAG_TRACE, &body)?;
+
+        let mut frame_ids = Vec::new();
+        for frame in thread.frames().filter(|f| f.has_code()) {
+            let cls_name = frame.current_class().name.clone();
+            let sig = frame.method_signature();
+            let mut body = Buf::default();
+            let frame_id = frame_ids.len() as u64 + 1;
+            body.id(frame_id)
+                .id(self.name_id(&sig.name)?)
+                .id(self.name_id(&sig.descriptor.to_string())?)
+                // source file
+                .id(0)
+                .u4(self.class_serial(&cls_name))
+                // line number is unknown
+                .u4(-1i32 as u32);
+            self.record(TAG_FRAME, &body)?;
+            frame_ids.push(frame_id);
+        }
+        let mut body = Buf::default();
+        body.u4(THREAD_TRACE_SERIAL)
+            .u4(THREAD_SERIAL)
+            .u4(frame_ids.len() as u32);
+        frame_ids.into_iter().for_each(|id| _ = body.id(id));
+        self.record(TAG_TRACE, &body)
+    }
+
+    // values in frames, loaded classes (whose static fields refer to objects),
+    // references held by native code and the host, java.lang.Class objects and interned strings
+    fn roots(
+        &mut self,
+        thread: &Thread,
+        meth_area: &MethodArea,
+        heap: &Heap,
+        classes: &[&Rc<Class>],
+    ) -> VMResult<()> {
+        let mut frame_num = 0;
+        for frame in thread.frames() {
+            for v in frame.values() {
+                let Value::Reference(r @ 1..) = v else {
+                    continue;
+                };
+                let mut sub = Buf::default();
+                if frame.has_code() {
+                    sub.u1(ROOT_JAVA_FRAME)
+                        .id(r as u64)
+                        .u4(THREAD_SERIAL)
+                        .u4(frame_num);
+                } else {
+                    // arguments passed from the host or native methods
+                    sub.u1(ROOT_NATIVE_STACK).id(r as u64).u4(THREAD_SERIAL);
+                }
+                self.sub_record(&sub)?;
+            }
+            if frame.has_code() {
+                frame_num += 1;
+            }
+        }
+        for cls in classes {
+            let mut sub = Buf::default();
+            sub.u1(ROOT_STICKY_CLASS).id(self.class_id(&cls.name));
+            self.sub_record(&sub)?;
+        }
+        let globals = meth_area.natives().jni.references();
+        for r in globals.into_iter().chain(heap.handle_references()) {
+            let mut sub = Buf::default();
+            sub.u1(ROOT_JNI_GLOBAL).id(r as u64).id(0);
+            self.sub_record(&sub)?;
+        }
+        let mirrors = meth_area.mirrors().filter_map(|m| match m.get() {
+            Value::Reference(r @ 1..) => Some(r),
+            _ => None,
+        });
+        for r in heap
+            .interned_string_references()
+            .chain(heap.pending_references())
+            .chain(mirrors)
+        {
+            let mut sub = Buf::default();
+            sub.u1(ROOT_UNKNOWN).id(r as u64);
+            self.sub_record(&sub)?;
+        }
+        Ok(())
+    }
+
+    fn class_dump(&mut self, cls: &Rc<Class>, meth_area: &MethodArea) -> VMResult<()> {
+        let mut sub = Buf::default();
+        let super_id = cls
+            .super_class
+            .as_deref()
+            .map_or(0, |name| self.class_id(name));
+        sub.u1(CLASS_DUMP)
+            .id(self.class_id(&cls.name))
+            .u4(EMPTY_TRACE_SERIAL)
+            .id(super_id);
+        // class loader, signers, protection domain and reserved ones
+        (0..5).for_each(|_| _ = sub.id(0));
+        // instances of interfaces and abstract classes can't be created, but their size is still meaningful
+        let size = heap::instance_size(cls, meth_area).unwrap_or(0);
+        // size of the constant pool
+        sub.u4(size as u32).u2(0);
+
+        let statics = cls.static_fields_with_info().collect::<Vec<_>>();
+        sub.u2(statics.len() as u16);
+        for (f, v) in statics {
+            let ty = basic_type(&f.descriptor);
+            sub.id(self.name_id(&f.name)?).u1(ty).value(v.get(), ty);
+        }
+        let fields = cls.instance_fields().collect::<Vec<_>>();
+        sub.u2(fields.len() as u16);
+        for f in fields {
+            sub.id(self.name_id(&f.name)?).u1(basic_type(&f.descriptor));
+        }
+        self.sub_record(&sub)
+    }
+
+    fn instance_dump(&mut self, r: usize, obj: &Object, meth_area: &MethodArea) -> VMResult<()> {
+        // values of fields declared in the class come first, followed by ones of its superclasses
+        let mut values = Buf::default();
+        let mut cls = Some(obj.get_class());
+        while let Some(c) = cls {
+            for f in c.instance_fields() {
+                let v = obj
+                    .get_field(&c.name, &f.name)
+                    .map_or(Value::Reference(0), |v| v.get());
+                values.value(v, basic_type(&f.descriptor));
+            }
+            cls = c
+                .super_class
+                .as_deref()
+                .and_then(|name| meth_area.loaded_class(name))
+                .cloned();
+        }
+
+        let mut sub = Buf::default();
+        sub.u1(INSTANCE_DUMP)
+            .id(r as u64)
+            .u4(EMPTY_TRACE_SERIAL)
+            .id(self.class_id(&obj.get_class().name))
+            .u4(values.0.len() as u32);
+        sub.0.extend(values.0);
+        self.sub_record(&sub)
+    }
+
+    fn array_dump(&mut self, r: usize, arr: &dyn JavaArray) -> VMResult<()> {
+        let desc = arr.descriptor();
+        let ty = basic_type(&desc.as_str()[1..]);
+        let mut sub = Buf::default();
+        if ty == T_OBJECT {
+            sub.u1(OBJ_ARRAY_DUMP)
+                .id(r as u64)
+                .u4(EMPTY_TRACE_SERIAL)
+                .u4(arr.len())
+                .id(self.class_id(desc.as_str()));
+        } else {
+            sub.u1(PRIM_ARRAY_DUMP)
+                .id(r as u64)
+                .u4(EMPTY_TRACE_SERIAL)
+                .u4(arr.len())
+                .u1(ty);
+        }
+        for i in 0..arr.len() {
+            sub.value(arr.get(i).unwrap_or(Value::Reference(0)), ty);
+        }
+        self.sub_record(&sub)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vm::options::VMConfig;
+
+    // (tag, body) of top-level records
+    fn records(dump: &[u8]) -> Vec<(u8, &[u8])> {
+        assert!(dump.starts_with(HEADER));
+        let mut rest = &dump[HEADER.len() + 4 + 8..];
+        let mut records = Vec::new();
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[5..9].try_into().unwrap()) as usize;
+            records.push((rest[0], &rest[9..9 + len]));
+            rest = &rest[9 + len..];
+        }
+        records
+    }
+
+    #[test]
+    fn test_dump() {
+        let meth_area = MethodArea::new(Rc::new(VMConfig::default()));
+        let mut heap = Heap::new();
+        let Value::Reference(ints) = heap.alloc_array(3, "I").unwrap() else {
+            unreachable!()
+        };
+        let Value::Reference(outer) = heap.alloc_array(1, "[I").unwrap() else {
+            unreachable!()
+        };
+        let Some(RefValue::Array(arr)) = heap.get(ints) else {
+            unreachable!()
+        };
+        (0..3).for_each(|i| arr.put(i, Value::Int(i as i32 + 1)));
+        let Some(RefValue::Array(arr)) = heap.get(outer) else {
+            unreachable!()
+        };
+        arr.put(0, Value::Reference(ints));
+        let _handle = heap.new_handle(outer).unwrap();
+
+        let mut out = Vec::new();
+        dump(&mut out, &Thread::new(), &meth_area, &heap).unwrap();
+        let records = records(&out);
+        let tags = records.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [
+                TAG_UTF8,
+                TAG_LOAD_CLASS,
+                TAG_TRACE,
+                TAG_TRACE,
+                TAG_HEAP_DUMP_SEGMENT,
+                TAG_HEAP_DUMP_END
+            ]
+        );
+        assert_eq!(&records[0].1[8..], b"[[I");
+
+        let mut expected = Buf::default();
+        expected
+            .u1(ROOT_JNI_GLOBAL)
+            .id(outer as u64)
+            .id(0)
+            // class of int[][]
+            .u1(CLASS_DUMP)
+            .id(CLASS_ID_BASE | 1)
+            .u4(EMPTY_TRACE_SERIAL);
+        (0..6).for_each(|_| _ = expected.id(0));
+        expected.u4(0).u2(0).u2(0).u2(0);
+        expected
+            .u1(PRIM_ARRAY_DUMP)
+            .id(ints as u64)
+            .u4(EMPTY_TRACE_SERIAL)
+            .u4(3)
+            .u1(T_INT)
+            .u4(1)
+            .u4(2)
+            .u4(3);
+        expected
+            .u1(OBJ_ARRAY_DUMP)
+            .id(outer as u64)
+            .u4(EMPTY_TRACE_SERIAL)
+            .u4(1)
+            .id(CLASS_ID_BASE | 1)
+            .id(ints as u64);
+        assert_eq!(records[4].1, expected.0.as_slice());
+    }
+}
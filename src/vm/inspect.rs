@@ -0,0 +1,395 @@
+// inspection of the heap: live entries, class histogram, retained sizes and the object graph.
+// retained sizes are computed from the dominator tree of the object graph, rooted at a virtual node which refers to all the GC roots.
+// the dominator tree is built by the iterative algorithm of Cooper, Harvey and Kennedy ("A Simple, Fast Dominance Algorithm").
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Write},
+    mem,
+};
+
+use super::{error::VMResult, heap::Heap};
+
+/// An entry of the heap, i.e. an object or an array.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapEntry {
+    /// Raw reference to the entry, as in [`Value::Reference`](crate::Value::Reference).
+    pub reference: usize,
+    /// Binary name of the class (e.g. `java/lang/String`), or the descriptor for arrays (e.g. `[I`).
+    pub class_name: String,
+    /// Size of the entry itself, in bytes.
+    pub shallow_size: usize,
+    /// Non-null references held by the fields or the elements of the entry.
+    pub references: Vec<usize>,
+}
+
+pub fn entries(heap: &Heap) -> impl Iterator<Item = HeapEntry> + '_ {
+    heap.entries().map(|(r, rv, size)| HeapEntry {
+        reference: r,
+        class_name: rv.class_name(),
+        shallow_size: size,
+        references: rv
+            .labeled_references()
+            .into_iter()
+            .map(|(_, r)| r)
+            .collect(),
+    })
+}
+
+/// Numbers of instances and bytes on the heap per class, like `jmap -histo`.
+///
+/// Entries are sorted by bytes in descending order. The [`Display`](fmt::Display) implementation prints the table in the format of `jmap`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassHistogram {
+    pub entries: Vec<HistogramEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramEntry {
+    /// Binary name of the class, or the descriptor for arrays.
+    pub class_name: String,
+    pub instances: usize,
+    pub bytes: usize,
+}
+
+impl ClassHistogram {
+    pub fn total_instances(&self) -> usize {
+        self.entries.iter().map(|e| e.instances).sum()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.entries.iter().map(|e| e.bytes).sum()
+    }
+}
+
+impl fmt::Display for ClassHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, " num     #instances         #bytes  class name")?;
+        writeln!(f, "----------------------------------------------")?;
+        for (i, e) in self.entries.iter().enumerate() {
+            writeln!(
+                f,
+                "{:4}: {:13} {:14}  {}",
+                i + 1,
+                e.instances,
+                e.bytes,
+                java_name(&e.class_name)
+            )?;
+        }
+        write!(
+            f,
+            "Total {:13} {:14}",
+            self.total_instances(),
+            self.total_bytes()
+        )
+    }
+}
+
+pub fn histogram(heap: &Heap) -> ClassHistogram {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for (_, rv, size) in heap.entries() {
+        let c = counts.entry(rv.class_name()).or_default();
+        c.0 += 1;
+        c.1 += size;
+    }
+    let mut entries = counts
+        .into_iter()
+        .map(|(class_name, (instances, bytes))| HistogramEntry {
+            class_name,
+            instances,
+            bytes,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| {
+        (b.bytes, b.instances)
+            .cmp(&(a.bytes, a.instances))
+            .then_with(|| a.class_name.cmp(&b.class_name))
+    });
+    ClassHistogram { entries }
+}
+
+/// Dominator tree of the objects reachable from the GC roots.
+///
+/// An object dominates another if every path from the roots to the latter goes through the former.
+/// The retained size of an object is the total size of the objects it dominates (including itself),
+/// i.e. the amount of memory which would be freed if the object became unreachable.
+#[derive(Debug, Clone)]
+pub struct DominatorTree {
+    // nodes are indexed in reverse postorder of the DFS from the virtual root (index 0)
+    refs: Vec<usize>,
+    index: HashMap<usize, usize>,
+    idom: Vec<usize>,
+    retained: Vec<usize>,
+}
+
+const VIRTUAL_ROOT: usize = 0;
+
+impl DominatorTree {
+    /// Immediate dominator of the object. Returns `None` if the object is not reachable, or is dominated only by the roots as a whole.
+    pub fn immediate_dominator(&self, r: usize) -> Option<usize> {
+        let &i = self.index.get(&r)?;
+        match self.idom[i] {
+            VIRTUAL_ROOT => None,
+            d => Some(self.refs[d]),
+        }
+    }
+
+    /// Retained size of the object in bytes. Returns `None` if the object is not reachable.
+    pub fn retained_size(&self, r: usize) -> Option<usize> {
+        self.index.get(&r).map(|&i| self.retained[i])
+    }
+
+    /// Reachable objects with their retained sizes, sorted by the sizes in descending order.
+    pub fn retained_sizes(&self) -> Vec<(usize, usize)> {
+        let mut sizes = (1..self.refs.len())
+            .map(|i| (self.refs[i], self.retained[i]))
+            .collect::<Vec<_>>();
+        sizes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        sizes
+    }
+}
+
+pub fn dominator_tree(heap: &Heap, roots: impl IntoIterator<Item = usize>) -> DominatorTree {
+    let mut roots = roots
+        .into_iter()
+        .chain(heap.interned_string_references())
+        .chain(heap.handle_references())
+        .chain(heap.pending_references())
+        .filter(|&r| heap.get_ref(r).is_some())
+        .collect::<Vec<_>>();
+    roots.sort();
+    roots.dedup();
+
+    let succs = |node: usize| -> Vec<usize> {
+        if node == VIRTUAL_ROOT {
+            roots.clone()
+        } else {
+            heap.get_ref(node)
+                .map(|rv| {
+                    rv.labeled_references()
+                        .into_iter()
+                        .map(|(_, r)| r)
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    };
+
+    // postorder by iterative DFS. the virtual root is represented by the null reference
+    let mut postorder = Vec::new();
+    let mut edges = HashMap::new();
+    let mut visited = HashSet::from([VIRTUAL_ROOT]);
+    let mut stack = vec![(VIRTUAL_ROOT, succs(VIRTUAL_ROOT), 0)];
+    while let Some((node, ss, i)) = stack.last_mut() {
+        if let Some(&s) = ss.get(*i) {
+            *i += 1;
+            if heap.get_ref(s).is_some() && visited.insert(s) {
+                let s_succs = succs(s);
+                stack.push((s, s_succs, 0));
+            }
+        } else {
+            let (node, ss) = (*node, mem::take(ss));
+            edges.insert(node, ss);
+            postorder.push(node);
+            stack.pop();
+        }
+    }
+
+    let refs = postorder.into_iter().rev().collect::<Vec<_>>();
+    let index = refs
+        .iter()
+        .enumerate()
+        .map(|(i, &r)| (r, i))
+        .collect::<HashMap<_, _>>();
+    let mut preds = vec![Vec::new(); refs.len()];
+    for (i, r) in refs.iter().enumerate() {
+        for s in &edges[r] {
+            if let Some(&j) = index.get(s) {
+                preds[j].push(i);
+            }
+        }
+    }
+
+    // nodes are numbered in reverse postorder, so dominators have smaller numbers than the nodes they dominate
+    const UNDEFINED: usize = usize::MAX;
+    let mut idom = vec![UNDEFINED; refs.len()];
+    idom[VIRTUAL_ROOT] = VIRTUAL_ROOT;
+    let intersect = |idom: &[usize], mut a: usize, mut b: usize| {
+        while a != b {
+            while a > b {
+                a = idom[a];
+            }
+            while b > a {
+                b = idom[b];
+            }
+        }
+        a
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for n in 1..refs.len() {
+            let new_idom = preds[n]
+                .iter()
+                .copied()
+                .filter(|&p| idom[p] != UNDEFINED)
+                .reduce(|a, b| intersect(&idom, a, b))
+                .expect("reachable node must have a processed predecessor");
+            if idom[n] != new_idom {
+                idom[n] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    let mut retained = refs
+        .iter()
+        .map(|&r| {
+            if r == VIRTUAL_ROOT {
+                0
+            } else {
+                heap.size_of(r)
+            }
+        })
+        .collect::<Vec<_>>();
+    for n in (1..refs.len()).rev() {
+        retained[idom[n]] += retained[n];
+    }
+
+    DominatorTree {
+        refs,
+        index,
+        idom,
+        retained,
+    }
+}
+
+// object graph reachable from `from`, in the DOT language of Graphviz
+pub fn object_graph_dot(heap: &Heap, from: usize) -> VMResult<String> {
+    if heap.get_ref(from).is_none() {
+        Err(format!("invalid or stale reference: {from:#x}"))?;
+    }
+    let mut dot = String::from("digraph heap {\n    node [shape=box];\n");
+    let mut visited = HashSet::from([from]);
+    let mut queue = vec![from];
+    while let Some(r) = queue.pop() {
+        let Some(rv) = heap.get_ref(r) else {
+            continue;
+        };
+        let _ = writeln!(
+            dot,
+            "    \"{r:#x}\" [label=\"{}\\n{} bytes\"];",
+            escape(&java_name(&rv.class_name())),
+            heap.size_of(r)
+        );
+        for (label, to) in rv.labeled_references() {
+            let _ = writeln!(
+                dot,
+                "    \"{r:#x}\" -> \"{to:#x}\" [label=\"{}\"];",
+                escape(&label)
+            );
+            if visited.insert(to) {
+                queue.push(to);
+            }
+        }
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+// class name as printed by Java tools (e.g. "java.lang.String", "[Ljava.lang.String;")
+fn java_name(name: &str) -> String {
+    name.replace('/', ".")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vm::{heap::RefValue, value::Value};
+
+    fn alloc(heap: &mut Heap, len: i32, desc: &str) -> usize {
+        match heap.alloc_array(len, desc).unwrap() {
+            Value::Reference(r) => r,
+            _ => unreachable!(),
+        }
+    }
+
+    fn link(heap: &mut Heap, from: usize, idx: u32, to: usize) {
+        let Some(RefValue::Array(arr)) = heap.get(from) else {
+            unreachable!()
+        };
+        arr.put(idx, Value::Reference(to));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut heap = Heap::new();
+        let a = alloc(&mut heap, 2, "I");
+        alloc(&mut heap, 2, "I");
+        let s = alloc(&mut heap, 1, "Ljava/lang/String;");
+        link(&mut heap, s, 0, a);
+
+        let histo = histogram(&heap);
+        assert_eq!(
+            histo.entries,
+            vec![
+                HistogramEntry {
+                    class_name: "[I".to_string(),
+                    instances: 2,
+                    bytes: 48,
+                },
+                HistogramEntry {
+                    class_name: "[Ljava/lang/String;".to_string(),
+                    instances: 1,
+                    bytes: 24,
+                },
+            ]
+        );
+        assert_eq!(histo.total_bytes(), 72);
+        assert!(histo
+            .to_string()
+            .contains("   2:             1             24  [Ljava.lang.String;"));
+
+        let entry = entries(&heap).find(|e| e.reference == s).unwrap();
+        assert_eq!(entry.class_name, "[Ljava/lang/String;");
+        assert_eq!(entry.references, vec![a]);
+    }
+
+    #[test]
+    fn test_dominator_tree() {
+        let mut heap = Heap::new();
+        // a -> c <- b, c -> d -> e, e -> d, and garbage
+        let a = alloc(&mut heap, 1, "Ljava/lang/Object;");
+        let b = alloc(&mut heap, 1, "Ljava/lang/Object;");
+        let c = alloc(&mut heap, 1, "Ljava/lang/Object;");
+        let d = alloc(&mut heap, 1, "Ljava/lang/Object;");
+        let e = alloc(&mut heap, 4, "Ljava/lang/Object;");
+        let garbage = alloc(&mut heap, 1, "Ljava/lang/Object;");
+        link(&mut heap, a, 0, c);
+        link(&mut heap, b, 0, c);
+        link(&mut heap, c, 0, d);
+        link(&mut heap, d, 0, e);
+        link(&mut heap, e, 0, d);
+        link(&mut heap, garbage, 0, a);
+
+        let tree = dominator_tree(&heap, [a, b]);
+        assert_eq!(tree.immediate_dominator(a), None);
+        assert_eq!(tree.immediate_dominator(c), None);
+        assert_eq!(tree.immediate_dominator(d), Some(c));
+        assert_eq!(tree.immediate_dominator(e), Some(d));
+        assert_eq!(tree.retained_size(a), Some(24));
+        assert_eq!(tree.retained_size(c), Some(24 + 24 + 32));
+        assert_eq!(tree.retained_size(garbage), None);
+        assert_eq!(tree.retained_sizes()[0], (c, 80));
+
+        let dot = object_graph_dot(&heap, c).unwrap();
+        assert!(dot.contains(&format!("\"{d:#x}\" -> \"{e:#x}\" [label=\"[0]\"];")));
+        assert!(dot.contains(&format!("\"{e:#x}\" -> \"{d:#x}\" [label=\"[0]\"];")));
+        assert!(!dot.contains(&format!("\"{a:#x}\"")));
+        assert!(object_graph_dot(&heap, garbage + (1 << 32)).is_err());
+    }
+}
@@ -1,10 +1,12 @@
 use crate::vm::heap::RefValue;
 
 use super::class::{MethodSignature, RunTimeCPInfo as CPInfo};
-use super::error::VMResult;
-use super::frame::Frame;
-use super::heap::Heap;
-use super::method_area::MethodArea;
+use super::error::{JavaException, VMResult};
+use super::exception;
+use super::gc;
+use super::heap::{self, Heap};
+use super::method_area::{self, MethodArea};
+use super::object_methods;
 use super::thread::Thread;
 use super::value::{Value, ValueCategory};
 
@@ -229,16 +231,19 @@ const INSTRUCTION_TABLE: [Option<Instruction>; 256] = instruction_table! {
     0xB7 => instr_invokespecial,
     0xB8 => instr_invokestatic,
     0xB9 => instr_invokeinterface,
-    // 0xBA => instr_invokedynamic,
+    0xBA => instr_invokedynamic,
     0xBB => instr_new,
     0xBC => instr_newarray,
     0xBD => instr_anewarray,
     0xBE => instr_arraylength,
-    // 0xBF => instr_athrow,
+    0xBF => instr_athrow,
     0xC0 => instr_checkcast,
     0xC1 => instr_instanceof,
-    // 0xC2 => instr_monitorenter,
-    // 0xC3 => instr_monitorexit,
+    0xC2 => instr_monitorenter,
+    0xC3 => instr_monitorexit,
+    0xC5 => instr_multianewarray,
+    0xC6 => instr_ifnull,
+    0xC7 => instr_ifnonnull,
 
     // TODO: implement extended instructions
 };
@@ -317,35 +322,56 @@ fn instr_sipush(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> Instruction
 }
 
 // push a constant from constant pool to the operand stack
-fn instr_ldc(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+fn instr_ldc(t: &mut Thread, meth_area: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
     let frame = t.current_frame();
-    let idx = frame.next_param_u8();
-    let v = match frame.get_cp_info(idx as u16) {
+    let idx = frame.next_param_u8() as u16;
+    let v = match frame.get_cp_info(idx) {
         CPInfo::Integer(v) => Value::Int(*v),
         CPInfo::Float(v) => Value::Float(*v),
+        CPInfo::String(s) => {
+            let s = s.clone();
+            heap.intern_string(&s, meth_area)?
+        }
+        CPInfo::Class { name } => {
+            let name = name.clone();
+            class_constant(&name, meth_area, heap)?
+        }
         CPInfo::Double(_) | CPInfo::Long(_) => Err("can't load double/long with ldc")?,
-        // TODO: support symbolic references, string consts, etc.
+        // TODO: support symbolic references, etc.
         _ => Err("unsupported constant pool entry")?,
     };
-    frame.push_operand(v);
+    t.current_frame().push_operand(v);
     Ok(())
 }
 
 // push a constant from constant pool to the operand stack (wide index)
-fn instr_ldc_w(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+fn instr_ldc_w(t: &mut Thread, meth_area: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
     let frame = t.current_frame();
     let idx = frame.next_param_u16();
     let v = match frame.get_cp_info(idx) {
         CPInfo::Integer(v) => Value::Int(*v),
         CPInfo::Float(v) => Value::Float(*v),
+        CPInfo::String(s) => {
+            let s = s.clone();
+            heap.intern_string(&s, meth_area)?
+        }
+        CPInfo::Class { name } => {
+            let name = name.clone();
+            class_constant(&name, meth_area, heap)?
+        }
         CPInfo::Double(_) | CPInfo::Long(_) => Err("can't load double/long consts with ldc_w")?,
-        // TODO: support symbolic references, string consts, etc.
+        // TODO: support symbolic references, etc.
         _ => Err("unsupported constant pool entry")?,
     };
-    frame.push_operand(v);
+    t.current_frame().push_operand(v);
     Ok(())
 }
 
+// the class is loaded (but not initialized) before its mirror is created
+fn class_constant(name: &str, meth_area: &mut MethodArea, heap: &mut Heap) -> VMResult<Value> {
+    meth_area.mirror(heap, name)
+}
+
 // push a long/double constant from constant pool to the operand stack (wide index)
 fn instr_ldc2_w(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
     let frame = t.current_frame();
@@ -508,11 +534,63 @@ instr_astore!(instr_iastore, Value::Int, "int");
 instr_astore!(instr_lastore, Value::Long, "long");
 instr_astore!(instr_fastore, Value::Float, "float");
 instr_astore!(instr_dastore, Value::Double, "double");
-instr_astore!(instr_aastore, Value::Reference, "reference");
 instr_astore!(instr_bastore, Value::Int, "int"); // Int -> Byte/Boolean
 instr_astore!(instr_castore, Value::Int, "int"); // Int -> Char
 instr_astore!(instr_sastore, Value::Int, "int"); // Int -> Short
 
+// store a reference to an array, which must be assignable to the component type of the array
+fn instr_aastore(t: &mut Thread, meth_area: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
+    let frame = t.current_frame();
+
+    let Value::Reference(v) = frame.pop_operand() else {
+        return Err("the value to store doesn't have type 'reference'")?;
+    };
+    let Value::Int(idx) = frame.pop_operand() else {
+        return Err("index is not an int")?;
+    };
+    let Value::Reference(r) = frame.pop_operand() else {
+        return Err("operand is not a reference value")?;
+    };
+    // class (or array descriptor) of the value, taken before borrowing the array
+    let v_cls_name = match v {
+        0 => None,
+        v => match heap.get_ref(v) {
+            Some(rv) => Some(rv.class_name()),
+            None => return Err("referent not found on heap")?,
+        },
+    };
+
+    // the array is checked in the order of null, index and then the type of the value
+    if r == 0 {
+        Err(JavaException::null_pointer_exception(
+            "Cannot store to object array",
+        ))?;
+    }
+    let Some(RefValue::Array(arr)) = heap.get(r) else {
+        return Err("referent is not an array")?;
+    };
+    let len = arr.len();
+    if idx < 0 || idx as u32 >= len {
+        Err(JavaException::array_index_out_of_bounds_exception(format!(
+            "Index {idx} out of bounds for length {len}"
+        )))?;
+    }
+    let desc = arr.descriptor();
+    let Some(comp) = heap::component_class_name(&desc.as_str()[1..]) else {
+        return Err("array of primitives can't store references")?;
+    };
+    if let Some(v_cls_name) = v_cls_name {
+        if !meth_area.is_assignable(&v_cls_name, comp)? {
+            Err(JavaException::array_store_exception(
+                v_cls_name.replace('/', "."),
+            ))?;
+        }
+    }
+    arr.put(idx as u32, Value::Reference(v));
+
+    Ok(())
+}
+
 macro_rules! pop_operand_if_category_matches {
     ($frame:expr, $category:pat) => {{
         let $category = $frame.peek_operand().category() else {
@@ -725,6 +803,20 @@ macro_rules! instr_unary_op {
     };
 }
 
+// negation of integers wraps around on overflow (-MIN == MIN)
+macro_rules! instr_int_unary_op {
+    ($name:ident, $op_fn:ident, $vtype:path, $vtype_name:expr) => {
+        fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+            let frame = t.current_frame();
+            let $vtype(v) = frame.pop_operand() else {
+                return Err(concat!("target operand is not type '", $vtype_name, "'").into());
+            };
+            frame.push_operand($vtype(v.$op_fn()));
+            Ok(())
+        }
+    };
+}
+
 macro_rules! instr_binary_op {
     ($name:ident, $op:tt, $vtype:path, $vtype_name:expr) => {
         fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
@@ -741,16 +833,54 @@ macro_rules! instr_binary_op {
     };
 }
 
+// arithmetic of integers wraps around on overflow
+macro_rules! instr_int_binary_op {
+    ($name:ident, $op_fn:ident, $vtype:path, $vtype_name:expr) => {
+        fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+            let frame = t.current_frame();
+            let $vtype(rhs) = frame.pop_operand() else {
+                return Err(concat!("target operand is not type '", $vtype_name, "'").into());
+            };
+            let $vtype(lhs) = frame.pop_operand() else {
+                return Err(concat!("target operand is not type '", $vtype_name, "'").into());
+            };
+            frame.push_operand($vtype(lhs.$op_fn(rhs)));
+            Ok(())
+        }
+    };
+}
+
+// division of integers throws ArithmeticException if the divisor is zero
+macro_rules! instr_int_div_op {
+    ($name:ident, $op_fn:ident, $vtype:path, $vtype_name:expr) => {
+        fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+            let frame = t.current_frame();
+            let $vtype(rhs) = frame.pop_operand() else {
+                return Err(concat!("target operand is not type '", $vtype_name, "'").into());
+            };
+            let $vtype(lhs) = frame.pop_operand() else {
+                return Err(concat!("target operand is not type '", $vtype_name, "'").into());
+            };
+            if rhs == 0 {
+                Err(JavaException::arithmetic_exception("/ by zero"))?;
+            }
+            // MIN / -1 overflows to MIN
+            frame.push_operand($vtype(lhs.$op_fn(rhs)));
+            Ok(())
+        }
+    };
+}
+
 macro_rules! instr_shift_op {
     // shift with zero-extension
     ($name:ident, $op:tt, u, Value::Int) => {
         fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
             let frame = t.current_frame();
             let Value::Int(v2) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'int'").into());
+                return Err("target operand is not type 'int'".into());
             };
             let Value::Int(v1) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'int'").into());
+                return Err("target operand is not type 'int'".into());
             };
             let s = v2 & 0x1F; // take lowest 5 bits
             frame.push_operand(Value::Int(((v1 as u32) $op s) as i32));
@@ -761,10 +891,10 @@ macro_rules! instr_shift_op {
         fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
             let frame = t.current_frame();
             let Value::Int(v2) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'int'").into());
+                return Err("target operand is not type 'int'".into());
             };
             let Value::Long(v1) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'long'").into());
+                return Err("target operand is not type 'long'".into());
             };
             let s = v2 & 0x3F; // take lowest 6 bits
             frame.push_operand(Value::Long(((v1 as u64) $op s) as i64));
@@ -776,10 +906,10 @@ macro_rules! instr_shift_op {
         fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
             let frame = t.current_frame();
             let Value::Int(v2) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'int'").into());
+                return Err("target operand is not type 'int'".into());
             };
             let Value::Int(v1) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'int'").into());
+                return Err("target operand is not type 'int'".into());
             };
             let s = v2 & 0x1F; // take lowest 5 bits
             frame.push_operand(Value::Int(v1 $op s));
@@ -790,10 +920,10 @@ macro_rules! instr_shift_op {
         fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
             let frame = t.current_frame();
             let Value::Int(v2) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'int'").into());
+                return Err("target operand is not type 'int'".into());
             };
             let Value::Long(v1) = frame.pop_operand() else {
-                return Err(concat!("target operand is not type 'long'").into());
+                return Err("target operand is not type 'long'".into());
             };
             let s = v2 & 0x3F; // take lowest 6 bits
             frame.push_operand(Value::Long(v1 $op s));
@@ -802,33 +932,33 @@ macro_rules! instr_shift_op {
     };
 }
 
-instr_binary_op!(instr_iadd, +, Value::Int, "int");
-instr_binary_op!(instr_ladd, +, Value::Long, "long");
+instr_int_binary_op!(instr_iadd, wrapping_add, Value::Int, "int");
+instr_int_binary_op!(instr_ladd, wrapping_add, Value::Long, "long");
 instr_binary_op!(instr_fadd, +, Value::Float, "float");
 instr_binary_op!(instr_dadd, +, Value::Double, "double");
 
-instr_binary_op!(instr_isub, -, Value::Int, "int");
-instr_binary_op!(instr_lsub, -, Value::Long, "long");
+instr_int_binary_op!(instr_isub, wrapping_sub, Value::Int, "int");
+instr_int_binary_op!(instr_lsub, wrapping_sub, Value::Long, "long");
 instr_binary_op!(instr_fsub, -, Value::Float, "float");
 instr_binary_op!(instr_dsub, -, Value::Double, "double");
 
-instr_binary_op!(instr_imul, *, Value::Int, "int");
-instr_binary_op!(instr_lmul, *, Value::Long, "long");
+instr_int_binary_op!(instr_imul, wrapping_mul, Value::Int, "int");
+instr_int_binary_op!(instr_lmul, wrapping_mul, Value::Long, "long");
 instr_binary_op!(instr_fmul, *, Value::Float, "float");
 instr_binary_op!(instr_dmul, *, Value::Double, "double");
 
-instr_binary_op!(instr_idiv, /, Value::Int, "int");
-instr_binary_op!(instr_ldiv, /, Value::Long, "long");
+instr_int_div_op!(instr_idiv, wrapping_div, Value::Int, "int");
+instr_int_div_op!(instr_ldiv, wrapping_div, Value::Long, "long");
 instr_binary_op!(instr_fdiv, /, Value::Float, "float");
 instr_binary_op!(instr_ddiv, /, Value::Double, "double");
 
-instr_binary_op!(instr_irem, %, Value::Int, "int");
-instr_binary_op!(instr_lrem, %, Value::Long, "long");
+instr_int_div_op!(instr_irem, wrapping_rem, Value::Int, "int");
+instr_int_div_op!(instr_lrem, wrapping_rem, Value::Long, "long");
 instr_binary_op!(instr_frem, %, Value::Float, "float");
 instr_binary_op!(instr_drem, %, Value::Double, "double");
 
-instr_unary_op!(instr_ineg, -, Value::Int, "int");
-instr_unary_op!(instr_lneg, -, Value::Long, "long");
+instr_int_unary_op!(instr_ineg, wrapping_neg, Value::Int, "int");
+instr_int_unary_op!(instr_lneg, wrapping_neg, Value::Long, "long");
 instr_unary_op!(instr_fneg, -, Value::Float, "float");
 instr_unary_op!(instr_dneg, -, Value::Double, "double");
 
@@ -861,7 +991,7 @@ fn instr_iinc(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionRe
     let Value::Int(v) = frame.get_local(idx) else {
         return Err("target local is not type 'int'".into());
     };
-    frame.set_local(idx, Value::Int(v + delta));
+    frame.set_local(idx, Value::Int(v.wrapping_add(delta)));
     Ok(())
 }
 
@@ -870,7 +1000,7 @@ macro_rules! instr_conversion {
         fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
             let frame = t.current_frame();
             let $from(v) = frame.pop_operand() else {
-                return Err(concat!("target operand has invalid type").into());
+                return Err("target operand has invalid type".into());
             };
             frame.push_operand(Value::Int((v as $to_raw) as i32));
             Ok(())
@@ -880,7 +1010,7 @@ macro_rules! instr_conversion {
         fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
             let frame = t.current_frame();
             let $from(v) = frame.pop_operand() else {
-                return Err(concat!("target operand has invalid type").into());
+                return Err("target operand has invalid type".into());
             };
             frame.push_operand($to(v as $to_raw));
             Ok(())
@@ -1020,6 +1150,30 @@ instr_cmp_cond!(instr_if_icmpge, >=, Value::Int, "int");
 instr_cmp_cond!(instr_if_acmpeq, ==, Value::Reference, "reference");
 instr_cmp_cond!(instr_if_acmpne, !=, Value::Reference, "reference");
 
+// compare the reference on the top of operand stack (r) with null.
+// if r $cmp_op null, move PC to: {current PC} + {delta}
+// operands: delta of PC(signed int)
+macro_rules! instr_if_null {
+    ($name:ident, $cmp_op:tt) => {
+        fn $name(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+            let frame = t.current_frame();
+
+            let pc_delta = frame.next_param_u16() as i16 as i32;
+            let Value::Reference(r) = frame.pop_operand() else {
+                return Err("target operand is not type 'reference'".into());
+            };
+            if r $cmp_op 0 {
+                let jmp_dest = (frame.get_pc() as i32 + pc_delta) as u32;
+                frame.jump_pc(jmp_dest);
+            }
+            Ok(())
+        }
+    };
+}
+
+instr_if_null!(instr_ifnull, ==);
+instr_if_null!(instr_ifnonnull, !=);
+
 // move PC to: {current PC} + {delta}
 // operands: delta of PC(signed int)
 fn instr_goto(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
@@ -1165,7 +1319,7 @@ fn instr_getstatic(
     cls.initialize(t, meth_area, heap)?;
 
     let frame = t.current_frame();
-    let field = meth_area.resolve_static_field(&cls_name, &fld_name)?;
+    let (_, field) = meth_area.resolve_static_field(&cls_name, &fld_name)?;
     frame.push_operand(field.get());
     Ok(())
 }
@@ -1193,13 +1347,17 @@ fn instr_putstatic(
     cls.initialize(t, meth_area, heap)?;
 
     let frame = t.current_frame();
-    let field = meth_area.resolve_static_field(&cls_name, &fld_name)?;
+    let (_, field) = meth_area.resolve_static_field(&cls_name, &fld_name)?;
     field.put(frame.pop_operand());
     Ok(())
 }
 
 // get a value of an instance field
-fn instr_getfield(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
+fn instr_getfield(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+) -> InstructionResult {
     let (cls_name, fld_name) = {
         let frame = t.current_frame();
         let idx = frame.next_param_u16();
@@ -1211,6 +1369,8 @@ fn instr_getfield(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> Instru
         };
         (class_name.clone(), name.clone())
     };
+    // the field may be declared in a superclass of the referenced class
+    let decl_cls = meth_area.resolve_instance_field(&cls_name, &fld_name)?;
 
     let frame = t.current_frame();
     let Value::Reference(r) = frame.pop_operand() else {
@@ -1222,7 +1382,7 @@ fn instr_getfield(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> Instru
     let RefValue::Object(obj) = rv else {
         return Err("referent is not a object")?;
     };
-    let Some(field) = obj.get_field(&cls_name, &fld_name) else {
+    let Some(field) = obj.get_field(&decl_cls.name, &fld_name) else {
         return Err(format!("field {cls_name}.{fld_name} not found"))?;
     };
 
@@ -1231,7 +1391,11 @@ fn instr_getfield(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> Instru
 }
 
 // put a value to an instance field
-fn instr_putfield(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
+fn instr_putfield(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+) -> InstructionResult {
     let (cls_name, fld_name) = {
         let frame = t.current_frame();
         let idx = frame.next_param_u16();
@@ -1243,8 +1407,11 @@ fn instr_putfield(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> Instru
         };
         (class_name.clone(), name.clone())
     };
+    // the field may be declared in a superclass of the referenced class
+    let decl_cls = meth_area.resolve_instance_field(&cls_name, &fld_name)?;
 
     let frame = t.current_frame();
+    let v = frame.pop_operand();
     let Value::Reference(r) = frame.pop_operand() else {
         return Err("operand is not a reference value")?;
     };
@@ -1254,11 +1421,11 @@ fn instr_putfield(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> Instru
     let RefValue::Object(obj) = rv else {
         return Err("referent is not a object")?;
     };
-    let Some(field) = obj.get_field(&cls_name, &fld_name) else {
+    let Some(field) = obj.get_field(&decl_cls.name, &fld_name) else {
         return Err(format!("field {cls_name}.{fld_name} not found"))?;
     };
 
-    field.put(frame.pop_operand());
+    field.put(v);
     Ok(())
 }
 
@@ -1282,78 +1449,68 @@ fn instr_invokevirtual(
     };
     // resolve method referenced by method ref
     let sig = MethodSignature::new(&meth_name, desc);
-    let resolved_meth = meth_area.resolve_instance_method(&ref_cls_name, &sig)?;
+    let resolved = meth_area.resolve_class_method(&ref_cls_name, &sig)?;
+    method_area::check_method_staticness(&resolved, false)?;
 
     // get receiver object
     let frame = t.current_frame();
-    let this @ Value::Reference(r) = frame.pop_operand() else {
+    let Value::Reference(r) = *frame.peek_operand_at(resolved.1.num_args()) else {
         return Err("operand is not a reference value")?;
     };
     let Some(rv) = heap.get(r) else {
         return Err("referent not found on heap")?;
     };
-    let RefValue::Object(obj) = rv else {
-        return Err("referent is not a object")?;
-    };
 
     // select method to be called
-    let rt_cls = obj.get_class();
-    let meth = meth_area.select_instance_method(&rt_cls, resolved_meth)?;
-    let num_args = meth.num_args();
+    let (cls, meth) = meth_area.select_virtual_method(rv, resolved)?;
 
     // method call
-    // create new frame for the method, transfer the receiver(`this`) and args to the frame, then push onto frame stack
-    let caller_frame = t.current_frame();
-    let mut callee_frame = Frame::new(rt_cls, meth);
-    Frame::transfer_receiver_and_args(caller_frame, &mut callee_frame, this, num_args);
-    t.push_frame(callee_frame);
-
-    Ok(())
+    t.invoke_from_current_frame(meth_area, heap, cls, meth)
 }
 
 fn instr_invokespecial(
     t: &mut Thread,
     meth_area: &mut MethodArea,
-    _: &mut Heap,
+    heap: &mut Heap,
 ) -> InstructionResult {
-    let (cls_name, meth_name, desc) = {
+    let (cur_cls, cp_info) = {
         let frame = t.current_frame();
         let idx = frame.next_param_u16();
-        let CPInfo::Methodref {
+        (frame.current_class(), idx)
+    };
+
+    // resolve method referenced by method ref (interface method ref is used for calls like `Iface.super.m()`)
+    let resolved = match cur_cls.get_cp_info(cp_info) {
+        CPInfo::Methodref {
             class_name,
             name,
             descriptor,
-        } = frame.get_cp_info(idx)
-        else {
-            return Err("invalid methodref")?;
-        };
-        (class_name.clone(), name.clone(), descriptor.clone())
+        } => {
+            let sig = MethodSignature::new(name, descriptor.clone());
+            meth_area.resolve_class_method(class_name, &sig)?
+        }
+        CPInfo::InterfaceMethodref {
+            iface_name,
+            name,
+            descriptor,
+        } => {
+            let sig = MethodSignature::new(name, descriptor.clone());
+            meth_area.resolve_interface_method(iface_name, &sig)?
+        }
+        _ => return Err("invalid methodref")?,
     };
-    if meth_name != "<init>" {
-        Err("invoking methods other than <init> is currently not supported")?;
-    }
+    method_area::check_method_staticness(&resolved, false)?;
 
     let frame = t.current_frame();
-    let this @ Value::Reference(_) = frame.pop_operand() else {
+    let Value::Reference(_) = frame.peek_operand_at(resolved.1.num_args()) else {
         return Err("operand is not a reference value")?;
     };
 
-    // lookup method to be called
-    let cls = meth_area.resolve_class(&cls_name)?;
-    let sig = MethodSignature::new(&meth_name, desc);
-    let meth = cls
-        .lookup_instance_method(&sig)
-        .ok_or("instance method {cls.name}.{sig} not found")?;
-    let num_args = meth.num_args();
+    // select method to be called
+    let (cls, meth) = meth_area.select_special_method(&cur_cls, resolved)?;
 
     // method call
-    // create new frame for the method, transfer the receiver(`this`) and args to the frame, then push onto frame stack
-    let caller_frame = t.current_frame();
-    let mut callee_frame = Frame::new(cls, meth);
-    Frame::transfer_receiver_and_args(caller_frame, &mut callee_frame, this, num_args);
-    t.push_frame(callee_frame);
-
-    Ok(())
+    t.invoke_from_current_frame(meth_area, heap, cls, meth)
 }
 
 fn instr_invokestatic(
@@ -1361,38 +1518,47 @@ fn instr_invokestatic(
     meth_area: &mut MethodArea,
     heap: &mut Heap,
 ) -> InstructionResult {
-    let (cls_name, meth_name, desc) = {
+    // lookup methodref from const pool (interface method ref is used for static interface methods)
+    let (is_iface_ref, cls_name, sig) = {
         let frame = t.current_frame();
-
-        // lookup methodref from const pool
         let idx = frame.next_param_u16();
-        let CPInfo::Methodref {
-            class_name,
-            name,
-            descriptor,
-        } = frame.get_cp_info(idx)
-        else {
-            return Err("invalid methodref")?;
-        };
-        (class_name.clone(), name.clone(), descriptor.clone())
+        match frame.get_cp_info(idx) {
+            CPInfo::Methodref {
+                class_name,
+                name,
+                descriptor,
+            } => (
+                false,
+                class_name.clone(),
+                MethodSignature::new(name, descriptor.clone()),
+            ),
+            CPInfo::InterfaceMethodref {
+                iface_name,
+                name,
+                descriptor,
+            } => (
+                true,
+                iface_name.clone(),
+                MethodSignature::new(name, descriptor.clone()),
+            ),
+            _ => return Err("invalid methodref")?,
+        }
     };
 
-    let cls = meth_area.resolve_class(&cls_name)?;
-    cls.initialize(t, meth_area, heap)?;
-
     // lookup method to be called
-    let sig = MethodSignature::new(&meth_name, desc);
-    let (cls, meth) = meth_area.resolve_static_method(&cls_name, &sig)?;
-    let num_args = meth.num_args();
+    let resolved = if is_iface_ref {
+        meth_area.resolve_interface_method(&cls_name, &sig)?
+    } else {
+        meth_area.resolve_class_method(&cls_name, &sig)?
+    };
+    method_area::check_method_staticness(&resolved, true)?;
 
-    // method call
-    // create new frame for the method, transfer args to the frame, then push onto frame stack
-    let caller_frame = t.current_frame();
-    let mut callee_frame = Frame::new(cls, meth);
-    Frame::transfer_args(caller_frame, &mut callee_frame, num_args);
-    t.push_frame(callee_frame);
+    // the class that declares the resolved method is initialized
+    let (cls, meth) = resolved;
+    cls.clone().initialize(t, meth_area, heap)?;
 
-    Ok(())
+    // method call
+    t.invoke_from_current_frame(meth_area, heap, cls, meth)
 }
 
 fn instr_invokeinterface(
@@ -1415,36 +1581,76 @@ fn instr_invokeinterface(
     };
     // resolve method referenced by method ref
     let sig = MethodSignature::new(&meth_name, desc);
-    let resolved_meth = meth_area.resolve_instance_method(&ref_cls_name, &sig)?;
+    let resolved = meth_area.resolve_interface_method(&ref_cls_name, &sig)?;
+    method_area::check_method_staticness(&resolved, false)?;
 
     // skip 2-bytes of code: count operand and the next byte (always 0)
     let frame = t.current_frame();
     frame.next_param_u16();
 
     // get receiver object
-    let this @ Value::Reference(r) = frame.pop_operand() else {
+    let Value::Reference(r) = *frame.peek_operand_at(resolved.1.num_args()) else {
         return Err("operand is not a reference value")?;
     };
     let Some(rv) = heap.get(r) else {
         return Err("referent not found on heap")?;
     };
-    let RefValue::Object(obj) = rv else {
-        return Err("referent is not a object")?;
-    };
 
     // select method to be called
-    let rt_cls = obj.get_class();
-    let meth = meth_area.select_instance_method(&rt_cls, resolved_meth)?;
-    let num_args = meth.num_args();
+    meth_area.check_receiver_implements(rv, &ref_cls_name)?;
+    let (cls, meth) = meth_area.select_virtual_method(rv, resolved)?;
 
     // method call
-    // create new frame for the method, transfer the receiver(`this`) and args to the frame, then push onto frame stack
-    let caller_frame = t.current_frame();
-    let mut callee_frame = Frame::new(rt_cls, meth);
-    Frame::transfer_receiver_and_args(caller_frame, &mut callee_frame, this, num_args);
-    t.push_frame(callee_frame);
+    t.invoke_from_current_frame(meth_area, heap, cls, meth)
+}
 
-    Ok(())
+fn instr_invokedynamic(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+) -> InstructionResult {
+    let (cls, bsm_idx, meth_name) = {
+        let frame = t.current_frame();
+        let idx = frame.next_param_u16();
+        // skip 2-bytes of code (always 0)
+        frame.next_param_u16();
+
+        let CPInfo::InvokeDynamic {
+            bootstrap_method_attr_idx,
+            name,
+            ..
+        } = frame.get_cp_info(idx)
+        else {
+            return Err("invalid invokedynamic")?;
+        };
+        (
+            frame.current_class(),
+            *bootstrap_method_attr_idx,
+            name.clone(),
+        )
+    };
+
+    let Some(bsm) = cls.get_bootstrap_method(bsm_idx) else {
+        return Err("bootstrap method not found")?;
+    };
+    let CPInfo::MethodHandle {
+        class_name: bsm_cls_name,
+        name: bsm_name,
+        ..
+    } = cls.get_cp_info(bsm.method_ref)
+    else {
+        return Err("bootstrap method is not a method handle")?;
+    };
+
+    // linking call sites with arbitrary bootstrap methods requires java.lang.invoke,
+    // so only well-known bootstrap methods are implemented intrinsically.
+    if object_methods::is_object_methods_bootstrap(bsm_cls_name, bsm_name) {
+        object_methods::invoke(t, meth_area, heap, &cls, bsm, &meth_name)
+    } else {
+        Err(format!(
+            "invokedynamic with bootstrap method {bsm_cls_name}.{bsm_name} is not supported"
+        ))?
+    }
 }
 
 fn instr_new(t: &mut Thread, meth_area: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
@@ -1460,13 +1666,19 @@ fn instr_new(t: &mut Thread, meth_area: &mut MethodArea, heap: &mut Heap) -> Ins
     let cls = meth_area.resolve_class(cls_name)?;
     cls.clone().initialize(t, meth_area, heap)?;
 
-    let rv = heap.alloc_object(cls.clone(), meth_area);
+    let size = heap::instance_size(&cls, meth_area)?;
+    gc::make_room(t, meth_area, heap, size);
+    let rv = heap.alloc_object(cls.clone(), meth_area)?;
     t.current_frame().push_operand(rv);
 
     Ok(())
 }
 
-fn instr_newarray(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
+fn instr_newarray(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+) -> InstructionResult {
     let frame = t.current_frame();
 
     let Value::Int(len) = frame.pop_operand() else {
@@ -1486,8 +1698,10 @@ fn instr_newarray(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> Instru
         11 => "J", // long
         _ => unreachable!(),
     };
+    meth_area.resolve_class(&format!("[{item_desc}"))?;
 
-    let rv = heap.alloc_array(len as u32, item_desc);
+    gc::make_room(t, meth_area, heap, heap::array_size(len, item_desc)?);
+    let rv = heap.alloc_array(len, item_desc)?;
     t.current_frame().push_operand(rv);
 
     Ok(())
@@ -1511,24 +1725,78 @@ fn instr_anewarray(
         (length, name.clone())
     };
 
-    // resolve the class of "innermost" array element
-    let innermost = cls_name.trim_start_matches('[');
-    let innermost_cls_name = &innermost[1..innermost.len() - 1]; // innermost = "L{cls_name};"
-    _ = meth_area.resolve_class(innermost_cls_name);
+    // the array class is created along with its component class
+    let item_desc = meth_area.resolve_class(&cls_name)?.descriptor();
+    meth_area.resolve_class(&format!("[{item_desc}"))?;
 
-    let is_array = cls_name.starts_with("[");
-    let item_desc = if is_array {
-        cls_name
-    } else {
-        format!("L{cls_name};")
+    gc::make_room(t, meth_area, heap, heap::array_size(len, &item_desc)?);
+    let rv = heap.alloc_array(len, &item_desc)?;
+    t.current_frame().push_operand(rv);
+
+    Ok(())
+}
+
+fn instr_multianewarray(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+) -> InstructionResult {
+    let (arr_desc, counts) = {
+        let frame = t.current_frame();
+        let idx = frame.next_param_u16();
+        let CPInfo::Class { name } = frame.get_cp_info(idx) else {
+            return Err("not class")?;
+        };
+        let arr_desc = name.clone();
+        let dims = frame.next_param_u8();
+
+        // counts are popped from the innermost dimension
+        let mut counts = Vec::with_capacity(dims as usize);
+        for _ in 0..dims {
+            let Value::Int(count) = frame.pop_operand() else {
+                return Err("invalid type for length of array")?;
+            };
+            counts.push(count);
+        }
+        counts.reverse();
+        (arr_desc, counts)
     };
 
-    let rv = heap.alloc_array(len as u32, &item_desc);
+    // the array class is created along with the classes of its components
+    meth_area.resolve_class(&arr_desc)?;
+
+    // room for all the arrays is made at once, so that GC doesn't run while they are being linked
+    let (mut size, mut n_arrays) = (0usize, 1usize);
+    for (d, &count) in counts.iter().enumerate() {
+        let each = heap::array_size(count, &arr_desc[d + 1..])?;
+        size = size.saturating_add(n_arrays.saturating_mul(each));
+        n_arrays = n_arrays.saturating_mul(count as usize);
+    }
+    gc::make_room(t, meth_area, heap, size);
+    let rv = alloc_multi_array(heap, &arr_desc[1..], &counts)?;
     t.current_frame().push_operand(rv);
 
     Ok(())
 }
 
+// allocate an array of `counts[0]` arrays of `counts[1]` ... elements
+fn alloc_multi_array(heap: &mut Heap, item_desc: &str, counts: &[i32]) -> VMResult<Value> {
+    let rv = heap.alloc_array(counts[0], item_desc)?;
+    if counts.len() > 1 {
+        let Value::Reference(r) = rv else {
+            unreachable!()
+        };
+        for i in 0..counts[0] as u32 {
+            let sub = alloc_multi_array(heap, &item_desc[1..], &counts[1..])?;
+            let Some(RefValue::Array(arr)) = heap.get(r) else {
+                unreachable!()
+            };
+            arr.put(i, sub);
+        }
+    }
+    Ok(rv)
+}
+
 // get the length of an array
 fn instr_arraylength(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
     let frame = t.current_frame();
@@ -1546,6 +1814,20 @@ fn instr_arraylength(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> Ins
     Ok(())
 }
 
+// throw the exception object on the stack top
+fn instr_athrow(t: &mut Thread, _: &mut MethodArea, heap: &mut Heap) -> InstructionResult {
+    let frame = t.current_frame();
+    let Value::Reference(r) = frame.pop_operand() else {
+        return Err("operand is not a reference value")?;
+    };
+    if r == 0 {
+        Err(JavaException::null_pointer_exception(
+            "Cannot throw exception because the value is null",
+        ))?;
+    }
+    Err(exception::from_object(heap, r)?)?
+}
+
 fn instr_checkcast(
     t: &mut Thread,
     meth_area: &mut MethodArea,
@@ -1568,11 +1850,16 @@ fn instr_checkcast(
         return Err("referent not found on heap")?;
     };
 
-    if rv.is_instance_of(&target_cls_name, meth_area) {
+    // null can be cast to any type
+    if r == 0 || rv.is_instance_of(&target_cls_name, meth_area)? {
         frame.push_operand(v);
         Ok(())
     } else {
-        Err(format!("value is not able to cast to type '{target_cls_name}'").into())
+        Err(JavaException::class_cast_exception(format!(
+            "class {} cannot be cast to class {}",
+            rv.class_name().replace('/', "."),
+            target_cls_name.replace('/', ".")
+        )))?
     }
 }
 
@@ -1598,7 +1885,7 @@ fn instr_instanceof(
         return Err("referent not found on heap")?;
     };
 
-    let res = if rv.is_instance_of(&target_cls_name, meth_area) {
+    let res = if rv.is_instance_of(&target_cls_name, meth_area)? {
         1
     } else {
         0
@@ -1607,3 +1894,28 @@ fn instr_instanceof(
 
     Ok(())
 }
+
+// the VM runs a single thread, so monitors are never contended. only null checks are performed
+fn instr_monitorenter(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+    let Value::Reference(r) = t.current_frame().pop_operand() else {
+        return Err("operand is not a reference value")?;
+    };
+    if r == 0 {
+        Err(JavaException::null_pointer_exception(
+            "Cannot enter synchronized block because the value is null",
+        ))?;
+    }
+    Ok(())
+}
+
+fn instr_monitorexit(t: &mut Thread, _: &mut MethodArea, _: &mut Heap) -> InstructionResult {
+    let Value::Reference(r) = t.current_frame().pop_operand() else {
+        return Err("operand is not a reference value")?;
+    };
+    if r == 0 {
+        Err(JavaException::null_pointer_exception(
+            "Cannot exit synchronized block because the value is null",
+        ))?;
+    }
+    Ok(())
+}
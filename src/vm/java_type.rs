@@ -0,0 +1,431 @@
+// conversion between Rust values and Java values, used by the typed invocation API (`VM::call_static`)
+
+use super::{
+    error::VMResult,
+    handle::{JArray, JObject, JString},
+    heap::RefValue,
+    value::Value,
+    VM,
+};
+
+/// Rust types which correspond to Java types.
+pub trait JavaType: Sized {
+    /// Descriptor of the corresponding Java type.
+    fn descriptor() -> String;
+
+    /// Whether values of the Java type described by `desc` can be converted to/from the Rust type.
+    fn accepts(desc: &str) -> bool {
+        desc == Self::descriptor()
+    }
+
+    fn into_value(self, vm: &mut VM) -> VMResult<Value>;
+
+    fn from_value(v: Value, vm: &mut VM) -> VMResult<Self>;
+}
+
+macro_rules! int_like_java_type {
+    ($($ty:ty => $desc:literal),* $(,)?) => {
+        $(
+            impl JavaType for $ty {
+                fn descriptor() -> String {
+                    $desc.to_string()
+                }
+
+                fn into_value(self, _: &mut VM) -> VMResult<Value> {
+                    Ok(Value::Int(self as i32))
+                }
+
+                fn from_value(v: Value, _: &mut VM) -> VMResult<Self> {
+                    let Some(i) = v.as_int() else {
+                        return Err(format!("value is not convertible to {}: {v:?}", stringify!($ty)))?;
+                    };
+                    Ok(i as $ty)
+                }
+            }
+        )*
+    };
+}
+
+int_like_java_type! {
+    i8 => "B",
+    i16 => "S",
+    u16 => "C",
+    i32 => "I",
+}
+
+macro_rules! primitive_java_type {
+    ($($ty:ty => $desc:literal, $vtype:path),* $(,)?) => {
+        $(
+            impl JavaType for $ty {
+                fn descriptor() -> String {
+                    $desc.to_string()
+                }
+
+                fn into_value(self, _: &mut VM) -> VMResult<Value> {
+                    Ok($vtype(self))
+                }
+
+                fn from_value(v: Value, _: &mut VM) -> VMResult<Self> {
+                    let $vtype(x) = v else {
+                        return Err(format!("value is not convertible to {}: {v:?}", stringify!($ty)))?;
+                    };
+                    Ok(x)
+                }
+            }
+        )*
+    };
+}
+
+primitive_java_type! {
+    i64 => "J", Value::Long,
+    f32 => "F", Value::Float,
+    f64 => "D", Value::Double,
+}
+
+// booleans are represented as int in JVM
+impl JavaType for bool {
+    fn descriptor() -> String {
+        "Z".to_string()
+    }
+
+    fn into_value(self, _: &mut VM) -> VMResult<Value> {
+        Ok(Value::Int(self as i32))
+    }
+
+    fn from_value(v: Value, _: &mut VM) -> VMResult<Self> {
+        let Some(i) = v.as_int() else {
+            return Err(format!("value is not convertible to bool: {v:?}"))?;
+        };
+        Ok(i != 0)
+    }
+}
+
+/// Rust types which correspond to Java primitive types, except for boolean.
+///
+/// Arrays of them can be borrowed as slices (cf. [`JArray::as_slice`]).
+pub trait JavaPrimitiveType: JavaType + Copy + 'static {}
+
+impl JavaPrimitiveType for i8 {}
+impl JavaPrimitiveType for i16 {}
+impl JavaPrimitiveType for u16 {}
+impl JavaPrimitiveType for i32 {}
+impl JavaPrimitiveType for i64 {}
+impl JavaPrimitiveType for f32 {}
+impl JavaPrimitiveType for f64 {}
+
+/// Rust types which correspond to Java reference types. `Option` of them can be used for nullable values.
+pub trait JavaRefType: JavaType {}
+
+fn non_null_ref(v: Value) -> VMResult<usize> {
+    match v {
+        Value::Reference(0) => Err("unexpected null reference")?,
+        Value::Reference(r) => Ok(r),
+        _ => Err(format!("value is not a reference: {v:?}"))?,
+    }
+}
+
+impl<T: JavaRefType> JavaType for Option<T> {
+    fn descriptor() -> String {
+        T::descriptor()
+    }
+
+    fn accepts(desc: &str) -> bool {
+        T::accepts(desc)
+    }
+
+    fn into_value(self, vm: &mut VM) -> VMResult<Value> {
+        match self {
+            Some(x) => x.into_value(vm),
+            None => Ok(Value::Reference(0)),
+        }
+    }
+
+    fn from_value(v: Value, vm: &mut VM) -> VMResult<Self> {
+        match v {
+            Value::Reference(0) => Ok(None),
+            v => T::from_value(v, vm).map(Some),
+        }
+    }
+}
+
+// content of a string is copied between Rust and Java
+impl JavaType for String {
+    fn descriptor() -> String {
+        "Ljava/lang/String;".to_string()
+    }
+
+    fn into_value(self, vm: &mut VM) -> VMResult<Value> {
+        vm.heap.alloc_string(&self, &mut vm.meth_area)
+    }
+
+    fn from_value(v: Value, vm: &mut VM) -> VMResult<Self> {
+        vm.heap.get_string(non_null_ref(v)?)
+    }
+}
+
+impl JavaRefType for String {}
+
+// elements of an array are copied between Rust and Java
+impl<T: JavaType> JavaType for Vec<T> {
+    fn descriptor() -> String {
+        format!("[{}", T::descriptor())
+    }
+
+    fn accepts(desc: &str) -> bool {
+        desc.strip_prefix('[').is_some_and(T::accepts)
+    }
+
+    fn into_value(self, vm: &mut VM) -> VMResult<Value> {
+        let item_vals = self
+            .into_iter()
+            .map(|x| x.into_value(vm))
+            .collect::<VMResult<Vec<_>>>()?;
+
+        let arr @ Value::Reference(r) = vm
+            .heap
+            .alloc_array(item_vals.len() as i32, &T::descriptor())?
+        else {
+            unreachable!()
+        };
+        let Some(RefValue::Array(a)) = vm.heap.get(r) else {
+            unreachable!()
+        };
+        for (i, v) in item_vals.into_iter().enumerate() {
+            a.put(i as u32, v);
+        }
+        Ok(arr)
+    }
+
+    fn from_value(v: Value, vm: &mut VM) -> VMResult<Self> {
+        let Some(RefValue::Array(a)) = vm.heap.get(non_null_ref(v)?) else {
+            return Err("referent is not an array")?;
+        };
+        let item_vals = (0..a.len()).filter_map(|i| a.get(i)).collect::<Vec<_>>();
+        item_vals
+            .into_iter()
+            .map(|v| T::from_value(v, vm))
+            .collect()
+    }
+}
+
+impl<T: JavaType> JavaRefType for Vec<T> {}
+
+impl JavaType for JObject {
+    fn descriptor() -> String {
+        "Ljava/lang/Object;".to_string()
+    }
+
+    fn accepts(desc: &str) -> bool {
+        desc.starts_with(['L', '['])
+    }
+
+    fn into_value(self, _: &mut VM) -> VMResult<Value> {
+        Ok(Value::Reference(self.r()))
+    }
+
+    fn from_value(v: Value, vm: &mut VM) -> VMResult<Self> {
+        let r = non_null_ref(v)?;
+        Ok(JObject(vm.heap.new_handle(r)?))
+    }
+}
+
+impl JavaRefType for JObject {}
+
+impl JavaType for JString {
+    fn descriptor() -> String {
+        <String as JavaType>::descriptor()
+    }
+
+    fn into_value(self, _: &mut VM) -> VMResult<Value> {
+        Ok(Value::Reference(self.r()))
+    }
+
+    fn from_value(v: Value, vm: &mut VM) -> VMResult<Self> {
+        let r = non_null_ref(v)?;
+        match vm.heap.get_ref(r) {
+            Some(RefValue::Object(obj)) if obj.get_class().name == "java/lang/String" => {
+                Ok(JString(vm.heap.new_handle(r)?))
+            }
+            _ => Err("referent is not a java.lang.String")?,
+        }
+    }
+}
+
+impl JavaRefType for JString {}
+
+impl<T: JavaType> JavaType for JArray<T> {
+    fn descriptor() -> String {
+        <Vec<T> as JavaType>::descriptor()
+    }
+
+    fn accepts(desc: &str) -> bool {
+        <Vec<T> as JavaType>::accepts(desc)
+    }
+
+    fn into_value(self, _: &mut VM) -> VMResult<Value> {
+        Ok(Value::Reference(self.r()))
+    }
+
+    fn from_value(v: Value, vm: &mut VM) -> VMResult<Self> {
+        let r = non_null_ref(v)?;
+        let Some(RefValue::Array(arr)) = vm.heap.get_ref(r) else {
+            return Err("referent is not an array")?;
+        };
+        let desc = arr.descriptor();
+        if !<Self as JavaType>::accepts(desc.as_str()) {
+            return Err(format!(
+                "array of type {desc} can't be handled as {}",
+                <Self as JavaType>::descriptor()
+            ))?;
+        }
+        Ok(JArray::new(vm.heap.new_handle(r)?))
+    }
+}
+
+impl<T: JavaType> JavaRefType for JArray<T> {}
+
+/// Rust types which correspond to return types of Java methods.
+pub trait JavaReturn: Sized {
+    fn descriptor() -> String;
+
+    fn accepts(desc: &str) -> bool;
+
+    fn from_return_value(v: Option<Value>, vm: &mut VM) -> VMResult<Self>;
+
+    fn into_return_value(self, vm: &mut VM) -> VMResult<Option<Value>>;
+}
+
+impl<T: JavaType> JavaReturn for T {
+    fn descriptor() -> String {
+        <T as JavaType>::descriptor()
+    }
+
+    fn accepts(desc: &str) -> bool {
+        <T as JavaType>::accepts(desc)
+    }
+
+    fn from_return_value(v: Option<Value>, vm: &mut VM) -> VMResult<Self> {
+        let Some(v) = v else {
+            return Err("method returned no value")?;
+        };
+        T::from_value(v, vm)
+    }
+
+    fn into_return_value(self, vm: &mut VM) -> VMResult<Option<Value>> {
+        self.into_value(vm).map(Some)
+    }
+}
+
+// `()` corresponds to void
+impl JavaReturn for () {
+    fn descriptor() -> String {
+        "V".to_string()
+    }
+
+    fn accepts(desc: &str) -> bool {
+        desc == "V"
+    }
+
+    fn from_return_value(_: Option<Value>, _: &mut VM) -> VMResult<Self> {
+        Ok(())
+    }
+
+    fn into_return_value(self, _: &mut VM) -> VMResult<Option<Value>> {
+        Ok(None)
+    }
+}
+
+/// Tuples of Rust types which correspond to parameter types of Java methods.
+pub trait JavaArgs: Sized {
+    fn descriptors() -> Vec<String>;
+
+    fn accepts(param_descs: &[&str]) -> bool;
+
+    fn into_values(self, vm: &mut VM) -> VMResult<Vec<Value>>;
+
+    fn from_values(values: &[Value], vm: &mut VM) -> VMResult<Self>;
+}
+
+macro_rules! java_args_for_tuple {
+    ($($t:ident),*) => {
+        impl<$($t: JavaType),*> JavaArgs for ($($t,)*) {
+            fn descriptors() -> Vec<String> {
+                vec![$($t::descriptor()),*]
+            }
+
+            #[allow(unused_variables, unused_mut, non_snake_case)]
+            fn accepts(param_descs: &[&str]) -> bool {
+                let [$($t),*] = param_descs else {
+                    return false;
+                };
+                true $(&& <$t as JavaType>::accepts($t))*
+            }
+
+            #[allow(unused_variables, non_snake_case)]
+            fn into_values(self, vm: &mut VM) -> VMResult<Vec<Value>> {
+                let ($($t,)*) = self;
+                Ok(vec![$($t.into_value(vm)?),*])
+            }
+
+            #[allow(unused_variables, non_snake_case)]
+            fn from_values(values: &[Value], vm: &mut VM) -> VMResult<Self> {
+                let &[$($t),*] = values else {
+                    return Err(format!("expected {} value(s), but {} given", Self::descriptors().len(), values.len()))?;
+                };
+                Ok(($($t::from_value($t, vm)?,)*))
+            }
+        }
+    };
+}
+
+java_args_for_tuple!();
+java_args_for_tuple!(A);
+java_args_for_tuple!(A, B);
+java_args_for_tuple!(A, B, C);
+java_args_for_tuple!(A, B, C, D);
+java_args_for_tuple!(A, B, C, D, E);
+java_args_for_tuple!(A, B, C, D, E, F);
+java_args_for_tuple!(A, B, C, D, E, F, G);
+java_args_for_tuple!(A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_args_accepts() {
+        assert!(<() as JavaArgs>::accepts(&[]));
+        assert!(<(i32, i64)>::accepts(&["I", "J"]));
+        assert!(!<(i32, i64)>::accepts(&["I"]));
+        assert!(!<(i32, i64)>::accepts(&["I", "I"]));
+        assert!(<(bool, Vec<i32>)>::accepts(&["Z", "[I"]));
+        assert!(<(Option<String>,)>::accepts(&["Ljava/lang/String;"]));
+        assert!(<(JObject, JObject)>::accepts(&["LFoo;", "[[D"]));
+        assert!(!<(JObject,)>::accepts(&["I"]));
+        assert!(<(Vec<JObject>,)>::accepts(&["[LFoo;"]));
+        assert!(!<(Vec<JObject>,)>::accepts(&["LFoo;"]));
+    }
+
+    #[test]
+    fn test_descriptors() {
+        assert_eq!(
+            <(u16, Vec<Vec<f32>>, String)>::descriptors(),
+            vec!["C", "[[F", "Ljava/lang/String;"]
+        );
+        assert_eq!(<() as JavaReturn>::descriptor(), "V");
+        assert_eq!(<i8 as JavaReturn>::descriptor(), "B");
+    }
+
+    #[test]
+    fn test_args_from_values() {
+        let mut vm = VM::new("");
+        let (b, x, c) = <(bool, i64, u16)>::from_values(
+            &[Value::Int(1), Value::Long(-2), Value::Int(97)],
+            &mut vm,
+        )
+        .unwrap();
+        assert_eq!((b, x, c), (true, -2, 'a' as u16));
+        assert!(<(i32,)>::from_values(&[], &mut vm).is_err());
+        assert!(<(i32,)>::from_values(&[Value::Double(1.0)], &mut vm).is_err());
+    }
+}
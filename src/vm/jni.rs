@@ -0,0 +1,700 @@
+// JNI (Java Native Interface) support: native methods implemented in shared libraries.
+// libraries are loaded by System.loadLibrary/System.load (or VM::load_library), and native methods are linked to
+// `Java_<class>_<method>` symbols exported from them, or to functions registered by RegisterNatives.
+// native code accesses the VM via the JNIEnv function table implemented in `functions`.
+
+mod abi;
+mod functions;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr, CString},
+    path::{Path, PathBuf},
+    ptr,
+    rc::Rc,
+};
+
+use libloading::Library;
+
+use super::{
+    class::{Class, Method, MethodDescriptor, MethodSignature},
+    error::{JavaException, VMError, VMResult},
+    heap::{Forwarding, JavaArray, RefValue},
+    native::{self, NativeEnv, NativeFn},
+    options::VMConfig,
+    value::Value,
+};
+
+// JNI_VERSION_10
+const JNI_VERSION: i32 = 0x000a_0000;
+const SUPPORTED_VERSIONS: [i32; 7] = [
+    0x0001_0001,
+    0x0001_0002,
+    0x0001_0004,
+    0x0001_0006,
+    0x0001_0008,
+    0x0009_0000,
+    0x000a_0000,
+];
+
+// JNI_COMMIT / JNI_ABORT: modes of releasing array elements
+const MODE_COMMIT: i32 = 1;
+const MODE_ABORT: i32 = 2;
+
+pub struct Jni {
+    libraries: Vec<(PathBuf, Library)>,
+    // created when the first library is loaded, and shared by all native method calls
+    env: *mut JniEnv,
+}
+
+impl Jni {
+    pub fn new() -> Self {
+        Jni {
+            libraries: Vec::new(),
+            env: ptr::null_mut(),
+        }
+    }
+
+    fn env(&mut self, config: &Rc<VMConfig>) -> *const JniEnv {
+        if self.env.is_null() {
+            self.env = JniEnv::new(config.clone());
+        }
+        self.env
+    }
+
+    fn lookup_symbol(&self, name: &str) -> Option<*mut c_void> {
+        self.libraries
+            .iter()
+            .find_map(|(_, lib)| unsafe { lib.get::<*mut c_void>(name.as_bytes()) }.ok())
+            .map(|sym| *sym)
+    }
+
+    // references to the heap held by native code: local and global references, pinned arrays and the pending exception
+    pub fn references(&self) -> Vec<usize> {
+        let Some(env) = (unsafe { self.env.as_ref() }) else {
+            return Vec::new();
+        };
+        let mut refs = Vec::new();
+        for table in [&env.locals, &env.globals] {
+            refs.extend(table.borrow().iter().flatten());
+        }
+        refs.extend(env.pinned.borrow().values().filter_map(|p| match p {
+            Pinned::Array(r, _) => Some(*r),
+            _ => None,
+        }));
+        let pending = env.pending.borrow();
+        let mut exc = pending
+            .as_ref()
+            .and_then(|e| e.downcast_ref::<JavaException>());
+        while let Some(e) = exc {
+            refs.extend(e.obj_ref);
+            exc = e.cause.as_deref();
+        }
+        refs
+    }
+
+    // update references held by native code moved by GC
+    pub fn forward_references(&self, fwd: &Forwarding) {
+        let Some(env) = (unsafe { self.env.as_ref() }) else {
+            return;
+        };
+        for table in [&env.locals, &env.globals] {
+            for r in table.borrow_mut().iter_mut().flatten() {
+                *r = fwd.forward(*r);
+            }
+        }
+        for p in env.pinned.borrow_mut().values_mut() {
+            if let Pinned::Array(r, _) = p {
+                *r = fwd.forward(*r);
+            }
+        }
+        let mut pending = env.pending.borrow_mut();
+        let mut exc = pending
+            .as_mut()
+            .and_then(|e| e.downcast_mut::<JavaException>());
+        while let Some(e) = exc {
+            e.obj_ref = e.obj_ref.map(|r| fwd.forward(r));
+            exc = e.cause.as_deref_mut();
+        }
+    }
+
+    // references held by native code become invalid when the heap is discarded
+    pub fn clear_references(&mut self) {
+        if let Some(env) = unsafe { self.env.as_ref() } {
+            env.globals.borrow_mut().clear();
+        }
+    }
+}
+
+impl Drop for Jni {
+    fn drop(&mut self) {
+        if !self.env.is_null() {
+            drop(unsafe { Box::from_raw(self.env) });
+        }
+    }
+}
+
+// load the library by the name like `System.loadLibrary` does.
+// the library is searched in the library path, then by the dynamic linker (e.g. paths in LD_LIBRARY_PATH).
+pub fn load_library_by_name(env: &mut NativeEnv, name: &str) -> VMResult<()> {
+    let file_name = format!("lib{name}.so");
+    let library_path = env.meth_area.config().library_path.clone();
+    let path = library_path
+        .iter()
+        .map(|dir| dir.join(&file_name))
+        .find(|p| p.is_file())
+        .unwrap_or_else(|| PathBuf::from(&file_name));
+
+    match unsafe { Library::new(&path) } {
+        Ok(lib) => register_library(env, &path, lib),
+        Err(_) => {
+            let paths = library_path
+                .iter()
+                .map(|p| p.to_string_lossy())
+                .collect::<Vec<_>>();
+            Err(JavaException::unsatisfied_link_error(format!(
+                "no {name} in java.library.path: {}",
+                paths.join(":")
+            )))?
+        }
+    }
+}
+
+// load the library at the path, like `System.load` does
+pub fn load_library(env: &mut NativeEnv, path: &Path) -> VMResult<()> {
+    let lib = unsafe { Library::new(path) }.map_err(|err| {
+        JavaException::unsatisfied_link_error(format!(
+            "Can't load library: {} ({err})",
+            path.display()
+        ))
+    })?;
+    register_library(env, path, lib)
+}
+
+type OnLoadFn = unsafe extern "C" fn(*const JavaVm, *mut c_void) -> i32;
+
+// keep the library loaded, and call `JNI_OnLoad` in it if exists
+fn register_library(env: &mut NativeEnv, path: &Path, lib: Library) -> VMResult<()> {
+    let jni = &mut env.meth_area.natives_mut().jni;
+    if jni.libraries.iter().any(|(p, _)| p == path) {
+        return Ok(());
+    }
+    let on_load = unsafe { lib.get::<OnLoadFn>(b"JNI_OnLoad") }
+        .ok()
+        .map(|sym| *sym);
+    jni.libraries.push((path.to_path_buf(), lib));
+
+    let Some(on_load) = on_load else {
+        return Ok(());
+    };
+    let version = enter(env, |jenv| {
+        Ok(unsafe { on_load(&jenv.java_vm, ptr::null_mut()) })
+    })?;
+    if !SUPPORTED_VERSIONS.contains(&version) {
+        Err(JavaException::unsatisfied_link_error(format!(
+            "unsupported JNI version 0x{version:x} required by {}",
+            path.display()
+        )))?;
+    }
+    Ok(())
+}
+
+// find the function implementing the native method in loaded libraries, by the short name then the long name
+pub fn link(jni: &Jni, cls: &Class, meth: &Method) -> Option<NativeFn> {
+    let code = jni
+        .lookup_symbol(&native::jni_short_symbol(cls, meth))
+        .or_else(|| jni.lookup_symbol(&native::jni_long_symbol(cls, meth)))?;
+    Some(native_fn(code, &cls.name, meth))
+}
+
+// native method implemented by the C function at `code`, which takes JNIEnv, the class (or the receiver) and arguments
+fn native_fn(code: *mut c_void, class_name: &str, meth: &Method) -> NativeFn {
+    let code = code as usize;
+    let class_name = class_name.to_string();
+    let desc = meth.signature.descriptor.clone();
+    let is_static = meth.access_flags.is_static();
+    Rc::new(move |env: &mut NativeEnv, args: &[Value]| {
+        call_native(env, code, &class_name, &desc, is_static, args)
+    })
+}
+
+fn call_native(
+    env: &mut NativeEnv,
+    code: usize,
+    class_name: &str,
+    desc: &MethodDescriptor,
+    is_static: bool,
+    args: &[Value],
+) -> VMResult<Option<Value>> {
+    // static methods take the class object
+    let Value::Reference(mirror) = env.meth_area.mirror(env.heap, class_name)? else {
+        unreachable!()
+    };
+    enter(env, |jenv| {
+        let (this, args) = match (is_static, args) {
+            (true, args) => (mirror, args),
+            (false, [Value::Reference(this), args @ ..]) => (*this, args),
+            _ => return Err("receiver of native method is not a reference")?,
+        };
+
+        let mut abi_args = vec![
+            abi::Arg::Int(jenv as *const JniEnv as u64),
+            abi::Arg::Int(jenv.new_local(this) as u64),
+        ];
+        for (ty, v) in desc.param_types().into_iter().zip(args) {
+            let arg = match (ty, *v) {
+                ("F", Value::Float(f)) => abi::Arg::Float(f),
+                ("D", Value::Double(d)) => abi::Arg::Double(d),
+                ("J", Value::Long(l)) => abi::Arg::Int(l as u64),
+                (_, Value::Reference(r)) => abi::Arg::Int(jenv.new_local(r) as u64),
+                (_, v) => match v.as_int() {
+                    Some(i) => abi::Arg::Int(i as i64 as u64),
+                    None => return Err(format!("unexpected argument for native method: {v:?}"))?,
+                },
+            };
+            abi_args.push(arg);
+        }
+
+        let ret_ty = desc.return_type();
+        let ret = unsafe {
+            abi::call(
+                code as *const c_void,
+                &abi_args,
+                matches!(ret_ty, "F" | "D"),
+            )
+        }?;
+        let v = match ret_ty {
+            "V" => return Ok(None),
+            "Z" => Value::Int((ret as u8 != 0) as i32),
+            "B" => Value::Int(ret as i8 as i32),
+            "C" => Value::Int(ret as u16 as i32),
+            "S" => Value::Int(ret as i16 as i32),
+            "I" => Value::Int(ret as i32),
+            "J" => Value::Long(ret as i64),
+            "F" => Value::Float(f32::from_bits(ret as u32)),
+            "D" => Value::Double(f64::from_bits(ret)),
+            _ => Value::Reference(jenv.deref_object(ret as *mut c_void)?),
+        };
+        Ok(Some(v))
+    })
+}
+
+// let native code run with the runtime data areas in `env`, in a new frame of local references.
+// the exception thrown by the native code is returned as an error.
+fn enter<T>(env: &mut NativeEnv, f: impl FnOnce(&JniEnv) -> VMResult<T>) -> VMResult<T> {
+    let config = env.meth_area.config().clone();
+    let jenv = unsafe { &*env.meth_area.natives_mut().jni.env(&config) };
+
+    let prev_env = jenv
+        .native_env
+        .replace(env as *mut NativeEnv as *mut c_void);
+    let prev_pending = jenv.pending.take();
+    jenv.push_local_frame();
+
+    let res = f(jenv);
+
+    jenv.pop_local_frame();
+    let pending = jenv.pending.replace(prev_pending);
+    jenv.native_env.set(prev_env);
+    match pending {
+        Some(err) => Err(err),
+        None => res,
+    }
+}
+
+// JavaVM (invocation interface)
+#[repr(C)]
+struct JavaVm {
+    functions: *const *const c_void,
+    env: *const JniEnv,
+}
+
+// handles of references passed to native code: index of the reference table with the tag of the table
+const TAG_LOCAL: usize = 1;
+const TAG_GLOBAL: usize = 2;
+const TAG_BITS: usize = 2;
+
+struct JniMethod {
+    class: Rc<Class>,
+    method: Rc<Method>,
+    // first chars of parameter types (read by variadic functions)
+    param_types: CString,
+}
+
+struct JniField {
+    class: Rc<Class>,
+    name: String,
+    descriptor: String,
+    // storage of the static field
+    static_value: Option<Rc<super::value::MutValue>>,
+}
+
+// buffers of array elements or string contents lent to native code
+enum Pinned {
+    Array(usize, Box<dyn PinnedElems>),
+    Chars(Box<[u16]>),
+    Utf(Box<[u8]>),
+}
+
+trait PinnedElems {
+    fn as_ptr(&self) -> *const c_void;
+    fn write_back(&self, arr: &mut dyn JavaArray, jenv: &JniEnv) -> VMResult<()>;
+}
+
+// JNIEnv. native code sees it as a pointer to the function table, so `functions` must come first
+#[repr(C)]
+struct JniEnv {
+    functions: *const *const c_void,
+    java_vm: JavaVm,
+    table: Box<[*const c_void]>,
+    invoke_table: Box<[*const c_void]>,
+    // NativeEnv of the native method being executed (null if no native method is running)
+    native_env: Cell<*mut c_void>,
+    // references to objects (including java.lang.Class objects for jclass) held by native code
+    locals: RefCell<Vec<Option<usize>>>,
+    // start indices of local frames
+    local_frames: RefCell<Vec<usize>>,
+    globals: RefCell<Vec<Option<usize>>>,
+    // the exception thrown by native code
+    pending: RefCell<Option<VMError>>,
+    methods: RefCell<Vec<Rc<JniMethod>>>,
+    method_ids: RefCell<HashMap<(String, MethodSignature), usize>>,
+    fields: RefCell<Vec<Rc<JniField>>>,
+    pinned: RefCell<HashMap<usize, Pinned>>,
+    // configuration of the VM, whose sinks messages from JNI functions are written to
+    config: Rc<VMConfig>,
+}
+
+impl JniEnv {
+    fn new(config: Rc<VMConfig>) -> *mut JniEnv {
+        let env = Box::new(JniEnv {
+            functions: ptr::null(),
+            java_vm: JavaVm {
+                functions: ptr::null(),
+                env: ptr::null(),
+            },
+            table: functions::function_table(),
+            invoke_table: functions::invoke_table(),
+            native_env: Cell::new(ptr::null_mut()),
+            locals: RefCell::new(Vec::new()),
+            local_frames: RefCell::new(Vec::new()),
+            globals: RefCell::new(Vec::new()),
+            pending: RefCell::new(None),
+            methods: RefCell::new(Vec::new()),
+            method_ids: RefCell::new(HashMap::new()),
+            fields: RefCell::new(Vec::new()),
+            pinned: RefCell::new(HashMap::new()),
+            config,
+        });
+        let env = Box::into_raw(env);
+        unsafe {
+            (*env).functions = (*env).table.as_ptr();
+            (*env).java_vm.functions = (*env).invoke_table.as_ptr();
+            (*env).java_vm.env = env;
+        }
+        env
+    }
+
+    // runtime data areas of the running native method
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn native_env(&self) -> VMResult<&mut NativeEnv<'_>> {
+        let env = self.native_env.get() as *mut NativeEnv;
+        match env.as_mut() {
+            Some(env) => Ok(env),
+            None => Err("JNI function is called outside of native methods")?,
+        }
+    }
+
+    fn throw(&self, err: VMError) {
+        *self.pending.borrow_mut() = Some(err);
+    }
+}
+
+// reference tables
+impl JniEnv {
+    fn push_local_frame(&self) {
+        let len = self.locals.borrow().len();
+        self.local_frames.borrow_mut().push(len);
+    }
+
+    fn pop_local_frame(&self) {
+        if let Some(start) = self.local_frames.borrow_mut().pop() {
+            self.locals.borrow_mut().truncate(start);
+        }
+    }
+
+    fn new_local(&self, r: usize) -> *mut c_void {
+        if r == 0 {
+            return ptr::null_mut();
+        }
+        let mut locals = self.locals.borrow_mut();
+        locals.push(Some(r));
+        handle(TAG_LOCAL, locals.len() - 1)
+    }
+
+    // local reference to the java.lang.Class object of the class
+    fn new_local_class(&self, ne: &mut NativeEnv, cls: &Class) -> VMResult<*mut c_void> {
+        let Value::Reference(mirror) = ne.meth_area.mirror(ne.heap, &cls.name)? else {
+            unreachable!()
+        };
+        Ok(self.new_local(mirror))
+    }
+
+    fn new_global(&self, r: usize) -> *mut c_void {
+        if r == 0 {
+            return ptr::null_mut();
+        }
+        let mut globals = self.globals.borrow_mut();
+        globals.push(Some(r));
+        handle(TAG_GLOBAL, globals.len() - 1)
+    }
+
+    fn delete(&self, h: *mut c_void) {
+        let table = match h as usize & ((1 << TAG_BITS) - 1) {
+            TAG_LOCAL => &self.locals,
+            TAG_GLOBAL => &self.globals,
+            _ => return,
+        };
+        let idx = (h as usize >> TAG_BITS).wrapping_sub(1);
+        if let Some(slot) = table.borrow_mut().get_mut(idx) {
+            *slot = None;
+        }
+    }
+
+    // tag of the table that holds the reference (0 if the handle is invalid)
+    fn ref_type(&self, h: *mut c_void) -> usize {
+        match self.deref(h) {
+            Ok(Some(_)) => h as usize & ((1 << TAG_BITS) - 1),
+            _ => 0,
+        }
+    }
+
+    // referent of the handle (None for null)
+    fn deref(&self, h: *mut c_void) -> VMResult<Option<usize>> {
+        if h.is_null() {
+            return Ok(None);
+        }
+        let table = match h as usize & ((1 << TAG_BITS) - 1) {
+            TAG_LOCAL => &self.locals,
+            TAG_GLOBAL => &self.globals,
+            _ => return Err(format!("invalid JNI reference: {h:?}"))?,
+        };
+        let idx = (h as usize >> TAG_BITS).wrapping_sub(1);
+        match table.borrow().get(idx) {
+            Some(Some(r)) => Ok(Some(*r)),
+            _ => Err(format!("invalid JNI reference: {h:?}"))?,
+        }
+    }
+
+    // reference to the object on the heap (0 for null)
+    fn deref_object(&self, h: *mut c_void) -> VMResult<usize> {
+        Ok(self.deref(h)?.unwrap_or(0))
+    }
+
+    fn deref_non_null(&self, h: *mut c_void) -> VMResult<usize> {
+        match self.deref_object(h)? {
+            0 => Err(JavaException::null_pointer_exception(
+                "null is passed to JNI function",
+            ))?,
+            r => Ok(r),
+        }
+    }
+
+    // class represented by the java.lang.Class object of the handle
+    fn deref_class(&self, ne: &mut NativeEnv, h: *mut c_void) -> VMResult<Rc<Class>> {
+        let r = self.deref_non_null(h)?;
+        let Some(name) = ne.meth_area.class_name_of_mirror(r) else {
+            return Err(format!("JNI reference is not a class: {h:?}"))?;
+        };
+        let name = name.to_string();
+        ne.meth_area.resolve_class(&name)
+    }
+}
+
+fn handle(tag: usize, idx: usize) -> *mut c_void {
+    (((idx + 1) << TAG_BITS) | tag) as *mut c_void
+}
+
+// method IDs and field IDs
+impl JniEnv {
+    fn method_id(&self, class: Rc<Class>, method: Rc<Method>) -> *mut c_void {
+        let key = (class.name.clone(), method.signature.clone());
+        if let Some(id) = self.method_ids.borrow().get(&key) {
+            return *id as *mut c_void;
+        }
+        let param_types = method
+            .signature
+            .descriptor
+            .param_types()
+            .iter()
+            .map(|t| t.as_bytes()[0])
+            .collect::<Vec<_>>();
+        let m = JniMethod {
+            class,
+            method,
+            param_types: CString::new(param_types).unwrap_or_default(),
+        };
+        let mut methods = self.methods.borrow_mut();
+        methods.push(Rc::new(m));
+        self.method_ids.borrow_mut().insert(key, methods.len());
+        methods.len() as *mut c_void
+    }
+
+    fn method(&self, id: *mut c_void) -> VMResult<Rc<JniMethod>> {
+        let idx = (id as usize).wrapping_sub(1);
+        match self.methods.borrow().get(idx) {
+            Some(m) => Ok(m.clone()),
+            None => Err(format!("invalid method ID: {id:?}"))?,
+        }
+    }
+
+    fn field_id(&self, field: JniField) -> *mut c_void {
+        let mut fields = self.fields.borrow_mut();
+        let found = fields.iter().position(|f| {
+            f.class.name == field.class.name
+                && f.name == field.name
+                && f.static_value.is_some() == field.static_value.is_some()
+        });
+        let idx = found.unwrap_or_else(|| {
+            fields.push(Rc::new(field));
+            fields.len() - 1
+        });
+        (idx + 1) as *mut c_void
+    }
+
+    fn field(&self, id: *mut c_void) -> VMResult<Rc<JniField>> {
+        let idx = (id as usize).wrapping_sub(1);
+        match self.fields.borrow().get(idx) {
+            Some(f) => Ok(f.clone()),
+            None => Err(format!("invalid field ID: {id:?}"))?,
+        }
+    }
+}
+
+// buffers lent to native code
+impl JniEnv {
+    // buffers are identified by their addresses when released. they must not be empty,
+    // since empty boxes share the same dangling address
+    fn pin(&self, p: Pinned) -> *mut c_void {
+        let ptr = match &p {
+            Pinned::Array(_, elems) => elems.as_ptr(),
+            Pinned::Chars(chars) => (**chars).as_ptr() as *const c_void,
+            Pinned::Utf(bytes) => (**bytes).as_ptr() as *const c_void,
+        };
+        debug_assert!(!self.pinned.borrow().contains_key(&(ptr as usize)));
+        self.pinned.borrow_mut().insert(ptr as usize, p);
+        ptr as *mut c_void
+    }
+
+    // release the buffer. elements of arrays are written back unless the mode is JNI_ABORT
+    fn unpin(&self, ptr: *const c_void, mode: i32, env: &mut NativeEnv) -> VMResult<()> {
+        let mut pinned = self.pinned.borrow_mut();
+        let Some(p) = pinned.get(&(ptr as usize)) else {
+            return Err(format!("buffer is not lent by JNI: {ptr:?}"))?;
+        };
+        if let Pinned::Array(r, elems) = p {
+            if mode != MODE_ABORT {
+                let Some(RefValue::Array(arr)) = env.heap.get(*r) else {
+                    return Err("referent of pinned elements is not an array")?;
+                };
+                elems.write_back(arr.as_mut(), self)?;
+            }
+        }
+        if mode != MODE_COMMIT {
+            pinned.remove(&(ptr as usize));
+        }
+        Ok(())
+    }
+}
+
+// read the NUL-terminated string in modified UTF-8
+unsafe fn read_utf(s: *const c_char) -> VMResult<String> {
+    if s.is_null() {
+        Err(JavaException::null_pointer_exception(
+            "null string is passed to JNI function",
+        ))?;
+    }
+    Ok(from_modified_utf8(CStr::from_ptr(s).to_bytes()))
+}
+
+// modified UTF-8 (cf. JNI spec "Modified UTF-8 Strings"): NUL is encoded in 2 bytes,
+// and supplementary characters are encoded as surrogate pairs of 3 bytes each.
+fn to_modified_utf8(utf16: &[u16]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(utf16.len());
+    for &c in utf16 {
+        match c {
+            0x0001..=0x007F => res.push(c as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                res.push(0xC0 | (c >> 6) as u8);
+                res.push(0x80 | (c & 0x3F) as u8);
+            }
+            _ => {
+                res.push(0xE0 | (c >> 12) as u8);
+                res.push(0x80 | ((c >> 6) & 0x3F) as u8);
+                res.push(0x80 | (c & 0x3F) as u8);
+            }
+        }
+    }
+    res
+}
+
+fn from_modified_utf8(bytes: &[u8]) -> String {
+    let mut utf16 = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i] as u16;
+        let cont = |k: usize| bytes.get(i + k).map_or(0, |&b| (b & 0x3F) as u16);
+        let (c, n) = match b {
+            0x00..=0x7F => (b, 1),
+            0xC0..=0xDF => (((b & 0x1F) << 6) | cont(1), 2),
+            0xE0..=0xEF => (((b & 0x0F) << 12) | (cont(1) << 6) | cont(2), 3),
+            _ => (0xFFFD, 1),
+        };
+        utf16.push(c);
+        i += n;
+    }
+    String::from_utf16_lossy(&utf16)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_modified_utf8() {
+        let s = "a\0\u{e9}\u{3042}\u{1F600}";
+        let utf16 = s.encode_utf16().collect::<Vec<_>>();
+        let encoded = to_modified_utf8(&utf16);
+        assert_eq!(
+            encoded,
+            [0x61, 0xC0, 0x80, 0xC3, 0xA9, 0xE3, 0x81, 0x82, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]
+        );
+        assert_eq!(from_modified_utf8(&encoded), s);
+    }
+
+    #[test]
+    fn test_references() {
+        let env = unsafe { Box::from_raw(JniEnv::new(Rc::new(VMConfig::default()))) };
+        env.push_local_frame();
+        let a = env.new_local(10);
+        let g = env.new_global(20);
+        assert!(env.new_local(0).is_null());
+
+        env.push_local_frame();
+        let b = env.new_local(30);
+        assert_eq!(env.deref_object(b).unwrap(), 30);
+        env.pop_local_frame();
+        // locals in the popped frame are invalidated
+        assert!(env.deref(b).is_err());
+
+        assert_eq!(env.deref_object(a).unwrap(), 10);
+        assert_eq!(env.ref_type(a), TAG_LOCAL);
+        assert_eq!(env.deref_object(g).unwrap(), 20);
+        assert_eq!(env.ref_type(g), TAG_GLOBAL);
+
+        env.delete(g);
+        assert!(env.deref(g).is_err());
+        assert_eq!(env.ref_type(g), 0);
+    }
+}
@@ -0,0 +1,180 @@
+// calling C functions whose signatures are known only at runtime, without libffi.
+// arguments are assigned to registers and stack slots in the way the C calling convention does
+// (System V AMD64 ABI / AAPCS64), then the function is called through a pointer of the type
+// which takes all argument registers and some stack slots. extra arguments are just ignored by the callee.
+
+use std::{ffi::c_void, mem};
+
+use crate::vm::error::VMResult;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Arg {
+    // integers and pointers, extended to 64 bits
+    Int(u64),
+    Float(f32),
+    Double(f64),
+}
+
+#[cfg(target_arch = "x86_64")]
+const NUM_INT_REGS: usize = 6;
+#[cfg(target_arch = "aarch64")]
+const NUM_INT_REGS: usize = 8;
+const NUM_FLOAT_REGS: usize = 8;
+const NUM_STACK_SLOTS: usize = 16;
+
+// values of argument registers and stack slots
+struct Slots {
+    ints: [u64; NUM_INT_REGS],
+    floats: [f64; NUM_FLOAT_REGS],
+    stack: [u64; NUM_STACK_SLOTS],
+}
+
+impl Slots {
+    fn assign(args: &[Arg]) -> VMResult<Slots> {
+        let mut slots = Slots {
+            ints: [0; NUM_INT_REGS],
+            floats: [0.0; NUM_FLOAT_REGS],
+            stack: [0; NUM_STACK_SLOTS],
+        };
+        let (mut n_int, mut n_float, mut n_stack) = (0, 0, 0);
+
+        for arg in args {
+            // float is passed in the lower half of the register or the stack slot
+            let (is_float, bits) = match *arg {
+                Arg::Int(i) => (false, i),
+                Arg::Float(f) => (true, f.to_bits() as u64),
+                Arg::Double(d) => (true, d.to_bits()),
+            };
+            if !is_float && n_int < NUM_INT_REGS {
+                slots.ints[n_int] = bits;
+                n_int += 1;
+            } else if is_float && n_float < NUM_FLOAT_REGS {
+                slots.floats[n_float] = f64::from_bits(bits);
+                n_float += 1;
+            } else if n_stack < NUM_STACK_SLOTS {
+                slots.stack[n_stack] = bits;
+                n_stack += 1;
+            } else {
+                return Err("too many arguments to call native function")?;
+            }
+        }
+        Ok(slots)
+    }
+}
+
+// call the function with the arguments, and return the raw bits of the return value.
+// the value is taken from the floating-point register if `float_ret` is true (for float, the lower 32 bits are valid).
+//
+// Safety: `code` must point to a function whose parameters are of the types of `args`.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[rustfmt::skip]
+pub unsafe fn call(code: *const c_void, args: &[Arg], float_ret: bool) -> VMResult<u64> {
+    let Slots { ints: i, floats: f, stack: s } = Slots::assign(args)?;
+
+    #[cfg(target_arch = "x86_64")]
+    macro_rules! call_as {
+        ($ret:ty) => {{
+            type Fn = unsafe extern "C" fn(
+                u64, u64, u64, u64, u64, u64,
+                f64, f64, f64, f64, f64, f64, f64, f64,
+                u64, u64, u64, u64, u64, u64, u64, u64,
+                u64, u64, u64, u64, u64, u64, u64, u64,
+            ) -> $ret;
+            let func = mem::transmute::<*const c_void, Fn>(code);
+            func(
+                i[0], i[1], i[2], i[3], i[4], i[5],
+                f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7],
+                s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7],
+                s[8], s[9], s[10], s[11], s[12], s[13], s[14], s[15],
+            )
+        }};
+    }
+    #[cfg(target_arch = "aarch64")]
+    macro_rules! call_as {
+        ($ret:ty) => {{
+            type Fn = unsafe extern "C" fn(
+                u64, u64, u64, u64, u64, u64, u64, u64,
+                f64, f64, f64, f64, f64, f64, f64, f64,
+                u64, u64, u64, u64, u64, u64, u64, u64,
+                u64, u64, u64, u64, u64, u64, u64, u64,
+            ) -> $ret;
+            let func = mem::transmute::<*const c_void, Fn>(code);
+            func(
+                i[0], i[1], i[2], i[3], i[4], i[5], i[6], i[7],
+                f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7],
+                s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7],
+                s[8], s[9], s[10], s[11], s[12], s[13], s[14], s[15],
+            )
+        }};
+    }
+
+    if float_ret {
+        Ok(call_as!(f64).to_bits())
+    } else {
+        Ok(call_as!(u64))
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub unsafe fn call(_: *const c_void, _: &[Arg], _: bool) -> VMResult<u64> {
+    Err("calling native functions is not supported on this platform")?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern "C" fn mixed(a: i32, b: f64, c: i64, d: f32, e: u8, f: i16) -> f64 {
+        a as f64 + b + c as f64 + d as f64 + e as f64 + f as f64
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[rustfmt::skip]
+    extern "C" fn many(
+        a: i32, b: i64, c: i32, d: i32, e: i32, f: i32, g: i32, h: i32, i: i32, j: i32,
+        x: f64, y: f32, z: f64, w: f32, v: f64, u: f64, t: f64, s: f64, r: f32, q: f64,
+    ) -> i64 {
+        let ints = [a as i64, b, c as i64, d as i64, e as i64, f as i64, g as i64, h as i64, i as i64, j as i64];
+        let floats = [x, y as f64, z, w as f64, v, u, t, s, r as f64, q];
+        ints.iter().enumerate().map(|(k, n)| n * (k as i64 + 1)).sum::<i64>()
+            + floats.iter().enumerate().map(|(k, n)| n * 100.0 * (k as f64 + 1.0)).sum::<f64>() as i64
+    }
+
+    extern "C" fn ret_float(x: f32) -> f32 {
+        x * 2.0
+    }
+
+    #[test]
+    fn test_call() {
+        let args = [
+            Arg::Int(-1i64 as u64),
+            Arg::Double(0.5),
+            Arg::Int(10),
+            Arg::Float(0.25),
+            Arg::Int(200),
+            Arg::Int(-3i64 as u64),
+        ];
+        let ret = unsafe { call(mixed as *const c_void, &args, true) }.unwrap();
+        assert_eq!(f64::from_bits(ret), 206.75);
+
+        // arguments that don't fit in registers are passed via the stack
+        let mut args = (1..=10).map(|n| Arg::Int(n as u64)).collect::<Vec<_>>();
+        args.extend([
+            Arg::Double(1.0),
+            Arg::Float(2.0),
+            Arg::Double(3.0),
+            Arg::Float(4.0),
+            Arg::Double(5.0),
+            Arg::Double(6.0),
+            Arg::Double(7.0),
+            Arg::Double(8.0),
+            Arg::Float(9.0),
+            Arg::Double(10.0),
+        ]);
+        let ret = unsafe { call(many as *const c_void, &args, false) }.unwrap();
+        assert_eq!(ret as i64, 385 + 38500);
+
+        let ret = unsafe { call(ret_float as *const c_void, &[Arg::Float(1.5)], true) }.unwrap();
+        assert_eq!(f32::from_bits(ret as u32), 3.0);
+    }
+}
@@ -0,0 +1,1278 @@
+// functions of the JNIEnv function table and the JavaVM invocation interface.
+// errors in functions are thrown to native code as pending exceptions, and functions return zero values then.
+
+use std::{
+    ffi::{c_char, c_void},
+    fmt, ptr, slice,
+};
+
+use crate::vm::{
+    class::{MethodCodeSpec, MethodSignature},
+    diagnostics::{Category, Level},
+    error::{JavaException, VMResult},
+    exception,
+    heap::{self, JavaArray, RefValue},
+    native::NativeEnv,
+    value::{MutValue, Value},
+};
+
+use super::{
+    native_fn, read_utf, to_modified_utf8, JavaVm, JniEnv, JniField, JniMethod, Pinned,
+    PinnedElems, JNI_VERSION, SUPPORTED_VERSIONS,
+};
+
+// jobject, jclass, jstring, jarray, ...
+type JRef = *mut c_void;
+// jmethodID, jfieldID
+type JId = *mut c_void;
+
+const JNI_OK: i32 = 0;
+const JNI_ERR: i32 = -1;
+const JNI_EVERSION: i32 = -3;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union JValue {
+    z: u8,
+    b: i8,
+    c: u16,
+    s: i16,
+    i: i32,
+    j: i64,
+    f: f32,
+    d: f64,
+    l: JRef,
+}
+
+#[repr(C)]
+struct NativeMethod {
+    name: *const c_char,
+    signature: *const c_char,
+    fn_ptr: *mut c_void,
+}
+
+// variadic functions and va_list versions of them (implemented in varargs.c)
+macro_rules! varargs_functions {
+    ($($name:ident, $name_v:ident ($($param:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        extern "C" {
+            $(
+                fn $name(env: *const c_void, $($param: $ty,)* ...) -> $ret;
+                fn $name_v(env: *const c_void, $($param: $ty,)* args: *mut c_void) -> $ret;
+            )*
+        }
+    };
+}
+
+varargs_functions! {
+    kafa_jni_new_object, kafa_jni_new_object_v(cls: JRef, mid: JId) -> JRef;
+
+    kafa_jni_call_object_method, kafa_jni_call_object_method_v(obj: JRef, mid: JId) -> JRef;
+    kafa_jni_call_boolean_method, kafa_jni_call_boolean_method_v(obj: JRef, mid: JId) -> u8;
+    kafa_jni_call_byte_method, kafa_jni_call_byte_method_v(obj: JRef, mid: JId) -> i8;
+    kafa_jni_call_char_method, kafa_jni_call_char_method_v(obj: JRef, mid: JId) -> u16;
+    kafa_jni_call_short_method, kafa_jni_call_short_method_v(obj: JRef, mid: JId) -> i16;
+    kafa_jni_call_int_method, kafa_jni_call_int_method_v(obj: JRef, mid: JId) -> i32;
+    kafa_jni_call_long_method, kafa_jni_call_long_method_v(obj: JRef, mid: JId) -> i64;
+    kafa_jni_call_float_method, kafa_jni_call_float_method_v(obj: JRef, mid: JId) -> f32;
+    kafa_jni_call_double_method, kafa_jni_call_double_method_v(obj: JRef, mid: JId) -> f64;
+    kafa_jni_call_void_method, kafa_jni_call_void_method_v(obj: JRef, mid: JId) -> ();
+
+    kafa_jni_call_nonvirtual_object_method, kafa_jni_call_nonvirtual_object_method_v(obj: JRef, cls: JRef, mid: JId) -> JRef;
+    kafa_jni_call_nonvirtual_boolean_method, kafa_jni_call_nonvirtual_boolean_method_v(obj: JRef, cls: JRef, mid: JId) -> u8;
+    kafa_jni_call_nonvirtual_byte_method, kafa_jni_call_nonvirtual_byte_method_v(obj: JRef, cls: JRef, mid: JId) -> i8;
+    kafa_jni_call_nonvirtual_char_method, kafa_jni_call_nonvirtual_char_method_v(obj: JRef, cls: JRef, mid: JId) -> u16;
+    kafa_jni_call_nonvirtual_short_method, kafa_jni_call_nonvirtual_short_method_v(obj: JRef, cls: JRef, mid: JId) -> i16;
+    kafa_jni_call_nonvirtual_int_method, kafa_jni_call_nonvirtual_int_method_v(obj: JRef, cls: JRef, mid: JId) -> i32;
+    kafa_jni_call_nonvirtual_long_method, kafa_jni_call_nonvirtual_long_method_v(obj: JRef, cls: JRef, mid: JId) -> i64;
+    kafa_jni_call_nonvirtual_float_method, kafa_jni_call_nonvirtual_float_method_v(obj: JRef, cls: JRef, mid: JId) -> f32;
+    kafa_jni_call_nonvirtual_double_method, kafa_jni_call_nonvirtual_double_method_v(obj: JRef, cls: JRef, mid: JId) -> f64;
+    kafa_jni_call_nonvirtual_void_method, kafa_jni_call_nonvirtual_void_method_v(obj: JRef, cls: JRef, mid: JId) -> ();
+
+    kafa_jni_call_static_object_method, kafa_jni_call_static_object_method_v(cls: JRef, mid: JId) -> JRef;
+    kafa_jni_call_static_boolean_method, kafa_jni_call_static_boolean_method_v(cls: JRef, mid: JId) -> u8;
+    kafa_jni_call_static_byte_method, kafa_jni_call_static_byte_method_v(cls: JRef, mid: JId) -> i8;
+    kafa_jni_call_static_char_method, kafa_jni_call_static_char_method_v(cls: JRef, mid: JId) -> u16;
+    kafa_jni_call_static_short_method, kafa_jni_call_static_short_method_v(cls: JRef, mid: JId) -> i16;
+    kafa_jni_call_static_int_method, kafa_jni_call_static_int_method_v(cls: JRef, mid: JId) -> i32;
+    kafa_jni_call_static_long_method, kafa_jni_call_static_long_method_v(cls: JRef, mid: JId) -> i64;
+    kafa_jni_call_static_float_method, kafa_jni_call_static_float_method_v(cls: JRef, mid: JId) -> f32;
+    kafa_jni_call_static_double_method, kafa_jni_call_static_double_method_v(cls: JRef, mid: JId) -> f64;
+    kafa_jni_call_static_void_method, kafa_jni_call_static_void_method_v(cls: JRef, mid: JId) -> ();
+}
+
+// Call<type>Method, Call<type>MethodV and Call<type>MethodA of each type, in the order of the table
+macro_rules! call_functions {
+    ($t:ident[$start:literal..] = $call_a:ident { $($f:ident, $f_v:ident: $ty:ty;)* }) => {
+        let fns: &[*const c_void] = &[
+            $($f as *const c_void, $f_v as *const c_void, $call_a::<$ty> as *const c_void,)*
+        ];
+        $t[$start..$start + fns.len()].copy_from_slice(fns);
+    };
+}
+
+// the generic function instantiated with each type, in the order of the table
+macro_rules! typed_functions {
+    ($t:ident[$start:literal..] = $f:ident::<$($ty:ty),*>) => {
+        let fns: &[*const c_void] = &[$($f::<$ty> as *const c_void),*];
+        $t[$start..$start + fns.len()].copy_from_slice(fns);
+    };
+}
+
+// number of entries of JNINativeInterface_ (as of JDK 17)
+const NUM_FUNCTIONS: usize = 234;
+
+pub(super) fn function_table() -> Box<[*const c_void]> {
+    let mut t = vec![unimplemented as *const c_void; NUM_FUNCTIONS];
+    // reserved slots. the first one is used by variadic functions
+    t[0] = param_types as *const c_void;
+    t[1..4].fill(ptr::null());
+
+    t[4] = get_version as *const c_void;
+    t[6] = find_class as *const c_void;
+    t[10] = get_superclass as *const c_void;
+    t[11] = is_assignable_from as *const c_void;
+    t[13] = throw as *const c_void;
+    t[14] = throw_new as *const c_void;
+    t[15] = exception_occurred as *const c_void;
+    t[16] = exception_describe as *const c_void;
+    t[17] = exception_clear as *const c_void;
+    t[18] = fatal_error as *const c_void;
+    t[19] = push_local_frame as *const c_void;
+    t[20] = pop_local_frame as *const c_void;
+    t[21] = new_global_ref as *const c_void;
+    t[22] = delete_ref as *const c_void;
+    t[23] = delete_ref as *const c_void;
+    t[24] = is_same_object as *const c_void;
+    t[25] = new_local_ref as *const c_void;
+    t[26] = ensure_local_capacity as *const c_void;
+    t[27] = alloc_object as *const c_void;
+    t[28] = kafa_jni_new_object as *const c_void;
+    t[29] = kafa_jni_new_object_v as *const c_void;
+    t[30] = new_object_a as *const c_void;
+    t[31] = get_object_class as *const c_void;
+    t[32] = is_instance_of as *const c_void;
+    t[33] = get_method_id as *const c_void;
+
+    call_functions!(t[34..] = call_method_a {
+        kafa_jni_call_object_method, kafa_jni_call_object_method_v: JRef;
+        kafa_jni_call_boolean_method, kafa_jni_call_boolean_method_v: u8;
+        kafa_jni_call_byte_method, kafa_jni_call_byte_method_v: i8;
+        kafa_jni_call_char_method, kafa_jni_call_char_method_v: u16;
+        kafa_jni_call_short_method, kafa_jni_call_short_method_v: i16;
+        kafa_jni_call_int_method, kafa_jni_call_int_method_v: i32;
+        kafa_jni_call_long_method, kafa_jni_call_long_method_v: i64;
+        kafa_jni_call_float_method, kafa_jni_call_float_method_v: f32;
+        kafa_jni_call_double_method, kafa_jni_call_double_method_v: f64;
+        kafa_jni_call_void_method, kafa_jni_call_void_method_v: ();
+    });
+    call_functions!(t[64..] = call_nonvirtual_method_a {
+        kafa_jni_call_nonvirtual_object_method, kafa_jni_call_nonvirtual_object_method_v: JRef;
+        kafa_jni_call_nonvirtual_boolean_method, kafa_jni_call_nonvirtual_boolean_method_v: u8;
+        kafa_jni_call_nonvirtual_byte_method, kafa_jni_call_nonvirtual_byte_method_v: i8;
+        kafa_jni_call_nonvirtual_char_method, kafa_jni_call_nonvirtual_char_method_v: u16;
+        kafa_jni_call_nonvirtual_short_method, kafa_jni_call_nonvirtual_short_method_v: i16;
+        kafa_jni_call_nonvirtual_int_method, kafa_jni_call_nonvirtual_int_method_v: i32;
+        kafa_jni_call_nonvirtual_long_method, kafa_jni_call_nonvirtual_long_method_v: i64;
+        kafa_jni_call_nonvirtual_float_method, kafa_jni_call_nonvirtual_float_method_v: f32;
+        kafa_jni_call_nonvirtual_double_method, kafa_jni_call_nonvirtual_double_method_v: f64;
+        kafa_jni_call_nonvirtual_void_method, kafa_jni_call_nonvirtual_void_method_v: ();
+    });
+
+    t[94] = get_field_id as *const c_void;
+    typed_functions!(t[95..] = get_field::<JRef, u8, i8, u16, i16, i32, i64, f32, f64>);
+    typed_functions!(t[104..] = set_field::<JRef, u8, i8, u16, i16, i32, i64, f32, f64>);
+
+    t[113] = get_static_method_id as *const c_void;
+    call_functions!(t[114..] = call_static_method_a {
+        kafa_jni_call_static_object_method, kafa_jni_call_static_object_method_v: JRef;
+        kafa_jni_call_static_boolean_method, kafa_jni_call_static_boolean_method_v: u8;
+        kafa_jni_call_static_byte_method, kafa_jni_call_static_byte_method_v: i8;
+        kafa_jni_call_static_char_method, kafa_jni_call_static_char_method_v: u16;
+        kafa_jni_call_static_short_method, kafa_jni_call_static_short_method_v: i16;
+        kafa_jni_call_static_int_method, kafa_jni_call_static_int_method_v: i32;
+        kafa_jni_call_static_long_method, kafa_jni_call_static_long_method_v: i64;
+        kafa_jni_call_static_float_method, kafa_jni_call_static_float_method_v: f32;
+        kafa_jni_call_static_double_method, kafa_jni_call_static_double_method_v: f64;
+        kafa_jni_call_static_void_method, kafa_jni_call_static_void_method_v: ();
+    });
+
+    t[144] = get_static_field_id as *const c_void;
+    typed_functions!(t[145..] = get_static_field::<JRef, u8, i8, u16, i16, i32, i64, f32, f64>);
+    typed_functions!(t[154..] = set_static_field::<JRef, u8, i8, u16, i16, i32, i64, f32, f64>);
+
+    t[163] = new_string as *const c_void;
+    t[164] = get_string_length as *const c_void;
+    t[165] = get_string_chars as *const c_void;
+    t[166] = release_string_chars as *const c_void;
+    t[167] = new_string_utf as *const c_void;
+    t[168] = get_string_utf_length as *const c_void;
+    t[169] = get_string_utf_chars as *const c_void;
+    t[170] = release_string_utf_chars as *const c_void;
+    t[171] = get_array_length as *const c_void;
+    t[172] = new_object_array as *const c_void;
+    t[173] = get_object_array_element as *const c_void;
+    t[174] = set_object_array_element as *const c_void;
+
+    typed_functions!(t[175..] = new_array::<u8, i8, u16, i16, i32, i64, f32, f64>);
+    typed_functions!(t[183..] = get_array_elements::<u8, i8, u16, i16, i32, i64, f32, f64>);
+    t[191..199].fill(release_array_elements as *const c_void);
+    typed_functions!(t[199..] = get_array_region::<u8, i8, u16, i16, i32, i64, f32, f64>);
+    typed_functions!(t[207..] = set_array_region::<u8, i8, u16, i16, i32, i64, f32, f64>);
+
+    t[215] = register_natives as *const c_void;
+    t[217] = monitor_enter_exit as *const c_void;
+    t[218] = monitor_enter_exit as *const c_void;
+    t[219] = get_java_vm as *const c_void;
+    t[220] = get_string_region as *const c_void;
+    t[221] = get_string_utf_region as *const c_void;
+    t[222] = get_primitive_array_critical as *const c_void;
+    t[223] = release_array_elements as *const c_void;
+    t[224] = get_string_chars as *const c_void;
+    t[225] = release_string_chars as *const c_void;
+    t[228] = exception_check as *const c_void;
+    t[232] = get_object_ref_type as *const c_void;
+
+    t.into()
+}
+
+pub(super) fn invoke_table() -> Box<[*const c_void]> {
+    Box::new([
+        ptr::null(),
+        ptr::null(),
+        ptr::null(),
+        destroy_java_vm as *const c_void,
+        attach_current_thread as *const c_void,
+        detach_current_thread as *const c_void,
+        get_env as *const c_void,
+        attach_current_thread as *const c_void,
+    ])
+}
+
+// run the function with the runtime data areas. errors are thrown to native code
+unsafe fn guard<T>(
+    env: *const JniEnv,
+    default: T,
+    f: impl FnOnce(&JniEnv, &mut NativeEnv) -> VMResult<T>,
+) -> T {
+    let jenv = &*env;
+    match jenv.native_env().and_then(|ne| f(jenv, ne)) {
+        Ok(v) => v,
+        Err(err) => {
+            jenv.throw(err);
+            default
+        }
+    }
+}
+
+unsafe extern "C" fn unimplemented(env: *const JniEnv) {
+    describe(
+        env,
+        format_args!("FATAL ERROR in native method: called JNI function which is not implemented"),
+    );
+    std::process::abort();
+}
+
+// write a line to the stderr sink of the VM. JNI functions are called only by native methods,
+// but the line is reported as a diagnostic message if no native method is running
+unsafe fn describe(env: *const JniEnv, args: fmt::Arguments) {
+    match (*env).native_env() {
+        Ok(ne) => ne.meth_area.config().eprintln(args),
+        Err(_) => (*env)
+            .config
+            .report(Level::Error, Category::Interpreter, args),
+    }
+}
+
+unsafe extern "C" fn param_types(env: *const JniEnv, mid: JId) -> *const c_char {
+    match (*env).method(mid) {
+        Ok(m) => m.param_types.as_ptr(),
+        Err(_) => c"".as_ptr(),
+    }
+}
+
+// values that JNI functions take and return
+trait JniValue: Copy + 'static {
+    // descriptor of the type (for references, only the first char)
+    const DESC: &'static str;
+    const ZERO: Self;
+    fn from_value(v: Value, jenv: &JniEnv) -> VMResult<Self>;
+    fn into_value(self, jenv: &JniEnv) -> VMResult<Value>;
+}
+
+macro_rules! jni_primitive_value {
+    ($($ty:ty => $desc:literal, $zero:literal, |$v:ident| $from:expr, |$x:ident| $into:expr;)*) => {
+        $(
+            impl JniValue for $ty {
+                const DESC: &'static str = $desc;
+                const ZERO: Self = $zero;
+
+                fn from_value($v: Value, _: &JniEnv) -> VMResult<Self> {
+                    match $from {
+                        Some(x) => Ok(x),
+                        None => Err(format!("value is not of type {}: {:?}", $desc, $v))?,
+                    }
+                }
+
+                fn into_value(self, _: &JniEnv) -> VMResult<Value> {
+                    let $x = self;
+                    Ok($into)
+                }
+            }
+        )*
+    };
+}
+
+jni_primitive_value! {
+    u8 => "Z", 0, |v| v.as_int().map(|i| i as u8), |x| Value::Int((x != 0) as i32);
+    i8 => "B", 0, |v| v.as_int().map(|i| i as i8), |x| Value::Int(x as i32);
+    u16 => "C", 0, |v| v.as_int().map(|i| i as u16), |x| Value::Int(x as i32);
+    i16 => "S", 0, |v| v.as_int().map(|i| i as i16), |x| Value::Int(x as i32);
+    i32 => "I", 0, |v| v.as_int(), |x| Value::Int(x);
+    i64 => "J", 0, |v| match v { Value::Long(l) => Some(l), _ => None }, |x| Value::Long(x);
+    f32 => "F", 0.0, |v| match v { Value::Float(f) => Some(f), _ => None }, |x| Value::Float(x);
+    f64 => "D", 0.0, |v| match v { Value::Double(d) => Some(d), _ => None }, |x| Value::Double(x);
+}
+
+impl JniValue for JRef {
+    const DESC: &'static str = "L";
+    const ZERO: Self = ptr::null_mut();
+
+    fn from_value(v: Value, jenv: &JniEnv) -> VMResult<Self> {
+        match v {
+            Value::Reference(r) => Ok(jenv.new_local(r)),
+            _ => Err(format!("value is not a reference: {v:?}"))?,
+        }
+    }
+
+    fn into_value(self, jenv: &JniEnv) -> VMResult<Value> {
+        Ok(Value::Reference(jenv.deref_object(self)?))
+    }
+}
+
+// return values of Call<type>Method
+trait JniReturn {
+    const ZERO: Self;
+    fn from_return_value(v: Option<Value>, jenv: &JniEnv) -> VMResult<Self>
+    where
+        Self: Sized;
+}
+
+impl<T: JniValue> JniReturn for T {
+    const ZERO: Self = <T as JniValue>::ZERO;
+
+    fn from_return_value(v: Option<Value>, jenv: &JniEnv) -> VMResult<Self> {
+        match v {
+            Some(v) => T::from_value(v, jenv),
+            None => Err("method returned no value")?,
+        }
+    }
+}
+
+impl JniReturn for () {
+    const ZERO: Self = ();
+
+    fn from_return_value(_: Option<Value>, _: &JniEnv) -> VMResult<Self> {
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn get_version(_: *const JniEnv) -> i32 {
+    JNI_VERSION
+}
+
+// classes
+
+unsafe extern "C" fn find_class(env: *const JniEnv, name: *const c_char) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let name = read_utf(name)?;
+        // the class is initialized when its static members are accessed or it is instantiated
+        let cls = ne.meth_area.resolve_class(&name)?;
+        jenv.new_local_class(ne, &cls)
+    })
+}
+
+unsafe extern "C" fn get_superclass(env: *const JniEnv, cls: JRef) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let cls = jenv.deref_class(ne, cls)?;
+        match &cls.super_class {
+            Some(name) if !cls.access_flags.is_interface() => {
+                let super_cls = ne.meth_area.resolve_class(name)?;
+                jenv.new_local_class(ne, &super_cls)
+            }
+            _ => Ok(ptr::null_mut()),
+        }
+    })
+}
+
+unsafe extern "C" fn is_assignable_from(env: *const JniEnv, sub: JRef, sup: JRef) -> u8 {
+    guard(env, 0, |jenv, ne| {
+        let (sub, sup) = (jenv.deref_class(ne, sub)?, jenv.deref_class(ne, sup)?);
+        Ok(ne.meth_area.is_assignable(&sub.name, &sup.name)? as u8)
+    })
+}
+
+// exceptions
+
+unsafe extern "C" fn throw(env: *const JniEnv, obj: JRef) -> i32 {
+    guard(env, JNI_ERR, |jenv, ne| {
+        let r = jenv.deref_non_null(obj)?;
+        let exc = exception::from_object(ne.heap, r)?;
+        jenv.throw(exc.into());
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn throw_new(env: *const JniEnv, cls: JRef, msg: *const c_char) -> i32 {
+    guard(env, JNI_ERR, |jenv, ne| {
+        let cls = jenv.deref_class(ne, cls)?;
+        let mut exc = JavaException::new(&cls.name, "");
+        exc.message = match msg.is_null() {
+            true => None,
+            false => Some(read_utf(msg)?),
+        };
+        jenv.throw(exc.into());
+        Ok(JNI_OK)
+    })
+}
+
+unsafe extern "C" fn exception_occurred(env: *const JniEnv) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let Some(mut err) = jenv.pending.take() else {
+            return Ok(ptr::null_mut());
+        };
+        // the exception object is created on the heap if not yet
+        let res = match err.downcast_mut::<JavaException>() {
+            Some(exc) => exception::materialize(exc, ne.thread, ne.meth_area, ne.heap)
+                .map(|r| jenv.new_local(r)),
+            None => Ok(ptr::null_mut()),
+        };
+        jenv.throw(err);
+        res
+    })
+}
+
+unsafe extern "C" fn exception_describe(env: *const JniEnv) {
+    let Some(err) = (*env).pending.take() else {
+        return;
+    };
+    describe(env, format_args!("Exception in thread \"main\" {err}"));
+}
+
+unsafe extern "C" fn exception_clear(env: *const JniEnv) {
+    (*env).pending.take();
+}
+
+unsafe extern "C" fn exception_check(env: *const JniEnv) -> u8 {
+    (*env).pending.borrow().is_some() as u8
+}
+
+unsafe extern "C" fn fatal_error(env: *const JniEnv, msg: *const c_char) {
+    let msg = read_utf(msg).unwrap_or_default();
+    describe(env, format_args!("FATAL ERROR in native method: {msg}"));
+    std::process::abort();
+}
+
+// references
+
+unsafe extern "C" fn push_local_frame(env: *const JniEnv, _capacity: i32) -> i32 {
+    (*env).push_local_frame();
+    JNI_OK
+}
+
+unsafe extern "C" fn pop_local_frame(env: *const JniEnv, result: JRef) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, _| {
+        let res = jenv.deref(result)?;
+        jenv.pop_local_frame();
+        Ok(res.map_or(ptr::null_mut(), |r| jenv.new_local(r)))
+    })
+}
+
+unsafe extern "C" fn new_global_ref(env: *const JniEnv, obj: JRef) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, _| {
+        Ok(jenv
+            .deref(obj)?
+            .map_or(ptr::null_mut(), |r| jenv.new_global(r)))
+    })
+}
+
+unsafe extern "C" fn new_local_ref(env: *const JniEnv, obj: JRef) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, _| {
+        Ok(jenv
+            .deref(obj)?
+            .map_or(ptr::null_mut(), |r| jenv.new_local(r)))
+    })
+}
+
+unsafe extern "C" fn delete_ref(env: *const JniEnv, obj: JRef) {
+    (*env).delete(obj);
+}
+
+unsafe extern "C" fn ensure_local_capacity(_: *const JniEnv, _capacity: i32) -> i32 {
+    JNI_OK
+}
+
+unsafe extern "C" fn is_same_object(env: *const JniEnv, a: JRef, b: JRef) -> u8 {
+    guard(env, 0, |jenv, _| {
+        Ok((jenv.deref(a)? == jenv.deref(b)?) as u8)
+    })
+}
+
+unsafe extern "C" fn get_object_ref_type(env: *const JniEnv, obj: JRef) -> i32 {
+    (*env).ref_type(obj) as i32
+}
+
+// objects
+
+unsafe extern "C" fn alloc_object(env: *const JniEnv, cls: JRef) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let r = alloc(jenv, ne, cls)?;
+        Ok(jenv.new_local(r))
+    })
+}
+
+unsafe extern "C" fn new_object_a(
+    env: *const JniEnv,
+    cls: JRef,
+    mid: JId,
+    args: *const JValue,
+) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let m = jenv.method(mid)?;
+        if m.method.signature.name != "<init>" {
+            return Err("method ID passed to NewObject is not of a constructor")?;
+        }
+        let obj = alloc(jenv, ne, cls)?;
+        // the local reference follows the object even if GC moves it while the constructor runs
+        let local = jenv.new_local(obj);
+        let mut all_args = vec![Value::Reference(obj)];
+        all_args.extend(read_args(jenv, &m, args)?);
+        ne.thread.invoke_method(
+            ne.meth_area,
+            ne.heap,
+            m.class.clone(),
+            m.method.clone(),
+            &all_args,
+        )?;
+        Ok(local)
+    })
+}
+
+fn alloc(jenv: &JniEnv, ne: &mut NativeEnv, cls: JRef) -> VMResult<usize> {
+    let cls = jenv.deref_class(ne, cls)?;
+    if cls.access_flags.is_interface() || cls.access_flags.is_abstract() {
+        Err(JavaException::instantiation_exception(
+            cls.name.replace('/', "."),
+        ))?;
+    }
+    cls.clone().initialize(ne.thread, ne.meth_area, ne.heap)?;
+    let Value::Reference(r) = ne.heap.alloc_object(cls, ne.meth_area)? else {
+        unreachable!()
+    };
+    Ok(r)
+}
+
+unsafe extern "C" fn get_object_class(env: *const JniEnv, obj: JRef) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let r = jenv.deref_non_null(obj)?;
+        let cls = match ne.heap.get(r) {
+            Some(RefValue::Object(obj)) => obj.get_class(),
+            Some(RefValue::Array(arr)) => ne.meth_area.resolve_class(arr.descriptor().as_str())?,
+            _ => Err("referent not found on heap")?,
+        };
+        jenv.new_local_class(ne, &cls)
+    })
+}
+
+unsafe extern "C" fn is_instance_of(env: *const JniEnv, obj: JRef, cls: JRef) -> u8 {
+    guard(env, 0, |jenv, ne| {
+        let cls = jenv.deref_class(ne, cls)?;
+        match jenv.deref_object(obj)? {
+            0 => Ok(1),
+            r => match ne.heap.get_ref(r) {
+                Some(rv) => Ok(rv.is_instance_of(&cls.name, ne.meth_area)? as u8),
+                None => Err("referent not found on heap")?,
+            },
+        }
+    })
+}
+
+// methods
+
+unsafe extern "C" fn get_method_id(
+    env: *const JniEnv,
+    cls: JRef,
+    name: *const c_char,
+    sig: *const c_char,
+) -> JId {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let (cls, sig) = (jenv.deref_class(ne, cls)?, signature(name, sig)?);
+        cls.clone().initialize(ne.thread, ne.meth_area, ne.heap)?;
+
+        let (decl_cls, meth) = if sig.name == "<init>" {
+            // constructors are not inherited
+            match cls.lookup_instance_method(&sig) {
+                Some(m) => (cls.clone(), m),
+                None => Err(no_such_method(&cls.name, &sig))?,
+            }
+        } else if cls.access_flags.is_interface() {
+            ne.meth_area.resolve_interface_method(&cls.name, &sig)?
+        } else {
+            ne.meth_area.resolve_class_method(&cls.name, &sig)?
+        };
+        if meth.access_flags.is_static() {
+            Err(no_such_method(&cls.name, &sig))?;
+        }
+        Ok(jenv.method_id(decl_cls, meth))
+    })
+}
+
+unsafe extern "C" fn get_static_method_id(
+    env: *const JniEnv,
+    cls: JRef,
+    name: *const c_char,
+    sig: *const c_char,
+) -> JId {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let (cls, sig) = (jenv.deref_class(ne, cls)?, signature(name, sig)?);
+        cls.clone().initialize(ne.thread, ne.meth_area, ne.heap)?;
+
+        let (decl_cls, meth) = ne.meth_area.resolve_class_method(&cls.name, &sig)?;
+        if !meth.access_flags.is_static() {
+            Err(no_such_method(&cls.name, &sig))?;
+        }
+        Ok(jenv.method_id(decl_cls, meth))
+    })
+}
+
+unsafe fn signature(name: *const c_char, sig: *const c_char) -> VMResult<MethodSignature> {
+    Ok(MethodSignature::new_with_raw_descriptor(
+        &read_utf(name)?,
+        &read_utf(sig)?,
+    ))
+}
+
+fn no_such_method(cls_name: &str, sig: &MethodSignature) -> JavaException {
+    JavaException::no_such_method_error(format!("{}.{sig}", cls_name.replace('/', ".")))
+}
+
+// convert arguments in the jvalue array to values of parameter types of the method
+unsafe fn read_args(jenv: &JniEnv, m: &JniMethod, args: *const JValue) -> VMResult<Vec<Value>> {
+    let params = m.method.signature.descriptor.param_types();
+    if params.is_empty() {
+        return Ok(Vec::new());
+    }
+    let args = slice::from_raw_parts(args, params.len());
+    params
+        .iter()
+        .zip(args)
+        .map(|(ty, a)| {
+            let v = match ty.as_bytes()[0] {
+                b'Z' => Value::Int((a.z != 0) as i32),
+                b'B' => Value::Int(a.b as i32),
+                b'C' => Value::Int(a.c as i32),
+                b'S' => Value::Int(a.s as i32),
+                b'I' => Value::Int(a.i),
+                b'J' => Value::Long(a.j),
+                b'F' => Value::Float(a.f),
+                b'D' => Value::Double(a.d),
+                _ => Value::Reference(jenv.deref_object(a.l)?),
+            };
+            Ok(v)
+        })
+        .collect()
+}
+
+unsafe extern "C" fn call_method_a<R: JniReturn>(
+    env: *const JniEnv,
+    obj: JRef,
+    mid: JId,
+    args: *const JValue,
+) -> R {
+    guard(env, R::ZERO, |jenv, ne| {
+        let m = jenv.method(mid)?;
+        let this = jenv.deref_non_null(obj)?;
+        let args = read_args(jenv, &m, args)?;
+        let ret = ne.thread.invoke_virtual(
+            ne.meth_area,
+            ne.heap,
+            Value::Reference(this),
+            &m.method.signature,
+            &args,
+        )?;
+        R::from_return_value(ret, jenv)
+    })
+}
+
+unsafe extern "C" fn call_nonvirtual_method_a<R: JniReturn>(
+    env: *const JniEnv,
+    obj: JRef,
+    _cls: JRef,
+    mid: JId,
+    args: *const JValue,
+) -> R {
+    guard(env, R::ZERO, |jenv, ne| {
+        let m = jenv.method(mid)?;
+        let this = jenv.deref_non_null(obj)?;
+        let mut all_args = vec![Value::Reference(this)];
+        all_args.extend(read_args(jenv, &m, args)?);
+        let ret = ne.thread.invoke_method(
+            ne.meth_area,
+            ne.heap,
+            m.class.clone(),
+            m.method.clone(),
+            &all_args,
+        )?;
+        R::from_return_value(ret, jenv)
+    })
+}
+
+unsafe extern "C" fn call_static_method_a<R: JniReturn>(
+    env: *const JniEnv,
+    _cls: JRef,
+    mid: JId,
+    args: *const JValue,
+) -> R {
+    guard(env, R::ZERO, |jenv, ne| {
+        let m = jenv.method(mid)?;
+        let args = read_args(jenv, &m, args)?;
+        m.class
+            .clone()
+            .initialize(ne.thread, ne.meth_area, ne.heap)?;
+        let ret = ne.thread.invoke_method(
+            ne.meth_area,
+            ne.heap,
+            m.class.clone(),
+            m.method.clone(),
+            &args,
+        )?;
+        R::from_return_value(ret, jenv)
+    })
+}
+
+// fields
+
+unsafe extern "C" fn get_field_id(
+    env: *const JniEnv,
+    cls: JRef,
+    name: *const c_char,
+    sig: *const c_char,
+) -> JId {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let (cls, name, desc) = (jenv.deref_class(ne, cls)?, read_utf(name)?, read_utf(sig)?);
+        cls.clone().initialize(ne.thread, ne.meth_area, ne.heap)?;
+
+        // search the class and its superclasses for the field
+        let mut c = cls.clone();
+        loop {
+            if c.instance_fields()
+                .any(|f| f.name == name && f.descriptor == desc)
+            {
+                let field = JniField {
+                    class: c,
+                    name,
+                    descriptor: desc,
+                    static_value: None,
+                };
+                return Ok(jenv.field_id(field));
+            }
+            let Some(super_name) = &c.super_class else {
+                return Err(JavaException::no_such_field_error(name))?;
+            };
+            c = ne.meth_area.resolve_class(super_name)?;
+        }
+    })
+}
+
+unsafe extern "C" fn get_static_field_id(
+    env: *const JniEnv,
+    cls: JRef,
+    name: *const c_char,
+    sig: *const c_char,
+) -> JId {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let (cls, name, desc) = (jenv.deref_class(ne, cls)?, read_utf(name)?, read_utf(sig)?);
+        cls.clone().initialize(ne.thread, ne.meth_area, ne.heap)?;
+
+        let (decl_cls, value) = ne.meth_area.resolve_static_field(&cls.name, &name)?;
+        if decl_cls.static_field_descriptor(&name) != Some(&desc) {
+            Err(JavaException::no_such_field_error(name.clone()))?;
+        }
+        let field = JniField {
+            class: decl_cls,
+            name,
+            descriptor: desc,
+            static_value: Some(value),
+        };
+        Ok(jenv.field_id(field))
+    })
+}
+
+// check the type of the field, then run `f` with the storage of the field
+fn with_field<T: JniValue, R>(
+    jenv: &JniEnv,
+    ne: &mut NativeEnv,
+    obj: JRef,
+    fid: JId,
+    f: impl FnOnce(&MutValue) -> VMResult<R>,
+) -> VMResult<R> {
+    let field = jenv.field(fid)?;
+    // references are accessed as objects, including arrays
+    let is_ref = field.descriptor.starts_with(['L', '[']);
+    if !(field.descriptor.starts_with(T::DESC) || T::DESC == "L" && is_ref) {
+        Err(format!(
+            "field {} of type {} is accessed as {}",
+            field.name,
+            field.descriptor,
+            T::DESC
+        ))?;
+    }
+    if let Some(v) = &field.static_value {
+        return f(v);
+    }
+    let r = jenv.deref_non_null(obj)?;
+    let Some(RefValue::Object(obj)) = ne.heap.get(r) else {
+        return Err("referent is not an object")?;
+    };
+    match obj.get_field(&field.class.name, &field.name) {
+        Some(v) => f(v),
+        None => Err(format!("object doesn't have field {}", field.name))?,
+    }
+}
+
+unsafe extern "C" fn get_field<T: JniValue>(env: *const JniEnv, obj: JRef, fid: JId) -> T {
+    guard(env, T::ZERO, |jenv, ne| {
+        with_field::<T, _>(jenv, ne, obj, fid, |v| T::from_value(v.get(), jenv))
+    })
+}
+
+unsafe extern "C" fn set_field<T: JniValue>(env: *const JniEnv, obj: JRef, fid: JId, val: T) {
+    guard(env, (), |jenv, ne| {
+        let val = val.into_value(jenv)?;
+        with_field::<T, _>(jenv, ne, obj, fid, |v| {
+            v.put(val);
+            Ok(())
+        })
+    })
+}
+
+unsafe extern "C" fn get_static_field<T: JniValue>(env: *const JniEnv, _cls: JRef, fid: JId) -> T {
+    get_field::<T>(env, ptr::null_mut(), fid)
+}
+
+unsafe extern "C" fn set_static_field<T: JniValue>(
+    env: *const JniEnv,
+    _cls: JRef,
+    fid: JId,
+    val: T,
+) {
+    set_field::<T>(env, ptr::null_mut(), fid, val)
+}
+
+// strings
+
+fn string_utf16(jenv: &JniEnv, ne: &mut NativeEnv, s: JRef) -> VMResult<Vec<u16>> {
+    let r = jenv.deref_non_null(s)?;
+    Ok(ne.heap.get_string(r)?.encode_utf16().collect())
+}
+
+fn new_string_object(jenv: &JniEnv, ne: &mut NativeEnv, s: &str) -> VMResult<JRef> {
+    let Value::Reference(r) = ne.heap.alloc_string(s, ne.meth_area)? else {
+        unreachable!()
+    };
+    Ok(jenv.new_local(r))
+}
+
+unsafe extern "C" fn new_string(env: *const JniEnv, chars: *const u16, len: i32) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let chars = match len {
+            0 => &[],
+            _ => slice::from_raw_parts(chars, len as usize),
+        };
+        new_string_object(jenv, ne, &String::from_utf16_lossy(chars))
+    })
+}
+
+unsafe extern "C" fn new_string_utf(env: *const JniEnv, bytes: *const c_char) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        if bytes.is_null() {
+            return Ok(ptr::null_mut());
+        }
+        new_string_object(jenv, ne, &read_utf(bytes)?)
+    })
+}
+
+unsafe extern "C" fn get_string_length(env: *const JniEnv, s: JRef) -> i32 {
+    guard(env, 0, |jenv, ne| {
+        Ok(string_utf16(jenv, ne, s)?.len() as i32)
+    })
+}
+
+unsafe extern "C" fn get_string_utf_length(env: *const JniEnv, s: JRef) -> i32 {
+    guard(env, 0, |jenv, ne| {
+        Ok(to_modified_utf8(&string_utf16(jenv, ne, s)?).len() as i32)
+    })
+}
+
+unsafe extern "C" fn get_string_chars(env: *const JniEnv, s: JRef, is_copy: *mut u8) -> *const u16 {
+    guard(env, ptr::null(), |jenv, ne| {
+        let mut chars = string_utf16(jenv, ne, s)?;
+        // buffers are never empty, so that each of them has its own address (cf. JniEnv::pin)
+        chars.push(0);
+        set_is_copy(is_copy);
+        Ok(jenv.pin(Pinned::Chars(chars.into())) as *const u16)
+    })
+}
+
+unsafe extern "C" fn release_string_chars(env: *const JniEnv, _s: JRef, chars: *const u16) {
+    guard(env, (), |jenv, ne| {
+        jenv.unpin(chars as *const c_void, 0, ne)
+    })
+}
+
+unsafe extern "C" fn get_string_utf_chars(
+    env: *const JniEnv,
+    s: JRef,
+    is_copy: *mut u8,
+) -> *const c_char {
+    guard(env, ptr::null(), |jenv, ne| {
+        let mut bytes = to_modified_utf8(&string_utf16(jenv, ne, s)?);
+        bytes.push(0);
+        set_is_copy(is_copy);
+        Ok(jenv.pin(Pinned::Utf(bytes.into())) as *const c_char)
+    })
+}
+
+unsafe extern "C" fn release_string_utf_chars(env: *const JniEnv, _s: JRef, chars: *const c_char) {
+    guard(env, (), |jenv, ne| {
+        jenv.unpin(chars as *const c_void, 0, ne)
+    })
+}
+
+unsafe extern "C" fn get_string_region(
+    env: *const JniEnv,
+    s: JRef,
+    start: i32,
+    len: i32,
+    buf: *mut u16,
+) {
+    guard(env, (), |jenv, ne| {
+        let chars = string_utf16(jenv, ne, s)?;
+        let region = region_of(&chars, start, len)?;
+        ptr::copy_nonoverlapping(region.as_ptr(), buf, region.len());
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn get_string_utf_region(
+    env: *const JniEnv,
+    s: JRef,
+    start: i32,
+    len: i32,
+    buf: *mut c_char,
+) {
+    guard(env, (), |jenv, ne| {
+        let chars = string_utf16(jenv, ne, s)?;
+        let mut bytes = to_modified_utf8(region_of(&chars, start, len)?);
+        bytes.push(0);
+        ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, bytes.len());
+        Ok(())
+    })
+}
+
+fn region_of<T>(items: &[T], start: i32, len: i32) -> VMResult<&[T]> {
+    let (start, end) = (start as usize, start as usize + len as usize);
+    if start > items.len() || end > items.len() || len < 0 || (start as i32) < 0 {
+        Err(JavaException::array_index_out_of_bounds_exception(format!(
+            "Range [{start}, {start} + {len}) out of bounds for length {}",
+            items.len()
+        )))?;
+    }
+    Ok(&items[start..end])
+}
+
+unsafe fn set_is_copy(is_copy: *mut u8) {
+    if !is_copy.is_null() {
+        *is_copy = 1;
+    }
+}
+
+// arrays
+
+fn with_array<R>(
+    jenv: &JniEnv,
+    ne: &mut NativeEnv,
+    arr: JRef,
+    f: impl FnOnce(&mut dyn JavaArray) -> VMResult<R>,
+) -> VMResult<R> {
+    let r = jenv.deref_non_null(arr)?;
+    match ne.heap.get(r) {
+        Some(RefValue::Array(arr)) => f(arr.as_mut()),
+        _ => Err("referent is not an array")?,
+    }
+}
+
+// elements of the primitive array of type T
+fn elements<T: JniValue>(jenv: &JniEnv, arr: &dyn JavaArray) -> VMResult<Vec<T>> {
+    if arr.descriptor().as_str() != format!("[{}", T::DESC) {
+        Err(format!(
+            "array of {} is accessed as array of {}",
+            arr.descriptor().as_str(),
+            T::DESC
+        ))?;
+    }
+    (0..arr.len())
+        .map(|i| match arr.get(i) {
+            Some(v) => T::from_value(v, jenv),
+            None => unreachable!(),
+        })
+        .collect()
+}
+
+unsafe extern "C" fn get_array_length(env: *const JniEnv, arr: JRef) -> i32 {
+    guard(env, 0, |jenv, ne| {
+        with_array(jenv, ne, arr, |a| Ok(a.len() as i32))
+    })
+}
+
+unsafe extern "C" fn new_object_array(env: *const JniEnv, len: i32, cls: JRef, init: JRef) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let cls = jenv.deref_class(ne, cls)?;
+        let init = jenv.deref_object(init)?;
+        ne.meth_area
+            .resolve_class(&format!("[{}", cls.descriptor()))?;
+        let Value::Reference(r) = ne.heap.alloc_array(len, &cls.descriptor())? else {
+            unreachable!()
+        };
+        if init != 0 {
+            with_array(jenv, ne, jenv.new_local(r), |a| {
+                (0..a.len()).for_each(|i| a.put(i, Value::Reference(init)));
+                Ok(())
+            })?;
+        }
+        Ok(jenv.new_local(r))
+    })
+}
+
+unsafe extern "C" fn get_object_array_element(env: *const JniEnv, arr: JRef, idx: i32) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let v = with_array(jenv, ne, arr, |a| match a.get(idx as u32) {
+            Some(v) if idx >= 0 => Ok(v),
+            _ => Err(index_out_of_bounds(idx, a.len()))?,
+        })?;
+        JRef::from_value(v, jenv)
+    })
+}
+
+unsafe extern "C" fn set_object_array_element(env: *const JniEnv, arr: JRef, idx: i32, val: JRef) {
+    guard(env, (), |jenv, ne| {
+        let v = jenv.deref_object(val)?;
+        let r = jenv.deref_non_null(arr)?;
+        let Some(RefValue::Array(a)) = ne.heap.get_ref(r) else {
+            return Err("referent is not an array")?;
+        };
+        if idx < 0 || idx as u32 >= a.len() {
+            Err(index_out_of_bounds(idx, a.len()))?;
+        }
+        let desc = a.descriptor();
+        let elem_desc = &desc.as_str()[1..];
+        let target = heap::component_class_name(elem_desc).unwrap_or(elem_desc);
+        let assignable = match ne.heap.get_ref(v) {
+            _ if v == 0 => true,
+            Some(rv) => rv.is_instance_of(target, ne.meth_area)?,
+            None => false,
+        };
+        if !assignable {
+            Err(JavaException::array_store_exception(format!(
+                "value can't be stored to an array of {}",
+                target.replace('/', ".")
+            )))?;
+        }
+        with_array(jenv, ne, arr, |a| {
+            a.put(idx as u32, Value::Reference(v));
+            Ok(())
+        })
+    })
+}
+
+fn index_out_of_bounds(idx: i32, len: u32) -> JavaException {
+    JavaException::array_index_out_of_bounds_exception(format!(
+        "Index {idx} out of bounds for length {len}"
+    ))
+}
+
+unsafe extern "C" fn new_array<T: JniValue>(env: *const JniEnv, len: i32) -> JRef {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let Value::Reference(r) = ne.heap.alloc_array(len, T::DESC)? else {
+            unreachable!()
+        };
+        Ok(jenv.new_local(r))
+    })
+}
+
+impl<T: JniValue> PinnedElems for Box<[T]> {
+    fn as_ptr(&self) -> *const c_void {
+        (**self).as_ptr() as *const c_void
+    }
+
+    // the trailing padding is not written back
+    fn write_back(&self, arr: &mut dyn JavaArray, jenv: &JniEnv) -> VMResult<()> {
+        for (i, x) in self.iter().take(arr.len() as usize).enumerate() {
+            arr.put(i as u32, x.into_value(jenv)?);
+        }
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn get_array_elements<T: JniValue>(
+    env: *const JniEnv,
+    arr: JRef,
+    is_copy: *mut u8,
+) -> *mut T {
+    guard(env, ptr::null_mut(), |jenv, ne| {
+        let mut elems = with_array(jenv, ne, arr, |a| elements::<T>(jenv, a))?;
+        // padded so that the buffer is never empty (cf. JniEnv::pin)
+        elems.push(T::ZERO);
+        set_is_copy(is_copy);
+        let r = jenv.deref_non_null(arr)?;
+        let elems: Box<[T]> = elems.into();
+        Ok(jenv.pin(Pinned::Array(r, Box::new(elems))) as *mut T)
+    })
+}
+
+unsafe extern "C" fn release_array_elements(
+    env: *const JniEnv,
+    _arr: JRef,
+    elems: *const c_void,
+    mode: i32,
+) {
+    guard(env, (), |jenv, ne| jenv.unpin(elems, mode, ne))
+}
+
+unsafe extern "C" fn get_primitive_array_critical(
+    env: *const JniEnv,
+    arr: JRef,
+    is_copy: *mut u8,
+) -> *mut c_void {
+    let desc = guard(env, None, |jenv, ne| {
+        with_array(jenv, ne, arr, |a| {
+            Ok(Some(a.descriptor().as_str().to_string()))
+        })
+    });
+    match desc.as_deref() {
+        Some("[Z") => get_array_elements::<u8>(env, arr, is_copy) as *mut c_void,
+        Some("[B") => get_array_elements::<i8>(env, arr, is_copy) as *mut c_void,
+        Some("[C") => get_array_elements::<u16>(env, arr, is_copy) as *mut c_void,
+        Some("[S") => get_array_elements::<i16>(env, arr, is_copy) as *mut c_void,
+        Some("[I") => get_array_elements::<i32>(env, arr, is_copy) as *mut c_void,
+        Some("[J") => get_array_elements::<i64>(env, arr, is_copy) as *mut c_void,
+        Some("[F") => get_array_elements::<f32>(env, arr, is_copy) as *mut c_void,
+        Some("[D") => get_array_elements::<f64>(env, arr, is_copy) as *mut c_void,
+        _ => ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn get_array_region<T: JniValue>(
+    env: *const JniEnv,
+    arr: JRef,
+    start: i32,
+    len: i32,
+    buf: *mut T,
+) {
+    guard(env, (), |jenv, ne| {
+        let elems = with_array(jenv, ne, arr, |a| elements::<T>(jenv, a))?;
+        let region = region_of(&elems, start, len)?;
+        ptr::copy_nonoverlapping(region.as_ptr(), buf, region.len());
+        Ok(())
+    })
+}
+
+unsafe extern "C" fn set_array_region<T: JniValue>(
+    env: *const JniEnv,
+    arr: JRef,
+    start: i32,
+    len: i32,
+    buf: *const T,
+) {
+    guard(env, (), |jenv, ne| {
+        with_array(jenv, ne, arr, |a| {
+            let elems = elements::<T>(jenv, a)?;
+            region_of(&elems, start, len)?;
+            for i in 0..len.max(0) as usize {
+                let v = (*buf.add(i)).into_value(jenv)?;
+                a.put(start as u32 + i as u32, v);
+            }
+            Ok(())
+        })
+    })
+}
+
+// native methods
+
+unsafe extern "C" fn register_natives(
+    env: *const JniEnv,
+    cls: JRef,
+    methods: *const NativeMethod,
+    n: i32,
+) -> i32 {
+    guard(env, JNI_ERR, |jenv, ne| {
+        let cls = jenv.deref_class(ne, cls)?;
+        if n <= 0 {
+            return Ok(JNI_OK);
+        }
+        for m in slice::from_raw_parts(methods, n as usize) {
+            let sig = signature(m.name, m.signature)?;
+            let Some(meth) = cls
+                .lookup_method(&sig)
+                .filter(|m| matches!(m.code_spec, MethodCodeSpec::Native))
+            else {
+                return Err(no_such_method(&cls.name, &sig))?;
+            };
+            let f = native_fn(m.fn_ptr, &cls.name, &meth);
+            ne.meth_area.natives_mut().register(
+                &cls.name,
+                &sig.name,
+                &sig.descriptor.to_string(),
+                f,
+            );
+        }
+        Ok(JNI_OK)
+    })
+}
+
+// there is only one thread
+unsafe extern "C" fn monitor_enter_exit(_: *const JniEnv, _obj: JRef) -> i32 {
+    JNI_OK
+}
+
+unsafe extern "C" fn get_java_vm(env: *const JniEnv, vm: *mut *const JavaVm) -> i32 {
+    *vm = &(*env).java_vm;
+    JNI_OK
+}
+
+// invocation interface
+
+unsafe extern "C" fn destroy_java_vm(_: *const JavaVm) -> i32 {
+    JNI_ERR
+}
+
+unsafe extern "C" fn attach_current_thread(
+    vm: *const JavaVm,
+    penv: *mut *const JniEnv,
+    _args: *mut c_void,
+) -> i32 {
+    *penv = (*vm).env;
+    JNI_OK
+}
+
+unsafe extern "C" fn detach_current_thread(_: *const JavaVm) -> i32 {
+    JNI_OK
+}
+
+unsafe extern "C" fn get_env(vm: *const JavaVm, penv: *mut *const JniEnv, version: i32) -> i32 {
+    if !SUPPORTED_VERSIONS.contains(&version) {
+        *penv = ptr::null();
+        return JNI_EVERSION;
+    }
+    *penv = (*vm).env;
+    JNI_OK
+}
@@ -0,0 +1,134 @@
+// variadic JNI functions (e.g. CallIntMethod) and their va_list versions (e.g. CallIntMethodV),
+// which can't be defined in stable Rust. they read arguments into a jvalue array according to the parameter types
+// of the method, then delegate to the array versions (e.g. CallIntMethodA) in the function table.
+
+#include <stdarg.h>
+#include <stdint.h>
+
+typedef union {
+    uint8_t z;
+    int8_t b;
+    uint16_t c;
+    int16_t s;
+    int32_t i;
+    int64_t j;
+    float f;
+    double d;
+    void *l;
+} jvalue;
+
+// a method can take at most 255 arguments
+#define MAX_ARGS 256
+
+// the function table of JNIEnv. its reserved slot 0 holds the function returning parameter types of the method
+#define FUNCTION(env, idx) ((*(void ***)(env))[idx])
+typedef const char *(*param_types_fn)(void *env, void *mid);
+
+static void read_args(void *env, void *mid, va_list args, jvalue *out) {
+    const char *types = ((param_types_fn)FUNCTION(env, 0))(env, mid);
+    for (int i = 0; types[i] != '\0' && i < MAX_ARGS; i++) {
+        // arguments smaller than int are promoted to int, and float to double
+        switch (types[i]) {
+        case 'Z': out[i].z = (uint8_t)va_arg(args, int); break;
+        case 'B': out[i].b = (int8_t)va_arg(args, int); break;
+        case 'C': out[i].c = (uint16_t)va_arg(args, int); break;
+        case 'S': out[i].s = (int16_t)va_arg(args, int); break;
+        case 'I': out[i].i = va_arg(args, int32_t); break;
+        case 'J': out[i].j = va_arg(args, int64_t); break;
+        case 'F': out[i].f = (float)va_arg(args, double); break;
+        case 'D': out[i].d = va_arg(args, double); break;
+        default: out[i].l = va_arg(args, void *); break;
+        }
+    }
+}
+
+// functions taking (env, obj or class, methodID, ...)
+#define CALL(name, type, idx_a)                                                                 \
+    type kafa_jni_##name##_v(void *env, void *obj, void *mid, va_list args) {                   \
+        jvalue a[MAX_ARGS];                                                                     \
+        read_args(env, mid, args, a);                                                           \
+        return ((type(*)(void *, void *, void *, const jvalue *))FUNCTION(env, idx_a))(env, obj, mid, a); \
+    }                                                                                           \
+    type kafa_jni_##name(void *env, void *obj, void *mid, ...) {                                \
+        va_list args;                                                                           \
+        va_start(args, mid);                                                                    \
+        type r = kafa_jni_##name##_v(env, obj, mid, args);                                      \
+        va_end(args);                                                                           \
+        return r;                                                                               \
+    }
+
+#define CALL_VOID(name, idx_a)                                                                  \
+    void kafa_jni_##name##_v(void *env, void *obj, void *mid, va_list args) {                   \
+        jvalue a[MAX_ARGS];                                                                     \
+        read_args(env, mid, args, a);                                                           \
+        ((void (*)(void *, void *, void *, const jvalue *))FUNCTION(env, idx_a))(env, obj, mid, a); \
+    }                                                                                           \
+    void kafa_jni_##name(void *env, void *obj, void *mid, ...) {                                \
+        va_list args;                                                                           \
+        va_start(args, mid);                                                                    \
+        kafa_jni_##name##_v(env, obj, mid, args);                                               \
+        va_end(args);                                                                           \
+    }
+
+// functions taking (env, obj, class, methodID, ...)
+#define CALL_NONVIRTUAL(name, type, idx_a)                                                      \
+    type kafa_jni_##name##_v(void *env, void *obj, void *cls, void *mid, va_list args) {        \
+        jvalue a[MAX_ARGS];                                                                     \
+        read_args(env, mid, args, a);                                                           \
+        return ((type(*)(void *, void *, void *, void *, const jvalue *))FUNCTION(env, idx_a))(env, obj, cls, mid, a); \
+    }                                                                                           \
+    type kafa_jni_##name(void *env, void *obj, void *cls, void *mid, ...) {                     \
+        va_list args;                                                                           \
+        va_start(args, mid);                                                                    \
+        type r = kafa_jni_##name##_v(env, obj, cls, mid, args);                                 \
+        va_end(args);                                                                           \
+        return r;                                                                               \
+    }
+
+#define CALL_NONVIRTUAL_VOID(name, idx_a)                                                       \
+    void kafa_jni_##name##_v(void *env, void *obj, void *cls, void *mid, va_list args) {        \
+        jvalue a[MAX_ARGS];                                                                     \
+        read_args(env, mid, args, a);                                                           \
+        ((void (*)(void *, void *, void *, void *, const jvalue *))FUNCTION(env, idx_a))(env, obj, cls, mid, a); \
+    }                                                                                           \
+    void kafa_jni_##name(void *env, void *obj, void *cls, void *mid, ...) {                     \
+        va_list args;                                                                           \
+        va_start(args, mid);                                                                    \
+        kafa_jni_##name##_v(env, obj, cls, mid, args);                                          \
+        va_end(args);                                                                           \
+    }
+
+CALL(new_object, void *, 30)
+
+CALL(call_object_method, void *, 36)
+CALL(call_boolean_method, uint8_t, 39)
+CALL(call_byte_method, int8_t, 42)
+CALL(call_char_method, uint16_t, 45)
+CALL(call_short_method, int16_t, 48)
+CALL(call_int_method, int32_t, 51)
+CALL(call_long_method, int64_t, 54)
+CALL(call_float_method, float, 57)
+CALL(call_double_method, double, 60)
+CALL_VOID(call_void_method, 63)
+
+CALL_NONVIRTUAL(call_nonvirtual_object_method, void *, 66)
+CALL_NONVIRTUAL(call_nonvirtual_boolean_method, uint8_t, 69)
+CALL_NONVIRTUAL(call_nonvirtual_byte_method, int8_t, 72)
+CALL_NONVIRTUAL(call_nonvirtual_char_method, uint16_t, 75)
+CALL_NONVIRTUAL(call_nonvirtual_short_method, int16_t, 78)
+CALL_NONVIRTUAL(call_nonvirtual_int_method, int32_t, 81)
+CALL_NONVIRTUAL(call_nonvirtual_long_method, int64_t, 84)
+CALL_NONVIRTUAL(call_nonvirtual_float_method, float, 87)
+CALL_NONVIRTUAL(call_nonvirtual_double_method, double, 90)
+CALL_NONVIRTUAL_VOID(call_nonvirtual_void_method, 93)
+
+CALL(call_static_object_method, void *, 116)
+CALL(call_static_boolean_method, uint8_t, 119)
+CALL(call_static_byte_method, int8_t, 122)
+CALL(call_static_char_method, uint16_t, 125)
+CALL(call_static_short_method, int16_t, 128)
+CALL(call_static_int_method, int32_t, 131)
+CALL(call_static_long_method, int64_t, 134)
+CALL(call_static_float_method, float, 137)
+CALL(call_static_double_method, double, 140)
+CALL_VOID(call_static_void_method, 143)
@@ -3,52 +3,308 @@ use std::{
     rc::Rc,
 };
 
-use crate::class_file::MethodAccessFlags;
+use crate::class_file::{ClassAccessFlags, MethodAccessFlags};
 
 use super::{
-    class::{Class, Method, MethodSignature},
+    class::{Class, DefiningLoader, Method, MethodSignature, ReferenceKind, ARRAY_INTERFACES},
     class_loader::ClassLoader,
-    error::VMResult,
-    value::MutValue,
+    diagnostics::{Category, Level},
+    error::{JavaException, VMResult},
+    heap::{self, Heap, RefValue},
+    native::NativeRegistry,
+    options::{VMConfig, Verification},
+    value::{MutValue, Value},
+    verifier,
 };
 
+const CLASS_CLASS: &str = "java/lang/Class";
+
+// names of primitive types (and void) by their descriptors, which have their own java.lang.Class objects
+const PRIMITIVE_TYPES: [(&str, &str); 9] = [
+    ("Z", "boolean"),
+    ("B", "byte"),
+    ("C", "char"),
+    ("S", "short"),
+    ("I", "int"),
+    ("J", "long"),
+    ("F", "float"),
+    ("D", "double"),
+    ("V", "void"),
+];
+
 pub struct MethodArea {
     classes: HashMap<String, Rc<Class>>,
+    // names of classes whose superclass/superinterfaces are being resolved
+    loading: HashSet<String>,
     loader: ClassLoader,
+    // implementations of native methods
+    natives: NativeRegistry,
+    // java.lang.Class objects representing classes (and array types by their descriptors), which are roots of GC
+    mirrors: HashMap<String, MutValue>,
+    config: Rc<VMConfig>,
 }
 
 impl MethodArea {
-    pub fn new(loader: ClassLoader) -> Self {
+    pub fn new(config: Rc<VMConfig>) -> Self {
+        Self::with_natives(config, NativeRegistry::with_builtins())
+    }
+
+    pub fn with_natives(config: Rc<VMConfig>, natives: NativeRegistry) -> Self {
         MethodArea {
             classes: HashMap::new(),
-            loader,
+            loading: HashSet::new(),
+            loader: ClassLoader::new(&config.boot_classpath, &config.classpath),
+            natives,
+            mirrors: HashMap::new(),
+            config,
         }
     }
+
+    // method area without classes, natives nor classpath, which allocates nothing. it stands in for the one in use while the latter is lent
+    pub fn placeholder(config: Rc<VMConfig>) -> Self {
+        MethodArea {
+            classes: HashMap::new(),
+            loading: HashSet::new(),
+            loader: ClassLoader::new(&[], &[]),
+            natives: NativeRegistry::new(),
+            mirrors: HashMap::new(),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Rc<VMConfig> {
+        &self.config
+    }
+
+    pub fn loaded_classes(&self) -> impl Iterator<Item = &Rc<Class>> {
+        self.classes.values()
+    }
+
+    pub fn loaded_class(&self, class_name: &str) -> Option<&Rc<Class>> {
+        self.classes.get(class_name)
+    }
+
+    // static fields of all loaded classes, whose values are roots of GC
+    pub fn static_fields(&self) -> impl Iterator<Item = &MutValue> {
+        self.classes.values().flat_map(|cls| cls.static_fields())
+    }
+
+    pub fn mirrors(&self) -> impl Iterator<Item = &MutValue> {
+        self.mirrors.values()
+    }
+
+    pub fn natives(&self) -> &NativeRegistry {
+        &self.natives
+    }
+
+    pub fn natives_mut(&mut self) -> &mut NativeRegistry {
+        &mut self.natives
+    }
 }
 
 impl MethodArea {
     pub fn resolve_class(&mut self, class_name: &str) -> VMResult<Rc<Class>> {
-        match self.classes.get(class_name) {
-            Some(cls) => Ok(cls.clone()),
-            None => {
-                // load a .class file under the class path
-                let cls = self
-                    .loader
-                    .load(class_name)
-                    .map_err(|err| format!("failed to load '{class_name}': {err}"))?;
-
-                // resolve the super class / interfaces
-                if let Some(super_cls_name) = &cls.super_class {
-                    self.resolve_class(super_cls_name)?;
-                }
-                for iface_name in &cls.interfaces {
-                    self.resolve_class(iface_name)?;
-                }
+        if let Some(cls) = self.classes.get(class_name) {
+            return Ok(cls.clone());
+        }
 
-                let cls = Rc::new(cls);
-                self.classes.insert(class_name.to_string(), cls.clone());
-                Ok(cls)
+        // the class is requested again while resolving its superclasses -> the class would be its own superclass
+        if !self.loading.insert(class_name.to_string()) {
+            Err(JavaException::class_circularity_error(class_name))?;
+        }
+        let res = match class_name.starts_with('[') {
+            true => self.create_array_class(class_name),
+            false => self.load_class(class_name),
+        };
+        self.loading.remove(class_name);
+
+        let cls = Rc::new(res?);
+        self.classes.insert(class_name.to_string(), cls.clone());
+        Ok(cls)
+    }
+
+    // load a class, and resolve its superclass & superinterfaces (JVM spec 5.3.5.)
+    fn load_class(&mut self, class_name: &str) -> VMResult<Class> {
+        // load a .class file under the class path
+        let (mut cls, source) = self.loader.load(class_name).map_err(|err| match err
+            .is::<JavaException>()
+        {
+            // linkage errors are thrown into Java world as they are
+            true => err,
+            false => format!("failed to load '{class_name}': {err}").into(),
+        })?;
+        let from_boot = self.loader.is_boot_entry(source);
+        cls.defining_loader = match from_boot {
+            true => DefiningLoader::Boot,
+            false => DefiningLoader::App,
+        };
+        self.config.report(
+            Level::Info,
+            Category::ClassLoading,
+            format_args!(
+                "loaded {} from {}",
+                class_name.replace('/', "."),
+                source.display()
+            ),
+        );
+        for tag in cls.skipped_cp_tags() {
+            self.config.report(
+                Level::Warning,
+                Category::ClassLoading,
+                format_args!(
+                    "skipped unsupported constant pool entry of type {tag} in {class_name}"
+                ),
+            );
+        }
+
+        // resolve the super class / interfaces
+        if let Some(super_cls_name) = &cls.super_class {
+            let super_cls = self.resolve_class(super_cls_name)?;
+            if super_cls.access_flags.is_interface() {
+                Err(JavaException::incompatible_class_change_error(format!(
+                    "class {} has interface {} as super class",
+                    cls.name, super_cls.name
+                )))?;
+            }
+            check_permitted_subclass(&cls, &super_cls)?;
+            cls.reference_kind = super_cls.reference_kind;
+            cls.has_finalizer = super_cls.has_finalizer;
+        }
+        if let Some(kind) = ReferenceKind::of_class(&cls.name) {
+            cls.reference_kind = Some(kind);
+        }
+        cls.has_finalizer |= cls.declares_finalizer();
+        for iface_name in &cls.interfaces {
+            let iface = self.resolve_class(iface_name)?;
+            if !iface.access_flags.is_interface() {
+                Err(JavaException::incompatible_class_change_error(format!(
+                    "class {} can not implement {}, because it is not an interface",
+                    cls.name, iface.name
+                )))?;
             }
+            check_permitted_subclass(&cls, &iface)?;
+        }
+
+        let verifies = match self.config.verification {
+            Verification::None => false,
+            Verification::Remote => cls.defining_loader != DefiningLoader::Boot,
+            Verification::All => true,
+        };
+        if verifies {
+            let mut superclasses = Vec::new();
+            let mut super_name = cls.super_class.clone();
+            while let Some(name) = super_name {
+                let sc = self.resolve_class(&name)?;
+                super_name = sc.super_class.clone();
+                superclasses.push(sc);
+            }
+            verifier::verify_class(&cls, &superclasses)?;
+            self.config.report(
+                Level::Debug,
+                Category::Linking,
+                format_args!("verified {}", class_name.replace('/', ".")),
+            );
+        }
+        self.natives.replace_methods(&mut cls);
+        Ok(cls)
+    }
+
+    // create an array class (JVM spec 5.3.3.). the component class is resolved first, and defines the array class.
+    // the superclass and superinterfaces are resolved as well, so that the array class can be treated as ordinary ones
+    fn create_array_class(&mut self, name: &str) -> VMResult<Class> {
+        let comp_desc = &name[1..];
+        let (comp_flags, loader) = match heap::component_class_name(comp_desc) {
+            Some(comp_name) => {
+                let comp = self.resolve_class(comp_name)?;
+                (comp.access_flags.clone(), comp.defining_loader)
+            }
+            None if comp_desc != "V" && primitive_name(comp_desc).is_some() => {
+                (ClassAccessFlags::PUBLIC, DefiningLoader::Boot)
+            }
+            None => Err(JavaException::no_class_def_found_error(format!(
+                "invalid array type: {name}"
+            )))?,
+        };
+        for sc_name in ["java/lang/Object"].iter().chain(ARRAY_INTERFACES.iter()) {
+            self.resolve_class(sc_name)?;
+        }
+        self.config.report(
+            Level::Info,
+            Category::ClassLoading,
+            format_args!("created array class {}", name.replace('/', ".")),
+        );
+        Ok(Class::array_class(name, comp_flags, loader))
+    }
+
+    // get the java.lang.Class object representing the class (resolving it), or the primitive type if `class_name` is its name (e.g. "int").
+    // it is created at the first request
+    pub fn mirror(&mut self, heap: &mut Heap, class_name: &str) -> VMResult<Value> {
+        if let Some(m) = self.mirrors.get(class_name) {
+            return Ok(m.get());
+        }
+        let cls = match PRIMITIVE_TYPES.iter().any(|&(_, n)| n == class_name) {
+            true => None,
+            false => Some(self.resolve_class(class_name)?),
+        };
+        // the component type is linked from the mirror of an array class, as Class.getComponentType() reads it
+        let comp_mirror = match cls.as_ref().and_then(|c| c.component_type.as_deref()) {
+            Some(comp_desc) => {
+                let comp_name = heap::component_class_name(comp_desc)
+                    .or_else(|| primitive_name(comp_desc))
+                    .ok_or("invalid component type")?;
+                Some(self.mirror(heap, comp_name)?)
+            }
+            None => None,
+        };
+
+        let class_cls = self.resolve_class(CLASS_CLASS)?;
+        let mirror @ Value::Reference(r) = heap.alloc_object(class_cls, self)? else {
+            unreachable!()
+        };
+        // the name is cached in the mirror, so that Class.getName() doesn't have to compute it
+        let name = heap.alloc_string(&class_name.replace('/', "."), self)?;
+        if let Some(RefValue::Object(obj)) = heap.get(r) {
+            if let Some(f) = obj.get_field(CLASS_CLASS, "name") {
+                f.put(name);
+            }
+            if let (Some(f), Some(m)) = (obj.get_field(CLASS_CLASS, "componentType"), comp_mirror) {
+                f.put(m);
+            }
+        }
+        self.mirrors
+            .insert(class_name.to_string(), MutValue::from_val(mirror));
+        Ok(mirror)
+    }
+
+    // name of the class (or the primitive type) represented by the java.lang.Class object
+    pub fn class_name_of_mirror(&self, mirror: usize) -> Option<&str> {
+        self.mirrors
+            .iter()
+            .find(|(_, m)| m.get() == Value::Reference(mirror))
+            .map(|(name, _)| name.as_str())
+    }
+
+    // whether a value of type S can be assigned to type T (cf. JVM spec 6.5. checkcast), resolving the classes of both.
+    // types are class names, or descriptors for array types
+    pub fn is_assignable(&mut self, s: &str, t: &str) -> VMResult<bool> {
+        if s == t {
+            return Ok(true);
+        }
+        let s_cls = self.resolve_class(s)?;
+        let t_cls = self.resolve_class(t)?;
+        match (&s_cls.component_type, &t_cls.component_type) {
+            // both are array types -> components are the same primitive type, or S's component can be assigned to T's
+            (Some(sc), Some(tc)) => {
+                match (
+                    heap::component_class_name(sc),
+                    heap::component_class_name(tc),
+                ) {
+                    (Some(sc), Some(tc)) => self.is_assignable(sc, tc),
+                    _ => Ok(sc == tc),
+                }
+            }
+            // otherwise T must be a superclass or a superinterface of S (array classes extend Object, and implement Cloneable and Serializable)
+            _ => Ok(self.is_subclass_of(s, t)),
         }
     }
 
@@ -78,64 +334,85 @@ impl MethodArea {
 
     pub fn collect_all_superclasses(&self, class_name: &str) -> VMResult<Vec<Rc<Class>>> {
         let Some(c) = self.classes.get(class_name) else {
-            return Err("class '{class_name}' have not been resolved")?;
+            return Err(format!("class '{class_name}' have not been resolved"))?;
         };
 
         // ignoring warning, since sc_set is just temporal for deduplication
         #[allow(clippy::mutable_key_type)]
         let mut sc_set = HashSet::new();
-        if let Some(sc_name) = &c.super_class {
-            let scs = self.collect_all_superclasses(sc_name)?;
-            for sc in scs {
-                sc_set.insert(sc);
-            }
-        }
-        for iface_name in &c.interfaces {
-            let scs = self.collect_all_superclasses(iface_name)?;
-            for sc in scs {
+        for sc_name in c.super_class.iter().chain(c.interfaces.iter()) {
+            let Some(sc) = self.classes.get(sc_name) else {
+                return Err(format!("class '{sc_name}' have not been resolved"))?;
+            };
+            sc_set.insert(sc.clone());
+            for sc in self.collect_all_superclasses(sc_name)? {
                 sc_set.insert(sc);
             }
         }
         Ok(sc_set.into_iter().collect())
     }
 
-    pub fn resolve_static_field(&mut self, class_name: &str, name: &str) -> VMResult<Rc<MutValue>> {
+    // field lookup (JVM spec 5.4.3.2.) for static fields.
+    // returns the resolved field along with the class that declares it.
+    pub fn resolve_static_field(
+        &mut self,
+        class_name: &str,
+        name: &str,
+    ) -> VMResult<(Rc<Class>, Rc<MutValue>)> {
+        match self.lookup_static_field(class_name, name)? {
+            Some(f) => Ok(f),
+            None => Err(format!("static field '{class_name}.{name}' not found"))?,
+        }
+    }
+
+    fn lookup_static_field(
+        &mut self,
+        class_name: &str,
+        name: &str,
+    ) -> VMResult<Option<(Rc<Class>, Rc<MutValue>)>> {
         // the symbolic reference to C given by the field reference must first be resolved.
         let cls = self.resolve_class(class_name)?;
 
         // 1. If C declares a field with the name and descriptor specified by the field reference, field lookup succeeds.
         if let Some(f) = cls.lookup_static_field(name) {
-            return Ok(f);
+            return Ok(Some((cls, f)));
         }
 
         // 2. Otherwise, field lookup is applied recursively to the direct superinterfaces of the specified class or interface C.
         for iface_name in &cls.interfaces {
-            if let Some(f) = self.maybe_resolve_static_field(iface_name, name)? {
-                return Ok(f);
+            if let Some(f) = self.lookup_static_field(iface_name, name)? {
+                return Ok(Some(f));
             }
         }
 
         // 3. Otherwise, if C has a superclass S, field lookup is applied recursively to S.
         if let Some(sc_name) = &cls.super_class {
-            if let Some(f) = self.maybe_resolve_static_field(sc_name, name)? {
-                return Ok(f);
-            }
+            return self.lookup_static_field(sc_name, name);
         }
 
         // 4. Otherwise, field lookup fails.
-        Err("static field '{class_name}.{name}' not found")?
+        Ok(None)
     }
 
-    fn maybe_resolve_static_field(
-        &mut self,
-        class_name: &str,
-        name: &str,
-    ) -> VMResult<Option<Rc<MutValue>>> {
-        let cls = self.resolve_class(class_name)?;
-        Ok(cls.lookup_static_field(name))
+    // field lookup (JVM spec 5.4.3.2.) for instance fields.
+    // returns the class that declares the field.
+    pub fn resolve_instance_field(&mut self, class_name: &str, name: &str) -> VMResult<Rc<Class>> {
+        let mut cls = self.resolve_class(class_name)?;
+        loop {
+            if cls.instance_fields().any(|f| f.name == name) {
+                return Ok(cls);
+            }
+            // interfaces declare no instance fields, so only superclasses are searched
+            let Some(sc_name) = &cls.super_class else {
+                return Err(format!("instance field '{class_name}.{name}' not found"))?;
+            };
+            cls = self.resolve_class(sc_name)?;
+        }
     }
 
-    pub fn resolve_static_method(
+    // method resolution (JVM spec 5.4.3.3.), for methods referenced by Methodref.
+    // returns the resolved method along with the class that declares it.
+    pub fn resolve_class_method(
         &mut self,
         class_name: &str,
         sig: &MethodSignature,
@@ -143,192 +420,481 @@ impl MethodArea {
         // the symbolic reference to C given by the method reference is first resolved.
         let cls = self.resolve_class(class_name)?;
 
-        // static interface method resolution (a portion of JVM spec 5.4.3.4.)
-        // static interface method is never inherited, so it's enough.
+        // 1. If C is an interface, method resolution throws an IncompatibleClassChangeError.
         if cls.access_flags.is_interface() {
-            return cls
-                .lookup_static_method(sig)
-                .map(|meth| (cls.clone(), meth))
-                .ok_or("static method '{class_name}.{sig}' not found".into());
+            Err(JavaException::incompatible_class_change_error(format!(
+                "Found interface {}, but class was expected",
+                dotted(class_name)
+            )))?;
         }
 
-        // static method resolution (JVM spec 5.4.3.3.)
-        // static interface method is never inherited, so no need to search superinterfaces.
-
-        // 2. (Otherwise,) if C declares a method with the name and descriptor specified by the method reference, method lookup succeeds.
-        if let Some(cm) = cls
-            .lookup_static_method(sig)
-            .map(|meth| (cls.clone(), meth))
-        {
-            return Ok(cm);
-        }
-        // Otherwise, if C has a superclass, step 2 of method resolution is recursively invoked on the direct superclass of C.
-        if let Some(sc_name) = &cls.super_class {
-            if let Ok(cm) = self.resolve_static_method(sc_name, sig) {
-                return Ok(cm);
+        // 2. Otherwise, method resolution attempts to locate the referenced method in C and its superclasses
+        let mut c = cls.clone();
+        loop {
+            if let Some(meth) = c.lookup_method(sig) {
+                return Ok((c, meth));
             }
+            let Some(sc_name) = &c.super_class else {
+                break;
+            };
+            c = self.resolve_class(sc_name)?;
         }
-        Err("static method '{class_name}.{sig}' not found")?
-    }
 
-    pub fn resolve_instance_method(
-        &mut self,
-        class_name: &str,
-        sig: &MethodSignature,
-    ) -> VMResult<Rc<Method>> {
-        // the symbolic reference to C given by the method reference is first resolved.
-        let cls = self.resolve_class(class_name)?;
-
-        if cls.access_flags.is_interface() {
-            self.resolve_instance_method_interface(&cls.name, sig)
-        } else {
-            self.resolve_instance_method_class(&cls.name, sig)
-        }
-        .map_err(|err| {
-            format!("failed to resolve instance method '{class_name}.{sig}': {err}").into()
-        })
+        // 3. Otherwise, method resolution attempts to locate the referenced method in the superinterfaces of the specified class C
+        self.resolve_superinterface_method(&cls, sig)
     }
 
-    fn resolve_instance_method_class(
+    // interface method resolution (JVM spec 5.4.3.4.), for methods referenced by InterfaceMethodref.
+    // returns the resolved method along with the interface (or java.lang.Object) that declares it.
+    pub fn resolve_interface_method(
         &mut self,
-        cls_name: &str,
+        iface_name: &str,
         sig: &MethodSignature,
-    ) -> VMResult<Rc<Method>> {
-        let cls = self.classes.get(cls_name).expect("").clone();
-        assert!(!cls.access_flags.is_interface());
+    ) -> VMResult<(Rc<Class>, Rc<Method>)> {
+        let iface = self.resolve_class(iface_name)?;
+
+        // 1. If C is not an interface, interface method resolution throws an IncompatibleClassChangeError.
+        if !iface.access_flags.is_interface() {
+            Err(JavaException::incompatible_class_change_error(format!(
+                "Found class {}, but interface was expected",
+                dotted(iface_name)
+            )))?;
+        }
 
-        if let Some(m) = cls.lookup_instance_method(sig) {
-            return Ok(m);
+        // 2. Otherwise, if C declares a method with the name and descriptor specified by the interface method reference, method lookup succeeds.
+        if let Some(meth) = iface.lookup_method(sig) {
+            return Ok((iface, meth));
         }
 
-        if let Some(sc_name) = &cls.super_class {
-            if let Ok(m) = self.resolve_instance_method_class(sc_name, sig) {
-                return Ok(m);
+        // 3. Otherwise, if the class Object declares a method with the name and descriptor specified by the interface method reference,
+        // which has its ACC_PUBLIC flag set and does not have its ACC_STATIC flag set, method lookup succeeds.
+        let obj_cls = self.resolve_class("java/lang/Object")?;
+        if let Some(meth) = obj_cls.lookup_instance_method(sig) {
+            if meth.access_flags.is_public_non_static() {
+                return Ok((obj_cls, meth));
             }
         }
 
-        self.find_maximally_specific_superinterface_method(&cls, sig)
+        // 4, 5. Otherwise, lookup the superinterfaces of C
+        self.resolve_superinterface_method(&iface, sig)
     }
 
-    fn resolve_instance_method_interface(
+    fn resolve_superinterface_method(
         &mut self,
-        iface_name: &str,
+        cls: &Class,
         sig: &MethodSignature,
-    ) -> VMResult<Rc<Method>> {
-        let iface = self.classes.get(iface_name).expect("").clone();
-        assert!(iface.access_flags.is_interface());
-        if let Some(m) = iface.lookup_instance_method(sig) {
-            return Ok(m);
-        }
+    ) -> VMResult<(Rc<Class>, Rc<Method>)> {
+        let candidates = self.maximally_specific_superinterface_methods(cls, sig)?;
 
-        if let Ok(m) = self.resolve_instance_method_class("java/lang/Object", sig) {
-            if m.access_flags.is_public_non_static() {
-                return Ok(m);
-            }
+        // if the maximally-specific superinterface methods of C for the name and descriptor include exactly one method
+        // that does not have its ACC_ABSTRACT flag set, then this method is chosen and method lookup succeeds.
+        let mut non_abstracts = candidates.iter().filter(|(_, m)| !is_abstract(m));
+        if let (Some(cm), None) = (non_abstracts.next(), non_abstracts.next()) {
+            return Ok(cm.clone());
         }
-
-        self.find_maximally_specific_superinterface_method(&iface, sig)
+        // Otherwise, if any superinterface of C declares a method with the name and descriptor specified by the method reference
+        // that has neither its ACC_PRIVATE flag nor its ACC_STATIC flag set, one of these is arbitrarily chosen and method lookup succeeds.
+        if let Some(cm) = candidates.into_iter().next() {
+            return Ok(cm);
+        }
+        // Otherwise, method lookup fails.
+        Err(JavaException::no_such_method_error(method_name(
+            &cls.name, sig,
+        )))?
     }
 
     // select method to be called, based on the algorithm specified in JVM spec 5.4.6.
+    // returns the selected method along with the class that declares it.
     pub fn select_instance_method(
         &mut self,
-        runtime_class: &Class,
-        resolved_meth: Rc<Method>,
-    ) -> VMResult<Rc<Method>> {
+        runtime_class: &Rc<Class>,
+        resolved: (Rc<Class>, Rc<Method>),
+    ) -> VMResult<(Rc<Class>, Rc<Method>)> {
         // 1. If mR is marked ACC_PRIVATE, then it is the selected method.
-        if resolved_meth
-            .access_flags
-            .contains(MethodAccessFlags::PRIVATE)
-        {
-            return Ok(resolved_meth);
+        if resolved.1.access_flags.contains(MethodAccessFlags::PRIVATE) {
+            return Ok(resolved);
         }
 
-        let sig = &resolved_meth.signature;
-
         // 2. Otherwise, the selected method is determined by the following lookup procedure:
-        let mut cls = runtime_class;
+        let mut cls = runtime_class.clone();
         loop {
             // If C contains a declaration of an instance method m that can override mR, then m is the selected method.
             // TODO: "transitive overriding" of methods with default access is not taken account for now.
-            if let Some(meth) = runtime_class.lookup_instance_method(sig) {
-                return Ok(meth);
+            if let Some(meth) = cls.lookup_instance_method(&resolved.1.signature) {
+                return self.ensure_not_abstract(runtime_class, &resolved, (cls, meth));
             }
             // Otherwise, if C has a superclass, a search for a declaration of an instance method that can override mR is performed,
             // starting with the direct superclass of C and continuing with the direct superclass of that class,
             // and so forth, until a method is found or no further superclasses exist.
             // If a method is found, it is the selected method.
-            match &cls.super_class {
-                Some(sc_name) => {
-                    cls = self
-                        .classes
-                        .get(sc_name)
-                        .expect("all superclasses must have been resolved");
-                }
-                None => break,
-            }
+            let Some(sc_name) = &cls.super_class else {
+                break;
+            };
+            cls = self.resolve_class(sc_name)?;
         }
         // Otherwise, the maximally-specific superinterface methods of C are determined.
         // If exactly one matches mR's name and descriptor and is not abstract, then it is the selected method.
-        self.find_maximally_specific_superinterface_method(runtime_class, sig)
+        self.select_superinterface_method(runtime_class, &resolved)
+    }
+
+    // select method to be called by invokevirtual/invokeinterface, based on the runtime class of the receiver.
+    pub fn select_virtual_method(
+        &mut self,
+        receiver: &RefValue,
+        resolved: (Rc<Class>, Rc<Method>),
+    ) -> VMResult<(Rc<Class>, Rc<Method>)> {
+        match receiver {
+            RefValue::Object(obj) => self.select_instance_method(&obj.get_class(), resolved),
+            RefValue::Null => Err(JavaException::null_pointer_exception(format!(
+                "Cannot invoke \"{}\" because the receiver is null",
+                method_name(&resolved.0.name, &resolved.1.signature)
+            )))?,
+            // array classes declare no methods, so the ones of java.lang.Object are selected
+            RefValue::Array(arr) => {
+                let arr_cls = self.resolve_class(arr.descriptor().as_str())?;
+                self.select_instance_method(&arr_cls, resolved)
+            }
+        }
+    }
+
+    // invokeinterface throws IncompatibleClassChangeError if the class of the receiver does not implement the referenced interface.
+    // null receivers are left to the method selection, which throws NullPointerException.
+    pub fn check_receiver_implements(
+        &mut self,
+        receiver: &RefValue,
+        iface_name: &str,
+    ) -> VMResult<()> {
+        let rt_cls_name = match receiver {
+            RefValue::Object(obj) => obj.get_class().name.clone(),
+            RefValue::Array(arr) => self.resolve_class(arr.descriptor().as_str())?.name.clone(),
+            RefValue::Null => return Ok(()),
+        };
+        if !self.is_subclass_of(&rt_cls_name, iface_name) {
+            Err(JavaException::incompatible_class_change_error(format!(
+                "Class {} does not implement the requested interface {}",
+                dotted(&rt_cls_name),
+                dotted(iface_name)
+            )))?;
+        }
+        Ok(())
+    }
+
+    // select method to be called by invokespecial (cf. JVM spec 6.5. invokespecial).
+    // `current_cls` is the class of the method which executes the instruction.
+    pub fn select_special_method(
+        &mut self,
+        current_cls: &Rc<Class>,
+        resolved: (Rc<Class>, Rc<Method>),
+    ) -> VMResult<(Rc<Class>, Rc<Method>)> {
+        let (resolved_cls, resolved_meth) = &resolved;
+
+        // if the resolved method is not an instance initialization method and the symbolic reference names a superclass of the current class,
+        // then lookup starts from the direct superclass of the current class (assuming ACC_SUPER is set).
+        let start_cls = match &current_cls.super_class {
+            Some(sc_name)
+                if resolved_meth.signature.name != "<init>"
+                    && !resolved_cls.access_flags.is_interface()
+                    && resolved_cls.name != current_cls.name
+                    && self.is_subclass_of(&current_cls.name, &resolved_cls.name) =>
+            {
+                self.resolve_class(sc_name)?
+            }
+            _ => resolved_cls.clone(),
+        };
+        let sig = &resolved_meth.signature;
+
+        // 1. If C contains a declaration for an instance method with the same name and descriptor as the resolved method, then it is the method to be invoked.
+        if let Some(meth) = start_cls.lookup_instance_method(sig) {
+            return self.ensure_not_abstract(&start_cls, &resolved, (start_cls.clone(), meth));
+        }
+        if start_cls.access_flags.is_interface() {
+            // 3. Otherwise, if C is an interface and the class Object contains a declaration of a public instance method
+            // with the same name and descriptor as the resolved method, then it is the method to be invoked.
+            let obj_cls = self.resolve_class("java/lang/Object")?;
+            if let Some(meth) = obj_cls.lookup_instance_method(sig) {
+                if meth.access_flags.is_public_non_static() {
+                    return Ok((obj_cls, meth));
+                }
+            }
+        } else {
+            // 2. Otherwise, if C is a class and has a superclass, a search for a declaration of an instance method
+            // with the same name and descriptor as the resolved method is performed in the direct superclass of C recursively.
+            let mut cls = start_cls.clone();
+            while let Some(sc_name) = &cls.super_class {
+                cls = self.resolve_class(sc_name)?;
+                if let Some(meth) = cls.lookup_instance_method(sig) {
+                    return self.ensure_not_abstract(&start_cls, &resolved, (cls, meth));
+                }
+            }
+        }
+        // 4. Otherwise, if there is exactly one maximally-specific method in the superinterfaces of C
+        // that matches the resolved method's name and descriptor and is not abstract, then it is the method to be invoked.
+        self.select_superinterface_method(&start_cls, &resolved)
     }
 
-    fn find_maximally_specific_superinterface_method(
+    fn select_superinterface_method(
+        &mut self,
+        cls: &Rc<Class>,
+        resolved: &(Rc<Class>, Rc<Method>),
+    ) -> VMResult<(Rc<Class>, Rc<Method>)> {
+        let candidates = self
+            .maximally_specific_superinterface_methods(cls, &resolved.1.signature)?
+            .into_iter()
+            .filter(|(_, m)| !is_abstract(m))
+            .collect::<Vec<_>>();
+
+        match candidates.as_slice() {
+            [] => Err(abstract_method_error(cls, resolved))?,
+            [cm] => Ok(cm.clone()),
+            // default methods of multiple superinterfaces conflict
+            [(i1, _), (i2, _), ..] => {
+                Err(JavaException::incompatible_class_change_error(format!(
+                    "Conflicting default methods: {} {}",
+                    method_name(&i1.name, &resolved.1.signature),
+                    method_name(&i2.name, &resolved.1.signature),
+                )))?
+            }
+        }
+    }
+
+    fn ensure_not_abstract(
+        &self,
+        cls: &Class,
+        resolved: &(Rc<Class>, Rc<Method>),
+        selected: (Rc<Class>, Rc<Method>),
+    ) -> VMResult<(Rc<Class>, Rc<Method>)> {
+        if is_abstract(&selected.1) {
+            Err(abstract_method_error(cls, resolved))?;
+        }
+        Ok(selected)
+    }
+
+    // collects the maximally-specific superinterface methods of the class/interface (JVM spec 5.4.3.3.),
+    // that is, methods declared in superinterfaces of `base`, which are not private nor static,
+    // and not overridden by methods in other superinterfaces.
+    fn maximally_specific_superinterface_methods(
         &self,
         base: &Class,
         sig: &MethodSignature,
-    ) -> VMResult<Rc<Method>> {
-        let mut seen_ifaces = HashSet::new();
-        let mut ifaces = base
-            .interfaces
-            .iter()
-            .map(|iface_name| {
-                seen_ifaces.insert(iface_name);
-                self.classes.get(iface_name)
+    ) -> VMResult<Vec<(Rc<Class>, Rc<Method>)>> {
+        let mut candidates = self
+            .collect_all_superclasses(&base.name)?
+            .into_iter()
+            .filter(|c| c.access_flags.is_interface())
+            .filter_map(|iface| {
+                iface
+                    .lookup_instance_method(sig)
+                    .filter(|m| {
+                        !m.access_flags
+                            .intersects(MethodAccessFlags::PRIVATE | MethodAccessFlags::STATIC)
+                    })
+                    .map(|m| (iface, m))
             })
-            .collect::<Option<Vec<_>>>()
-            .expect("all superinterfaces must have been resolved");
+            .collect::<Vec<_>>();
+        // for deterministic results
+        candidates.sort_by(|(i1, _), (i2, _)| i1.name.cmp(&i2.name));
 
-        loop {
-            let candidates = ifaces
-                .iter()
-                .filter_map(|&iface| {
-                    iface
-                        .lookup_instance_method(sig)
-                        .filter(|meth| meth.access_flags.is_interface_default())
-                })
-                .collect::<Vec<_>>();
-
-            if candidates.len() == 1 {
-                return Ok(candidates[0].clone());
-            } else if candidates.len() >= 2 {
-                return Err("maximally-specific superinterface method not found".into());
-            } else {
-                // no candidates -> travarse next level up
-                let next = ifaces
+        let maximals = candidates
+            .iter()
+            .filter(|(i, _)| {
+                !candidates
                     .iter()
-                    .flat_map(|&iface| {
-                        iface
-                            .interfaces // names of superinterfaces
-                            .iter()
-                            .filter_map(|iface_name| {
-                                // already seen interfaces can be ignored safely,
-                                // since they shouldn't have method that matches with sig.
-                                let seen = seen_ifaces.insert(iface_name);
-                                if seen {
-                                    None
-                                } else {
-                                    self.classes.get(iface_name)
-                                }
-                            })
-                            .collect::<Vec<_>>()
-                    })
-                    .collect::<Vec<_>>();
-                if next.is_empty() {
-                    return Err("maximally-specific superinterface method not found".into());
-                }
-                ifaces = next;
+                    .any(|(j, _)| j.name != i.name && self.is_subclass_of(&j.name, &i.name))
+            })
+            .cloned()
+            .collect();
+        Ok(maximals)
+    }
+}
+
+fn is_abstract(meth: &Method) -> bool {
+    meth.access_flags.contains(MethodAccessFlags::ABSTRACT)
+}
+
+fn dotted(cls_name: &str) -> String {
+    cls_name.replace('/', ".")
+}
+
+// human readable name of a method with its full descriptor (e.g. "java.lang.Object.equals(Ljava/lang/Object;)Z")
+pub fn method_name(cls_name: &str, sig: &MethodSignature) -> String {
+    format!("{}.{}{}", dotted(cls_name), sig.name, sig.descriptor)
+}
+
+fn abstract_method_error(rt_cls: &Class, resolved: &(Rc<Class>, Rc<Method>)) -> JavaException {
+    let (resolved_cls, resolved_meth) = resolved;
+    JavaException::abstract_method_error(format!(
+        "Receiver class {} does not define or inherit an implementation of the resolved method {}",
+        dotted(&rt_cls.name),
+        method_name(&resolved_cls.name, &resolved_meth.signature)
+    ))
+}
+
+// checks if the resolved method is static (or non-static) as expected by the invocation instruction.
+pub fn check_method_staticness(
+    resolved: &(Rc<Class>, Rc<Method>),
+    expect_static: bool,
+) -> VMResult<()> {
+    let (cls, meth) = resolved;
+    match (
+        meth.access_flags.contains(MethodAccessFlags::STATIC),
+        expect_static,
+    ) {
+        (false, true) => Err(JavaException::incompatible_class_change_error(format!(
+            "Expected static method {}",
+            method_name(&cls.name, &meth.signature)
+        )))?,
+        (true, false) => Err(JavaException::incompatible_class_change_error(format!(
+            "Expecting non-static method {}",
+            method_name(&cls.name, &meth.signature)
+        )))?,
+        _ => Ok(()),
+    }
+}
+
+// checks if the class `cls` is allowed to extend/implement the superclass/superinterface `super_cls`, which may be sealed (JVM spec 5.3.5. step 3, 4).
+fn check_permitted_subclass(cls: &Class, super_cls: &Class) -> VMResult<()> {
+    let Some(permitted) = super_cls.permitted_subclasses() else {
+        // super class is not sealed
+        return Ok(());
+    };
+
+    // a subclass must be in the same module as the sealed class if it is named, or in the same run-time package otherwise
+    match (in_java_base(cls), in_java_base(super_cls)) {
+        (true, true) => {}
+        (false, false) if cls.package_name() == super_cls.package_name() => {}
+        (false, false) => Err(JavaException::incompatible_class_change_error(format!(
+            "class {} is in a different package from its sealed super type {}",
+            cls.name, super_cls.name
+        )))?,
+        _ => Err(JavaException::incompatible_class_change_error(format!(
+            "class {} is in a different module from its sealed super type {}",
+            cls.name, super_cls.name
+        )))?,
+    }
+    if !permitted.contains(&cls.name) {
+        Err(JavaException::incompatible_class_change_error(format!(
+            "class {} cannot inherit from sealed class {}",
+            cls.name, super_cls.name
+        )))?;
+    }
+    Ok(())
+}
+
+// classes on the boot classpath, and ones in java.* packages (which only named modules can define), belong to the named module java.base.
+// the others belong to the unnamed module
+fn in_java_base(cls: &Class) -> bool {
+    cls.defining_loader == DefiningLoader::Boot || cls.package_name().starts_with("java/")
+}
+
+// name of the primitive type denoted by the descriptor (e.g. "I" -> "int")
+pub fn primitive_name(desc: &str) -> Option<&'static str> {
+    PRIMITIVE_TYPES
+        .iter()
+        .find(|&&(d, _)| d == desc)
+        .map(|&(_, name)| name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // register stub classes instead of loading class files: (name, superclass, superinterfaces).
+    // classes without superclasses other than java.lang.Object are interfaces
+    fn with_classes(classes: &[(&str, Option<&str>, &[&str])]) -> MethodArea {
+        let mut meth_area = MethodArea::new(Rc::new(VMConfig::default()));
+        for &(name, super_class, interfaces) in classes {
+            let mut cls = Class::dummy();
+            cls.name = name.to_string();
+            cls.access_flags = ClassAccessFlags::PUBLIC;
+            if super_class.is_none() && name != "java/lang/Object" {
+                cls.access_flags |= ClassAccessFlags::INTERFACE | ClassAccessFlags::ABSTRACT;
             }
+            cls.super_class = super_class.map(str::to_string);
+            cls.interfaces = interfaces.iter().map(|i| i.to_string()).collect();
+            meth_area.classes.insert(name.to_string(), Rc::new(cls));
+        }
+        meth_area
+    }
+
+    fn jdk_classes() -> MethodArea {
+        let obj = Some("java/lang/Object");
+        with_classes(&[
+            ("java/lang/Object", None, &[]),
+            ("java/lang/Cloneable", None, &[]),
+            ("java/io/Serializable", None, &[]),
+            ("java/lang/Comparable", None, &[]),
+            (
+                "java/lang/String",
+                obj,
+                &["java/io/Serializable", "java/lang/Comparable"],
+            ),
+            ("java/lang/Integer", obj, &[]),
+        ])
+    }
+
+    #[test]
+    fn test_array_class() {
+        let mut meth_area = jdk_classes();
+        let cls = meth_area.resolve_class("[[Ljava/lang/String;").unwrap();
+        assert!(cls.is_array());
+        assert_eq!(cls.component_type.as_deref(), Some("[Ljava/lang/String;"));
+        assert_eq!(cls.super_class.as_deref(), Some("java/lang/Object"));
+        assert_eq!(cls.interfaces, ARRAY_INTERFACES);
+        assert_eq!(
+            cls.access_flags.bits(),
+            (ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL | ClassAccessFlags::ABSTRACT)
+                .bits()
+        );
+        assert_eq!(cls.defining_loader, DefiningLoader::Boot);
+        assert_eq!(cls.package_name(), "java/lang");
+        assert_eq!(cls.descriptor(), "[[Ljava/lang/String;");
+        // the component class is created as well
+        assert!(meth_area.loaded_class("[Ljava/lang/String;").is_some());
+
+        // arrays of a class inherit its defining loader and accessibility
+        let mut app_cls = Class::dummy();
+        app_cls.name = "app/Item".to_string();
+        app_cls.defining_loader = DefiningLoader::App;
+        meth_area
+            .classes
+            .insert(app_cls.name.clone(), Rc::new(app_cls));
+        let cls = meth_area.resolve_class("[Lapp/Item;").unwrap();
+        assert_eq!(cls.defining_loader, DefiningLoader::App);
+        assert!(!cls.access_flags.contains(ClassAccessFlags::PUBLIC));
+        assert_eq!(cls.package_name(), "app");
+
+        let cls = meth_area.resolve_class("[I").unwrap();
+        assert_eq!(cls.component_type.as_deref(), Some("I"));
+        assert!(meth_area.resolve_class("[V").is_err());
+        assert!(meth_area.resolve_class("[X").is_err());
+    }
+
+    #[test]
+    fn test_is_assignable() {
+        let mut meth_area = jdk_classes();
+        for (s, t) in [
+            ("[[I", "java/lang/Object"),
+            ("[[I", "java/lang/Cloneable"),
+            ("[[I", "[Ljava/lang/Object;"),
+            ("[[I", "[Ljava/io/Serializable;"),
+            ("[[Ljava/lang/String;", "[[Ljava/lang/Comparable;"),
+            ("[[Ljava/lang/String;", "[Ljava/lang/Object;"),
+            ("[[Ljava/lang/String;", "[[Ljava/lang/Object;"),
+            ("[Ljava/lang/String;", "java/io/Serializable"),
+            ("java/lang/String", "java/lang/Comparable"),
+        ] {
+            assert!(meth_area.is_assignable(s, t).unwrap(), "{s} -> {t}");
+        }
+        for (s, t) in [
+            ("[I", "[J"),
+            ("[I", "[Ljava/lang/Object;"),
+            ("[[I", "[[Ljava/lang/Object;"),
+            ("[Ljava/lang/Object;", "[Ljava/lang/String;"),
+            ("[Ljava/lang/String;", "[Ljava/lang/Integer;"),
+            ("[Ljava/lang/String;", "java/lang/Comparable"),
+            ("java/lang/Object", "[I"),
+        ] {
+            assert!(!meth_area.is_assignable(s, t).unwrap(), "{s} -> {t}");
         }
     }
 }
@@ -0,0 +1,796 @@
+// native methods implemented in Rust.
+// native methods are looked up by (class, name, descriptor) from the registry, and invoked without creating frames.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    rc::Rc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::class_file::ClassAccessFlags;
+
+use super::{
+    class::{Class, Method, MethodSignature},
+    error::{JavaException, VMResult},
+    gc,
+    heap::{self, Heap, RefValue},
+    jni::{self, Jni},
+    method_area::{self, MethodArea},
+    thread::Thread,
+    value::{MutValue, Value},
+};
+
+// runtime data areas that native methods can access
+pub struct NativeEnv<'a> {
+    pub thread: &'a mut Thread,
+    pub meth_area: &'a mut MethodArea,
+    pub heap: &'a mut Heap,
+}
+
+// arguments include the receiver for instance methods. returns None for void methods.
+// exceptions are thrown by returning JavaException as an error.
+pub type NativeFn = Rc<dyn Fn(&mut NativeEnv, &[Value]) -> VMResult<Option<Value>>>;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct NativeMethodKey {
+    class_name: String,
+    signature: MethodSignature,
+}
+
+pub struct NativeRegistry {
+    natives: HashMap<NativeMethodKey, NativeFn>,
+    // Java methods replaced with natives, since they can't run on this VM
+    replaced: Vec<NativeMethodKey>,
+    // native libraries loaded via JNI
+    pub jni: Jni,
+}
+
+impl NativeRegistry {
+    pub fn new() -> Self {
+        NativeRegistry {
+            natives: HashMap::new(),
+            replaced: Vec::new(),
+            jni: Jni::new(),
+        }
+    }
+
+    // registry with natives of the core library that the VM implements
+    pub fn with_builtins() -> Self {
+        let mut reg = Self::new();
+        for (cls_name, name, desc, f) in BUILTIN_NATIVES {
+            reg.register(cls_name, name, desc, Rc::new(f));
+        }
+        for (cls_name, name, desc, f) in BUILTIN_REPLACEMENTS {
+            reg.register(cls_name, name, desc, Rc::new(f));
+            reg.replaced.push(NativeMethodKey {
+                class_name: cls_name.to_string(),
+                signature: MethodSignature::new_with_raw_descriptor(name, desc),
+            });
+        }
+        reg
+    }
+
+    // turn the Java methods of the class that are replaced by natives into native methods
+    pub fn replace_methods(&self, cls: &mut Class) {
+        for key in &self.replaced {
+            if key.class_name == cls.name {
+                cls.make_native(&key.signature);
+            }
+        }
+    }
+
+    pub fn register(&mut self, class_name: &str, name: &str, desc: &str, f: NativeFn) {
+        let key = NativeMethodKey {
+            class_name: class_name.to_string(),
+            signature: MethodSignature::new_with_raw_descriptor(name, desc),
+        };
+        self.natives.insert(key, f);
+    }
+
+    pub fn lookup(&self, class_name: &str, signature: &MethodSignature) -> Option<NativeFn> {
+        let key = NativeMethodKey {
+            class_name: class_name.to_string(),
+            signature: signature.clone(),
+        };
+        self.natives.get(&key).cloned()
+    }
+}
+
+// invoke the native method with the arguments (including the receiver, if it is an instance method).
+pub fn invoke(
+    thread: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+    cls: &Class,
+    meth: &Method,
+    args: &[Value],
+) -> VMResult<Option<Value>> {
+    let f = match meth_area.natives().lookup(&cls.name, &meth.signature) {
+        Some(f) => f,
+        // link to the function in loaded native libraries at the first invocation
+        None => {
+            let Some(f) = jni::link(&meth_area.natives().jni, cls, meth) else {
+                return Err(JavaException::unsatisfied_link_error(format!(
+                    "'{}' (symbol: {})",
+                    method_area::method_name(&cls.name, &meth.signature),
+                    jni_symbol(cls, meth),
+                )))?;
+            };
+            let sig = &meth.signature;
+            meth_area.natives_mut().register(
+                &cls.name,
+                &sig.name,
+                &sig.descriptor.to_string(),
+                f.clone(),
+            );
+            f
+        }
+    };
+
+    let mut env = NativeEnv {
+        thread,
+        meth_area,
+        heap,
+    };
+    let ret = f(&mut env, args)?;
+    match (meth.returns_void(), ret) {
+        (true, None) | (false, Some(_)) => Ok(ret),
+        _ => Err(format!(
+            "native method {} returned unexpected value: {ret:?}",
+            method_area::method_name(&cls.name, &meth.signature)
+        ))?,
+    }
+}
+
+// name of the symbol of the native method in JNI convention (cf. JNI spec "Resolving Native Method Names").
+// long name (with mangled argument signature) is used if the native method is overloaded.
+pub fn jni_symbol(cls: &Class, meth: &Method) -> String {
+    if cls.native_methods_named(&meth.signature.name).count() <= 1 {
+        jni_short_symbol(cls, meth)
+    } else {
+        jni_long_symbol(cls, meth)
+    }
+}
+
+pub fn jni_short_symbol(cls: &Class, meth: &Method) -> String {
+    format!(
+        "Java_{}_{}",
+        jni_mangle(&cls.name),
+        jni_mangle(&meth.signature.name)
+    )
+}
+
+pub fn jni_long_symbol(cls: &Class, meth: &Method) -> String {
+    let params = meth.signature.descriptor.param_types().concat();
+    format!("{}__{}", jni_short_symbol(cls, meth), jni_mangle(&params))
+}
+
+fn jni_mangle(s: &str) -> String {
+    let mut res = String::new();
+    for c in s.chars() {
+        match c {
+            '/' => res.push('_'),
+            '_' => res.push_str("_1"),
+            ';' => res.push_str("_2"),
+            '[' => res.push_str("_3"),
+            c if c.is_ascii_alphanumeric() => res.push(c),
+            c => {
+                let mut buf = [0u16; 2];
+                for u in c.encode_utf16(&mut buf) {
+                    res.push_str(&format!("_0{:04x}", u));
+                }
+            }
+        }
+    }
+    res
+}
+
+type BuiltinNative = fn(&mut NativeEnv, &[Value]) -> VMResult<Option<Value>>;
+
+const BUILTIN_NATIVES: [(&str, &str, &str, BuiltinNative); 31] = [
+    ("java/lang/System", "registerNatives", "()V", no_op),
+    ("java/lang/Class", "registerNatives", "()V", no_op),
+    (
+        "java/lang/Class",
+        "getPrimitiveClass",
+        "(Ljava/lang/String;)Ljava/lang/Class;",
+        class_get_primitive_class,
+    ),
+    // assertions are always disabled
+    (
+        "java/lang/Class",
+        "desiredAssertionStatus0",
+        "(Ljava/lang/Class;)Z",
+        return_false,
+    ),
+    ("java/lang/Class", "isArray", "()Z", class_is_array),
+    ("java/lang/Class", "isPrimitive", "()Z", class_is_primitive),
+    ("java/lang/Class", "isInterface", "()Z", class_is_interface),
+    (
+        "java/lang/Class",
+        "getModifiers",
+        "()I",
+        class_get_modifiers,
+    ),
+    (
+        "java/lang/Class",
+        "getSuperclass",
+        "()Ljava/lang/Class;",
+        class_get_superclass,
+    ),
+    (
+        "java/lang/Class",
+        "isInstance",
+        "(Ljava/lang/Object;)Z",
+        class_is_instance,
+    ),
+    (
+        "java/lang/Class",
+        "isAssignableFrom",
+        "(Ljava/lang/Class;)Z",
+        class_is_assignable_from,
+    ),
+    ("java/lang/Object", "hashCode", "()I", object_hash_code),
+    (
+        "java/lang/Object",
+        "getClass",
+        "()Ljava/lang/Class;",
+        object_get_class,
+    ),
+    (
+        "java/lang/Object",
+        "clone",
+        "()Ljava/lang/Object;",
+        object_clone,
+    ),
+    // no other thread can be waiting on the monitor
+    ("java/lang/Object", "notify", "()V", no_op),
+    ("java/lang/Object", "notifyAll", "()V", no_op),
+    ("java/lang/Object", "wait", "(J)V", object_wait),
+    (
+        "java/lang/System",
+        "identityHashCode",
+        "(Ljava/lang/Object;)I",
+        system_identity_hash_code,
+    ),
+    (
+        "java/lang/ref/Reference",
+        "refersTo0",
+        "(Ljava/lang/Object;)Z",
+        reference_refers_to,
+    ),
+    (
+        "java/lang/ref/PhantomReference",
+        "refersTo0",
+        "(Ljava/lang/Object;)Z",
+        reference_refers_to,
+    ),
+    ("java/lang/ref/Reference", "clear0", "()V", reference_clear),
+    ("java/lang/Runtime", "gc", "()V", runtime_gc),
+    (
+        "java/lang/System",
+        "arraycopy",
+        "(Ljava/lang/Object;ILjava/lang/Object;II)V",
+        system_arraycopy,
+    ),
+    (
+        "java/lang/System",
+        "currentTimeMillis",
+        "()J",
+        system_current_time_millis,
+    ),
+    ("java/lang/System", "nanoTime", "()J", system_nano_time),
+    (
+        "java/lang/Throwable",
+        "fillInStackTrace",
+        "(I)Ljava/lang/Throwable;",
+        throwable_fill_in_stack_trace,
+    ),
+    (
+        "java/lang/StringUTF16",
+        "isBigEndian",
+        "()Z",
+        string_utf16_is_big_endian,
+    ),
+    (
+        "java/lang/Float",
+        "floatToRawIntBits",
+        "(F)I",
+        float_to_raw_int_bits,
+    ),
+    (
+        "java/lang/Float",
+        "intBitsToFloat",
+        "(I)F",
+        int_bits_to_float,
+    ),
+    (
+        "java/lang/Double",
+        "doubleToRawLongBits",
+        "(D)J",
+        double_to_raw_long_bits,
+    ),
+    (
+        "java/lang/Double",
+        "longBitsToDouble",
+        "(J)D",
+        long_bits_to_double,
+    ),
+];
+
+// Java methods of the core library which are implemented natively in this VM instead
+const BUILTIN_REPLACEMENTS: [(&str, &str, &str, BuiltinNative); 7] = [
+    // starts the reference handler thread. cleared references are enqueued by the VM instead (cf. Thread::process_pending)
+    ("java/lang/ref/Reference", "<clinit>", "()V", no_op),
+    (
+        "java/lang/ref/ReferenceQueue",
+        "<clinit>",
+        "()V",
+        reference_queue_clinit,
+    ),
+    // starts the thread which runs cleaning actions. they are run by the VM instead
+    (
+        "jdk/internal/ref/CleanerImpl",
+        "start",
+        "(Ljava/lang/ref/Cleaner;Ljava/util/concurrent/ThreadFactory;)V",
+        no_op,
+    ),
+    (
+        "java/lang/System",
+        "loadLibrary",
+        "(Ljava/lang/String;)V",
+        system_load_library,
+    ),
+    (
+        "java/lang/System",
+        "load",
+        "(Ljava/lang/String;)V",
+        system_load,
+    ),
+    (
+        "java/lang/System",
+        "getProperty",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        system_get_property,
+    ),
+    (
+        "java/lang/System",
+        "getProperty",
+        "(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;",
+        system_get_property,
+    ),
+];
+
+fn return_false(_: &mut NativeEnv, _: &[Value]) -> VMResult<Option<Value>> {
+    Ok(Some(Value::Int(0)))
+}
+
+fn no_op(_: &mut NativeEnv, _: &[Value]) -> VMResult<Option<Value>> {
+    Ok(None)
+}
+
+fn object_hash_code(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    system_identity_hash_code(env, args)
+}
+
+fn system_identity_hash_code(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[Value::Reference(r)] = args else {
+        return Err("invalid arguments for System.identityHashCode")?;
+    };
+    if r == 0 {
+        return Ok(Some(Value::Int(0)));
+    }
+    let Some(hash) = env.heap.identity_hash_code(r) else {
+        return Err("referent not found on heap")?;
+    };
+    Ok(Some(Value::Int(hash)))
+}
+
+fn object_get_class(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[Value::Reference(this)] = args else {
+        return Err("invalid arguments for Object.getClass")?;
+    };
+    let Some(rv) = env.heap.get_ref(this) else {
+        return Err("referent not found on heap")?;
+    };
+    let cls_name = rv.class_name();
+    Ok(Some(env.meth_area.mirror(env.heap, &cls_name)?))
+}
+
+fn object_clone(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[Value::Reference(this)] = args else {
+        return Err("invalid arguments for Object.clone")?;
+    };
+    // arrays are always cloneable
+    if let Some(RefValue::Object(obj)) = env.heap.get_ref(this) {
+        let cls_name = obj.get_class().name.clone();
+        if !env
+            .meth_area
+            .is_subclass_of(&cls_name, "java/lang/Cloneable")
+        {
+            Err(JavaException::clone_not_supported_exception(
+                cls_name.replace('/', "."),
+            ))?;
+        }
+    }
+    // GC may move the object while making room for the copy
+    let handle = env.heap.new_handle(this)?;
+    let size = env.heap.size_of(this);
+    gc::make_room(env.thread, env.meth_area, env.heap, size);
+    Ok(Some(env.heap.clone_object(handle.get())?))
+}
+
+// mirrors of primitive types are keyed by their names (e.g. "int")
+fn class_get_primitive_class(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let name = string_arg(env, args, "Class.getPrimitiveClass")?;
+    Ok(Some(env.meth_area.mirror(env.heap, &name)?))
+}
+
+// class represented by the java.lang.Class object, along with its name. the class is None for primitive types
+fn mirrored_class(env: &NativeEnv, mirror: Value) -> VMResult<(String, Option<Rc<Class>>)> {
+    let Value::Reference(r) = mirror else {
+        return Err("operand is not a reference value")?;
+    };
+    let Some(name) = env.meth_area.class_name_of_mirror(r) else {
+        return Err("not a java.lang.Class object created by the VM")?;
+    };
+    Ok((name.to_string(), env.meth_area.loaded_class(name).cloned()))
+}
+
+fn class_is_array(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let (_, cls) = mirrored_class(env, args[0])?;
+    let is_array = cls.is_some_and(|c| c.is_array());
+    Ok(Some(Value::Int(is_array as i32)))
+}
+
+fn class_is_primitive(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let (_, cls) = mirrored_class(env, args[0])?;
+    Ok(Some(Value::Int(cls.is_none() as i32)))
+}
+
+fn class_is_interface(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let (_, cls) = mirrored_class(env, args[0])?;
+    let is_iface = cls.is_some_and(|c| c.access_flags.is_interface());
+    Ok(Some(Value::Int(is_iface as i32)))
+}
+
+// primitive types are public, final and abstract, as array classes are
+fn class_get_modifiers(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let (_, cls) = mirrored_class(env, args[0])?;
+    let flags = match cls {
+        Some(c) => c.access_flags.clone() - ClassAccessFlags::SUPER,
+        None => ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL | ClassAccessFlags::ABSTRACT,
+    };
+    Ok(Some(Value::Int(flags.bits() as i32)))
+}
+
+// null for Object, interfaces and primitive types
+fn class_get_superclass(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let (_, cls) = mirrored_class(env, args[0])?;
+    match cls {
+        Some(c) if !c.access_flags.is_interface() => match &c.super_class {
+            Some(sc_name) => Ok(Some(env.meth_area.mirror(env.heap, sc_name)?)),
+            None => Ok(Some(Value::Reference(0))),
+        },
+        _ => Ok(Some(Value::Reference(0))),
+    }
+}
+
+fn class_is_instance(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[this, Value::Reference(obj)] = args else {
+        return Err("invalid arguments for Class.isInstance")?;
+    };
+    let (name, cls) = mirrored_class(env, this)?;
+    let is_inst = match env.heap.get_ref(obj) {
+        _ if obj == 0 || cls.is_none() => false,
+        Some(rv) => rv.is_instance_of(&name, env.meth_area)?,
+        None => return Err("referent not found on heap")?,
+    };
+    Ok(Some(Value::Int(is_inst as i32)))
+}
+
+// primitive types are assignable only from themselves
+fn class_is_assignable_from(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[this, other @ Value::Reference(other_ref)] = args else {
+        return Err("invalid arguments for Class.isAssignableFrom")?;
+    };
+    if other_ref == 0 {
+        Err(JavaException::null_pointer_exception(
+            "argument of Class.isAssignableFrom is null",
+        ))?;
+    }
+    let (name, cls) = mirrored_class(env, this)?;
+    let (other_name, other_cls) = mirrored_class(env, other)?;
+    let assignable = match (cls, other_cls) {
+        (Some(_), Some(_)) => env.meth_area.is_assignable(&other_name, &name)?,
+        _ => name == other_name,
+    };
+    Ok(Some(Value::Int(assignable as i32)))
+}
+
+// there is no other thread which can notify, so it just sleeps until the timeout.
+// an untimed wait would never end, so it returns at once (which is allowed as a spurious wakeup)
+fn object_wait(_: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[_, Value::Long(timeout)] = args else {
+        return Err("invalid arguments for Object.wait")?;
+    };
+    if timeout < 0 {
+        Err(JavaException::illegal_argument_exception(
+            "timeout value is negative",
+        ))?;
+    }
+    thread::sleep(Duration::from_millis(timeout as u64));
+    Ok(None)
+}
+
+fn system_arraycopy(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[Value::Reference(src), Value::Int(src_pos), Value::Reference(dest), Value::Int(dest_pos), Value::Int(len)] =
+        args
+    else {
+        return Err("invalid arguments for System.arraycopy")?;
+    };
+    if src == 0 || dest == 0 {
+        Err(JavaException::null_pointer_exception(""))?;
+    }
+
+    let Some(RefValue::Array(src_arr)) = env.heap.get_ref(src) else {
+        return Err(JavaException::array_store_exception(
+            "arraycopy: source type is not an array",
+        ))?;
+    };
+    let src_desc = src_arr.descriptor().to_string();
+    let src_len = src_arr.len() as i64;
+    let Some(RefValue::Array(dest_arr)) = env.heap.get_ref(dest) else {
+        return Err(JavaException::array_store_exception(
+            "arraycopy: destination type is not an array",
+        ))?;
+    };
+    let dest_desc = dest_arr.descriptor().to_string();
+    let dest_len = dest_arr.len() as i64;
+
+    // arrays of primitives can be copied only to arrays of the same type
+    let is_prim = |d: &str| d.len() == 2;
+    if (is_prim(&src_desc) || is_prim(&dest_desc)) && src_desc != dest_desc {
+        Err(JavaException::array_store_exception(format!(
+            "arraycopy: type mismatch: can not copy {src_desc} into {dest_desc}"
+        )))?;
+    }
+    for (pos, arr_len, which) in [
+        (src_pos, src_len, "source"),
+        (dest_pos, dest_len, "destination"),
+    ] {
+        if pos < 0 || len < 0 || pos as i64 + len as i64 > arr_len {
+            Err(JavaException::array_index_out_of_bounds_exception(format!(
+                "arraycopy: last {which} index {} out of bounds for length {arr_len}",
+                pos as i64 + len as i64
+            )))?;
+        }
+    }
+
+    // copy via temporary buffer, since the source and the destination may be the same array
+    let Some(RefValue::Array(src_arr)) = env.heap.get_ref(src) else {
+        unreachable!()
+    };
+    let vals = (0..len)
+        .filter_map(|i| src_arr.get((src_pos + i) as u32))
+        .collect::<Vec<_>>();
+
+    // elements of arrays of references must be assignable to the component type of the destination.
+    // elements before the first unassignable one are copied.
+    let mut n_copy = vals.len();
+    if !is_prim(&dest_desc) && src_desc != dest_desc {
+        let comp = &dest_desc[1..];
+        let target = heap::component_class_name(comp).unwrap_or(comp);
+        for (i, v) in vals.iter().enumerate() {
+            let assignable = match v {
+                Value::Reference(0) => true,
+                Value::Reference(r) => match env.heap.get_ref(*r) {
+                    Some(rv) => rv.is_instance_of(target, env.meth_area)?,
+                    None => false,
+                },
+                _ => false,
+            };
+            if !assignable {
+                n_copy = i;
+                break;
+            }
+        }
+    }
+
+    let Some(RefValue::Array(dest_arr)) = env.heap.get(dest) else {
+        unreachable!()
+    };
+    for (i, v) in vals.iter().take(n_copy).enumerate() {
+        dest_arr.put(dest_pos as u32 + i as u32, *v);
+    }
+    if n_copy < vals.len() {
+        Err(JavaException::array_store_exception(format!(
+            "arraycopy: element type mismatch: can not store an element of {src_desc} into {dest_desc}"
+        )))?;
+    }
+    Ok(None)
+}
+
+fn runtime_gc(env: &mut NativeEnv, _: &[Value]) -> VMResult<Option<Value>> {
+    gc::collect(env.thread, env.meth_area, env.heap, true);
+    Ok(None)
+}
+
+// java.lang.ref.Reference.referent of the receiver
+fn referent(heap: &mut Heap, this: Value) -> VMResult<&MutValue> {
+    let Value::Reference(r) = this else {
+        return Err("receiver is not a reference value")?;
+    };
+    let Some(RefValue::Object(obj)) = heap.get(r) else {
+        return Err("receiver is not a object")?;
+    };
+    let Some(f) = obj.get_field("java/lang/ref/Reference", "referent") else {
+        return Err("unexpected layout of java.lang.ref.Reference")?;
+    };
+    Ok(f)
+}
+
+// create the sentinel queues. the original one needs java.lang.Class to check the status of assertions
+fn reference_queue_clinit(env: &mut NativeEnv, _: &[Value]) -> VMResult<Option<Value>> {
+    let queue_cls = env
+        .meth_area
+        .resolve_class("java/lang/ref/ReferenceQueue")?;
+    let null_cls = env
+        .meth_area
+        .resolve_class("java/lang/ref/ReferenceQueue$Null")?;
+    null_cls
+        .clone()
+        .initialize(env.thread, env.meth_area, env.heap)?;
+    let ctor_sig = MethodSignature::new_with_raw_descriptor("<init>", "()V");
+    let Some(ctor) = null_cls.lookup_instance_method(&ctor_sig) else {
+        return Err("unexpected layout of java.lang.ref.ReferenceQueue")?;
+    };
+    for name in ["NULL", "ENQUEUED"] {
+        let Some(f) = queue_cls.lookup_static_field(name) else {
+            return Err("unexpected layout of java.lang.ref.ReferenceQueue")?;
+        };
+        // static fields are roots, so the queue survives GC while the constructor runs
+        let queue = env.heap.alloc_object(null_cls.clone(), env.meth_area)?;
+        f.put(queue);
+        env.thread.invoke_method(
+            env.meth_area,
+            env.heap,
+            null_cls.clone(),
+            ctor.clone(),
+            &[queue],
+        )?;
+    }
+    if let Some(f) = queue_cls.lookup_static_field("$assertionsDisabled") {
+        f.put(Value::Int(1));
+    }
+    Ok(None)
+}
+
+fn reference_refers_to(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[this, obj] = args else {
+        return Err("invalid arguments for Reference.refersTo0")?;
+    };
+    let refers = referent(env.heap, this)?.get() == obj;
+    Ok(Some(Value::Int(refers as i32)))
+}
+
+fn reference_clear(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let &[this] = args else {
+        return Err("invalid arguments for Reference.clear0")?;
+    };
+    referent(env.heap, this)?.put(Value::Reference(0));
+    Ok(None)
+}
+
+fn system_current_time_millis(_: &mut NativeEnv, _: &[Value]) -> VMResult<Option<Value>> {
+    let d = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    Ok(Some(Value::Long(d.as_millis() as i64)))
+}
+
+// nanoseconds elapsed since the VM was built. only differences between values are meaningful
+fn system_nano_time(env: &mut NativeEnv, _: &[Value]) -> VMResult<Option<Value>> {
+    let d = env.meth_area.config().start_time.elapsed();
+    Ok(Some(Value::Long(d.as_nanos() as i64)))
+}
+
+// the stack trace is not recorded in the exception object for now; only its depth is recorded
+fn throwable_fill_in_stack_trace(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let Value::Reference(r) = args[0] else {
+        return Err("receiver is not a reference value")?;
+    };
+    let depth = env.thread.stack_trace().len();
+    if let Some(RefValue::Object(obj)) = env.heap.get_ref(r) {
+        if let Some(f) = obj.get_field("java/lang/Throwable", "depth") {
+            f.put(Value::Int(depth as i32));
+        }
+    }
+    Ok(Some(args[0]))
+}
+
+// UTF-16 strings are stored in native byte order
+fn string_utf16_is_big_endian(_: &mut NativeEnv, _: &[Value]) -> VMResult<Option<Value>> {
+    Ok(Some(Value::Int(cfg!(target_endian = "big") as i32)))
+}
+
+fn float_to_raw_int_bits(_: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let [Value::Float(f)] = args else {
+        return Err("invalid arguments for Float.floatToRawIntBits")?;
+    };
+    Ok(Some(Value::Int(f.to_bits() as i32)))
+}
+
+fn int_bits_to_float(_: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let [Value::Int(i)] = args else {
+        return Err("invalid arguments for Float.intBitsToFloat")?;
+    };
+    Ok(Some(Value::Float(f32::from_bits(*i as u32))))
+}
+
+fn double_to_raw_long_bits(_: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let [Value::Double(d)] = args else {
+        return Err("invalid arguments for Double.doubleToRawLongBits")?;
+    };
+    Ok(Some(Value::Long(d.to_bits() as i64)))
+}
+
+fn long_bits_to_double(_: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let [Value::Long(l)] = args else {
+        return Err("invalid arguments for Double.longBitsToDouble")?;
+    };
+    Ok(Some(Value::Double(f64::from_bits(*l as u64))))
+}
+
+fn system_load_library(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let name = string_arg(env, args, "System.loadLibrary")?;
+    jni::load_library_by_name(env, &name)?;
+    Ok(None)
+}
+
+fn system_load(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let path = string_arg(env, args, "System.load")?;
+    jni::load_library(env, Path::new(&path))?;
+    Ok(None)
+}
+
+// System.getProperty(key) and System.getProperty(key, def).
+// properties are taken from the VM configuration, instead of System.props initialized by the JDK
+fn system_get_property(env: &mut NativeEnv, args: &[Value]) -> VMResult<Option<Value>> {
+    let key = string_arg(env, &args[..args.len().min(1)], "System.getProperty")?;
+    let prop = env.meth_area.config().properties.get(&key).cloned();
+    match (prop, args.get(1)) {
+        (Some(v), _) => Ok(Some(env.heap.alloc_string(&v, env.meth_area)?)),
+        (None, Some(&def)) => Ok(Some(def)),
+        (None, None) => Ok(Some(Value::Reference(0))),
+    }
+}
+
+fn string_arg(env: &mut NativeEnv, args: &[Value], meth_name: &str) -> VMResult<String> {
+    match args {
+        [Value::Reference(0)] => Err(JavaException::null_pointer_exception(format!(
+            "argument of {meth_name} is null"
+        )))?,
+        [Value::Reference(r)] => env.heap.get_string(*r),
+        _ => Err(format!("invalid arguments for {meth_name}"))?,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_jni_mangle() {
+        let tests = [
+            ("java/lang/Object", "java_lang_Object"),
+            ("my_pkg/Cls", "my_1pkg_Cls"),
+            ("[Ljava/lang/String;I", "_3Ljava_lang_String_2I"),
+            ("Cl$ss", "Cl_00024ss"),
+            ("日本", "_065e5_0672c"),
+        ];
+        for (input, exp) in tests {
+            assert_eq!(jni_mangle(input), exp);
+        }
+    }
+}
@@ -0,0 +1,342 @@
+// intrinsic implementation of the bootstrap method `java/lang/runtime/ObjectMethods.bootstrap`,
+// which is used by record classes to implement `toString`, `equals` and `hashCode`.
+
+use crate::class_file::BootstrapMethod;
+
+use super::{
+    class::{Class, MethodSignature, RunTimeCPInfo as CPInfo},
+    error::VMResult,
+    heap::{Heap, RefValue},
+    method_area::MethodArea,
+    thread::Thread,
+    value::Value,
+};
+
+const OBJECT_METHODS_CLASS: &str = "java/lang/runtime/ObjectMethods";
+const BOOTSTRAP_METHOD_NAME: &str = "bootstrap";
+
+// cf. JVM spec 5.4.3.5.
+const REF_GET_FIELD: u8 = 1;
+
+pub fn is_object_methods_bootstrap(cls_name: &str, meth_name: &str) -> bool {
+    cls_name == OBJECT_METHODS_CLASS && meth_name == BOOTSTRAP_METHOD_NAME
+}
+
+// a record component, read through the getter method handle passed to the bootstrap method
+struct Component {
+    class_name: String,
+    name: String,
+    descriptor: String,
+}
+
+// execute the method `meth_name` that the call site (invokedynamic) bootstrapped with ObjectMethods stands for.
+// the operands (receiver and arguments) are taken from the current frame, and the result is pushed to it.
+pub fn invoke(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+    caller_cls: &Class,
+    bsm: &BootstrapMethod,
+    meth_name: &str,
+) -> VMResult<()> {
+    // static arguments: (Class recordClass, String names, MethodHandle... getters)
+    let [rec_cls_idx, _, getter_idxs @ ..] = bsm.arguments.as_slice() else {
+        return Err("too few static arguments for ObjectMethods.bootstrap")?;
+    };
+    let CPInfo::Class { name: rec_cls_name } = caller_cls.get_cp_info(*rec_cls_idx) else {
+        return Err("record class for ObjectMethods.bootstrap is not a class")?;
+    };
+    let comps = getter_idxs
+        .iter()
+        .map(|idx| match caller_cls.get_cp_info(*idx) {
+            CPInfo::MethodHandle {
+                reference_kind: REF_GET_FIELD,
+                class_name,
+                name,
+                descriptor,
+            } => Ok(Component {
+                class_name: class_name.clone(),
+                name: name.clone(),
+                descriptor: descriptor.clone(),
+            }),
+            _ => Err("getter of record component must be a REF_getField method handle"),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // operands are popped after the method completes, so that GC can see them while the method calls others
+    let (res, n_operands) = match meth_name {
+        "toString" => {
+            let s = record_to_string(t, meth_area, heap, rec_cls_name, &comps)?;
+            (heap.alloc_string(&s, meth_area)?, 1)
+        }
+        "hashCode" => {
+            let h = record_hash_code(t, meth_area, heap, &comps)?;
+            (Value::Int(h), 1)
+        }
+        "equals" => {
+            let eq = record_equals(t, meth_area, heap, &comps)?;
+            (Value::Int(eq as i32), 2)
+        }
+        _ => return Err(format!("unsupported method for ObjectMethods: {meth_name}"))?,
+    };
+    let frame = t.current_frame();
+    frame.pop_operands(n_operands);
+    frame.push_operand(res);
+    Ok(())
+}
+
+fn get_component(heap: &mut Heap, this: Value, comp: &Component) -> VMResult<Value> {
+    let Value::Reference(r) = this else {
+        return Err("operand is not a reference value")?;
+    };
+    let Some(RefValue::Object(obj)) = heap.get(r) else {
+        return Err("referent is not a object")?;
+    };
+    let Some(f) = obj.get_field(&comp.class_name, &comp.name) else {
+        return Err(format!("field {}.{} not found", comp.class_name, comp.name))?;
+    };
+    Ok(f.get())
+}
+
+// "Point[x=1, y=2]"
+fn record_to_string(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+    rec_cls_name: &str,
+    comps: &[Component],
+) -> VMResult<String> {
+    let mut comp_strs = Vec::with_capacity(comps.len());
+    for comp in comps {
+        // the receiver may be moved by GC while the method of a component runs
+        let this = *t.current_frame().peek_operand();
+        let v = get_component(heap, this, comp)?;
+        let s = match (comp.descriptor.as_str(), v) {
+            ("Z", v) => (int_value(v)? != 0).to_string(),
+            ("C", v) => char::from_u32(int_value(v)? as u32)
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                .to_string(),
+            ("B" | "S" | "I", v) => int_value(v)?.to_string(),
+            ("J", Value::Long(l)) => l.to_string(),
+            ("F", Value::Float(f)) => java_float_to_string(f as f64, f.to_string()),
+            ("D", Value::Double(d)) => java_float_to_string(d, d.to_string()),
+            (_, Value::Reference(0)) => "null".to_string(),
+            (_, this @ Value::Reference(_)) => {
+                let sig =
+                    MethodSignature::new_with_raw_descriptor("toString", "()Ljava/lang/String;");
+                match t.invoke_virtual(meth_area, heap, this, &sig, &[])? {
+                    Some(Value::Reference(0)) => "null".to_string(),
+                    Some(Value::Reference(s)) => heap.get_string(s)?,
+                    _ => return Err("toString() must return a string")?,
+                }
+            }
+            _ => return Err(format!("record component {} has invalid value", comp.name))?,
+        };
+        comp_strs.push(format!("{}={}", comp.name, s));
+    }
+
+    // simple name of the record class
+    let simple_name = rec_cls_name
+        .rsplit(['/', '$'])
+        .next()
+        .unwrap_or(rec_cls_name);
+    Ok(format!("{}[{}]", simple_name, comp_strs.join(", ")))
+}
+
+// result = 31 * result + (hash code of component), for each components
+fn record_hash_code(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+    comps: &[Component],
+) -> VMResult<i32> {
+    let mut res = 0i32;
+    for comp in comps {
+        let this = *t.current_frame().peek_operand();
+        let v = get_component(heap, this, comp)?;
+        let h = match (comp.descriptor.as_str(), v) {
+            // Boolean.hashCode()
+            ("Z", v) => {
+                if int_value(v)? != 0 {
+                    1231
+                } else {
+                    1237
+                }
+            }
+            ("B" | "C" | "S" | "I", v) => int_value(v)?,
+            ("J", Value::Long(l)) => long_hash_code(l),
+            ("F", Value::Float(f)) => float_to_int_bits(f),
+            ("D", Value::Double(d)) => long_hash_code(double_to_long_bits(d)),
+            (_, Value::Reference(0)) => 0,
+            (_, this @ Value::Reference(_)) => {
+                let sig = MethodSignature::new_with_raw_descriptor("hashCode", "()I");
+                let Some(Value::Int(h)) = t.invoke_virtual(meth_area, heap, this, &sig, &[])?
+                else {
+                    return Err("hashCode() must return an int")?;
+                };
+                h
+            }
+            _ => return Err(format!("record component {} has invalid value", comp.name))?,
+        };
+        res = res.wrapping_mul(31).wrapping_add(h);
+    }
+    Ok(res)
+}
+
+fn record_equals(
+    t: &mut Thread,
+    meth_area: &mut MethodArea,
+    heap: &mut Heap,
+    comps: &[Component],
+) -> VMResult<bool> {
+    let other = *t.current_frame().peek_operand_at(0);
+    let this = *t.current_frame().peek_operand_at(1);
+    if this == other {
+        return Ok(true);
+    }
+    // other must be an instance of exactly the same class as this
+    let Value::Reference(other_ref) = other else {
+        return Err("operand is not a reference value")?;
+    };
+    let other_cls = match heap.get(other_ref) {
+        Some(RefValue::Object(obj)) => obj.get_class(),
+        _ => return Ok(false),
+    };
+    let Value::Reference(this_ref) = this else {
+        return Err("operand is not a reference value")?;
+    };
+    let Some(RefValue::Object(this_obj)) = heap.get(this_ref) else {
+        return Err("referent is not a object")?;
+    };
+    if this_obj.get_class() != other_cls {
+        return Ok(false);
+    }
+
+    for comp in comps {
+        let other = *t.current_frame().peek_operand_at(0);
+        let this = *t.current_frame().peek_operand_at(1);
+        let v1 = get_component(heap, this, comp)?;
+        let v2 = get_component(heap, other, comp)?;
+        let eq = match (comp.descriptor.as_str(), v1, v2) {
+            ("Z" | "B" | "C" | "S" | "I", v1, v2) => int_value(v1)? == int_value(v2)?,
+            ("J", Value::Long(l1), Value::Long(l2)) => l1 == l2,
+            // equivalent to Float.compare(f1, f2) == 0
+            ("F", Value::Float(f1), Value::Float(f2)) => {
+                float_to_int_bits(f1) == float_to_int_bits(f2)
+            }
+            // equivalent to Double.compare(d1, d2) == 0
+            ("D", Value::Double(d1), Value::Double(d2)) => {
+                double_to_long_bits(d1) == double_to_long_bits(d2)
+            }
+            // Objects.equals(v1, v2)
+            (_, v1 @ Value::Reference(r1), v2 @ Value::Reference(r2)) => {
+                if r1 == r2 {
+                    true
+                } else if r1 == 0 {
+                    false
+                } else {
+                    let sig =
+                        MethodSignature::new_with_raw_descriptor("equals", "(Ljava/lang/Object;)Z");
+                    let Some(Value::Int(eq)) =
+                        t.invoke_virtual(meth_area, heap, v1, &sig, &[v2])?
+                    else {
+                        return Err("equals() must return a boolean")?;
+                    };
+                    eq != 0
+                }
+            }
+            _ => return Err(format!("record component {} has invalid value", comp.name))?,
+        };
+        if !eq {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn int_value(v: Value) -> VMResult<i32> {
+    Ok(v.as_int().ok_or("value is not an int-like value")?)
+}
+
+// Long.hashCode()
+fn long_hash_code(l: i64) -> i32 {
+    (l ^ ((l as u64) >> 32) as i64) as i32
+}
+
+// Float.floatToIntBits(): all NaNs are collapsed into the canonical NaN
+fn float_to_int_bits(f: f32) -> i32 {
+    if f.is_nan() {
+        0x7fc00000
+    } else {
+        f.to_bits() as i32
+    }
+}
+
+// Double.doubleToLongBits(): all NaNs are collapsed into the canonical NaN
+fn double_to_long_bits(d: f64) -> i64 {
+    if d.is_nan() {
+        0x7ff8000000000000
+    } else {
+        d.to_bits() as i64
+    }
+}
+
+// format a floating-point number like Float.toString()/Double.toString().
+// `repr` is the shortest representation of the number formatted by Rust.
+fn java_float_to_string(v: f64, repr: String) -> String {
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    if v.is_infinite() {
+        return if v > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+
+    let abs = v.abs();
+    if abs == 0.0 || (1e-3..1e7).contains(&abs) {
+        // decimal notation, with at least one digit after the decimal point
+        if repr.contains('.') {
+            repr
+        } else {
+            format!("{repr}.0")
+        }
+    } else {
+        // computerized scientific notation (e.g. 1.0E10)
+        let sci = format!("{:e}", repr.parse::<f64>().unwrap_or(v));
+        let (mantissa, exp) = sci.split_once('e').unwrap_or((&sci, "0"));
+        if mantissa.contains('.') {
+            format!("{mantissa}E{exp}")
+        } else {
+            format!("{mantissa}.0E{exp}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_java_float_to_string() {
+        let tests = [
+            (1.0, "1.0"),
+            (-0.5, "-0.5"),
+            (0.0, "0.0"),
+            (123.456, "123.456"),
+            (1e7, "1.0E7"),
+            (1.5e-5, "1.5E-5"),
+            (f64::NAN, "NaN"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ];
+        for (input, exp) in tests {
+            assert_eq!(java_float_to_string(input, input.to_string()), exp);
+        }
+    }
+
+    #[test]
+    fn test_long_hash_code() {
+        assert_eq!(long_hash_code(0), 0);
+        assert_eq!(long_hash_code(1), 1);
+        assert_eq!(long_hash_code(-1), 0);
+        assert_eq!(long_hash_code(1 << 32), 1);
+    }
+}
@@ -0,0 +1,387 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    ffi::OsStr,
+    fmt,
+    io::{self, Write},
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+    rc::Rc,
+    time::Instant,
+};
+
+use super::{
+    diagnostics::{self, Category, DiagnosticSink, Diagnostics, Level},
+    heap::Heap,
+    method_area::MethodArea,
+    thread::Thread,
+    VM,
+};
+
+pub const DEFAULT_MAX_FRAME_DEPTH: usize = 1024;
+pub const DEFAULT_MAX_HEAP_SIZE: usize = 256 * 1024 * 1024;
+
+/// Which classes are verified when they are loaded (like `-Xverify`).
+///
+/// Verification checks static constraints on the code of methods (e.g. branch targets and indices of local variables)
+/// and overriding of final methods, and throws `VerifyError` if the class violates them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verification {
+    /// No classes are verified.
+    None,
+    /// Classes loaded from the classpath are verified, while classes from the boot classpath are trusted.
+    #[default]
+    Remote,
+    /// All classes are verified.
+    All,
+}
+
+/// Garbage collector of the heap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Collector {
+    /// Non-moving mark & sweep collector of the whole heap.
+    #[default]
+    MarkSweep,
+    /// Objects are allocated in the nursery of `nursery_size` bytes by bumping a pointer,
+    /// and survivors are copied to the old generation when the nursery is full.
+    /// The old generation is compacted when it has grown, or on `System.gc()` and [`VM::gc`].
+    ///
+    /// Objects larger than the nursery are allocated in the old generation directly.
+    Generational { nursery_size: usize },
+}
+
+// configuration shared by the runtime data areas
+pub struct VMConfig {
+    pub boot_classpath: Vec<PathBuf>,
+    pub classpath: Vec<PathBuf>,
+    pub library_path: Vec<PathBuf>,
+    pub max_frame_depth: usize,
+    pub max_heap_size: usize,
+    pub collector: Collector,
+    pub heap_dump_path: Option<PathBuf>,
+    pub verification: Verification,
+    // system properties, including the default ones
+    pub properties: HashMap<String, String>,
+    pub diagnostics: Diagnostics,
+    // origin of System.nanoTime, which is monotonic unlike the wall clock
+    pub start_time: Instant,
+    stdout: RefCell<Box<dyn Write>>,
+    stderr: RefCell<Box<dyn Write>>,
+}
+
+impl VMConfig {
+    // write a line to the sink. failures of writing are ignored, like PrintStream does
+    pub fn println(&self, args: fmt::Arguments) {
+        let _ = writeln!(self.stdout.borrow_mut(), "{args}");
+    }
+
+    // flushed at once like the stderr of the process, so that nothing is lost on abort
+    pub fn eprintln(&self, args: fmt::Arguments) {
+        let mut stderr = self.stderr.borrow_mut();
+        let _ = writeln!(stderr, "{args}");
+        let _ = stderr.flush();
+    }
+
+    // report the diagnostic message to the sink, or to the stdout if no sink is set.
+    // the message is formatted only if the level and the category are enabled
+    pub fn report(&self, level: Level, category: Category, args: fmt::Arguments) {
+        if !self.diagnostics.enabled(level, category) {
+            return;
+        }
+        let msg = args.to_string();
+        match &self.diagnostics.sink {
+            Some(sink) => sink.report(level, category, &msg),
+            None => self.println(format_args!(
+                "{}",
+                diagnostics::format_message(level, category, &msg)
+            )),
+        }
+    }
+}
+
+impl Default for VMConfig {
+    fn default() -> Self {
+        VMConfig {
+            boot_classpath: Vec::new(),
+            classpath: Vec::new(),
+            library_path: Vec::new(),
+            max_frame_depth: DEFAULT_MAX_FRAME_DEPTH,
+            max_heap_size: DEFAULT_MAX_HEAP_SIZE,
+            collector: Collector::default(),
+            heap_dump_path: None,
+            verification: Verification::default(),
+            properties: HashMap::new(),
+            diagnostics: Diagnostics::default(),
+            start_time: Instant::now(),
+            stdout: RefCell::new(Box::new(io::stdout())),
+            stderr: RefCell::new(Box::new(io::stderr())),
+        }
+    }
+}
+
+/// Builder for [`VM`].
+#[derive(Default)]
+pub struct VMBuilder {
+    config: VMConfig,
+}
+
+impl VMBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Paths (directories or jar files) to search application classes for.
+    pub fn classpath<I, P>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.config.classpath = entries.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Appends a path to the classpath.
+    pub fn classpath_entry<P: Into<PathBuf>>(mut self, entry: P) -> Self {
+        self.config.classpath.push(entry.into());
+        self
+    }
+
+    /// Paths to search classes of the core library (e.g. `java.lang.Object`) for.
+    ///
+    /// They are searched before the classpath, and classes loaded from them are trusted by [`Verification::Remote`].
+    pub fn boot_classpath<I, P>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.config.boot_classpath = entries.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Directories to search native libraries for (like `java.library.path`).
+    pub fn library_path<I, P>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.config.library_path = entries.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Maximum number of frames in the stack. `StackOverflowError` is thrown when a method call exceeds it.
+    ///
+    /// Defaults to 1024.
+    pub fn max_frame_depth(mut self, depth: usize) -> Self {
+        self.config.max_frame_depth = depth;
+        self
+    }
+
+    /// Maximum size of the heap in bytes (like `-Xmx`). When an allocation would exceed it, garbage is collected,
+    /// and then `OutOfMemoryError` is thrown if there is still no room.
+    ///
+    /// Defaults to 256 MiB.
+    pub fn max_heap_size(mut self, bytes: usize) -> Self {
+        self.config.max_heap_size = bytes;
+        self
+    }
+
+    /// Garbage collector of the heap. Defaults to [`Collector::MarkSweep`].
+    pub fn collector(mut self, collector: Collector) -> Self {
+        self.config.collector = collector;
+        self
+    }
+
+    /// Writes the heap dump in the HPROF format to the path when `OutOfMemoryError` is thrown for the first time
+    /// (like `-XX:+HeapDumpOnOutOfMemoryError`). See also [`VM::dump_heap`].
+    pub fn heap_dump_on_out_of_memory<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.config.heap_dump_path = Some(path.into());
+        self
+    }
+
+    pub fn verification(mut self, verification: Verification) -> Self {
+        self.config.verification = verification;
+        self
+    }
+
+    /// Sets the system property, which can be read by `System.getProperty`.
+    ///
+    /// Properties set here override the default ones (e.g. `java.class.path`).
+    pub fn system_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.properties.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sink for the standard output of the VM, which diagnostic messages are written to unless [`Self::diagnostic_sink`] is set.
+    /// Defaults to the stdout of the process.
+    pub fn stdout<W: Write + 'static>(mut self, sink: W) -> Self {
+        self.config.stdout = RefCell::new(Box::new(sink));
+        self
+    }
+
+    /// Sink for the standard error of the VM (e.g. `ExceptionDescribe` of JNI). Defaults to the stderr of the process.
+    pub fn stderr<W: Write + 'static>(mut self, sink: W) -> Self {
+        self.config.stderr = RefCell::new(Box::new(sink));
+        self
+    }
+
+    /// Enables diagnostic messages of the category (like `-verbose:class`). Diagnostics are silent by default.
+    pub fn verbose(mut self, category: Category) -> Self {
+        if !self.config.diagnostics.categories.contains(&category) {
+            self.config.diagnostics.categories.push(category);
+        }
+        self
+    }
+
+    /// Most verbose level of diagnostic messages to be reported. Defaults to [`Level::Info`].
+    pub fn diagnostic_level(mut self, level: Level) -> Self {
+        self.config.diagnostics.level = level;
+        self
+    }
+
+    /// Receiver of diagnostic messages of the enabled categories, instead of the stdout sink.
+    pub fn diagnostic_sink<S: DiagnosticSink + 'static>(mut self, sink: S) -> Self {
+        self.config.diagnostics.sink = Some(Box::new(sink));
+        self
+    }
+
+    pub fn build(self) -> VM {
+        let mut config = self.config;
+        let mut properties = default_properties(&config);
+        properties.extend(config.properties);
+        config.properties = properties;
+        config.start_time = Instant::now();
+
+        let config = Rc::new(config);
+        VM {
+            thread: Thread::with_max_depth(config.max_frame_depth),
+            meth_area: MethodArea::new(config.clone()),
+            heap: Heap::with_options(config.max_heap_size, config.collector),
+            config,
+        }
+    }
+}
+
+fn default_properties(config: &VMConfig) -> HashMap<String, String> {
+    let join = |paths: &[PathBuf]| {
+        let paths = paths
+            .iter()
+            .map(|p| p.to_string_lossy())
+            .collect::<Vec<_>>();
+        paths.join(":")
+    };
+    let os_name = match env::consts::OS {
+        "linux" => "Linux",
+        "macos" => "Mac OS X",
+        os => os,
+    };
+    let os_arch = match env::consts::ARCH {
+        "x86_64" => "amd64",
+        arch => arch,
+    };
+    let cwd = env::current_dir().unwrap_or_default();
+
+    [
+        ("java.version", "17".to_string()),
+        ("java.specification.version", "17".to_string()),
+        ("java.vm.name", "kafa".to_string()),
+        ("java.vendor", "kafa".to_string()),
+        ("java.class.path", join(&config.classpath)),
+        ("java.library.path", join(&config.library_path)),
+        ("os.name", os_name.to_string()),
+        ("os.arch", os_arch.to_string()),
+        ("file.separator", "/".to_string()),
+        ("path.separator", ":".to_string()),
+        ("line.separator", "\n".to_string()),
+        ("user.dir", cwd.to_string_lossy().into_owned()),
+        (
+            "java.io.tmpdir",
+            env::temp_dir().to_string_lossy().into_owned(),
+        ),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect()
+}
+
+/// Splits the list of paths separated by `;` (e.g. `"classes;lib/app.jar"`), skipping empty entries.
+// the logic is borrowed from std::env::split_paths
+pub fn split_paths<P>(paths: &P) -> Vec<PathBuf>
+where
+    P: AsRef<OsStr> + ?Sized,
+{
+    fn bytes_to_path(b: &[u8]) -> PathBuf {
+        PathBuf::from(<OsStr as OsStrExt>::from_bytes(b))
+    }
+    fn is_separator(b: &u8) -> bool {
+        *b == b';'
+    }
+
+    paths
+        .as_ref()
+        .as_bytes()
+        .split(is_separator as fn(&u8) -> bool)
+        .filter(|b| !b.is_empty())
+        .map(bytes_to_path as fn(&[u8]) -> PathBuf)
+        .collect::<Vec<_>>()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_paths() {
+        assert_eq!(
+            split_paths("classes;lib/a.jar;;/tmp"),
+            [
+                PathBuf::from("classes"),
+                PathBuf::from("lib/a.jar"),
+                PathBuf::from("/tmp")
+            ]
+        );
+        assert!(split_paths("").is_empty());
+    }
+
+    #[test]
+    fn test_system_properties() {
+        let vm = VMBuilder::new()
+            .classpath(["classes", "lib/a.jar"])
+            .system_property("java.version", "17.0.1")
+            .system_property("app.name", "kafa")
+            .build();
+        assert_eq!(
+            vm.system_property("java.class.path"),
+            Some("classes:lib/a.jar")
+        );
+        assert_eq!(vm.system_property("java.version"), Some("17.0.1"));
+        assert_eq!(vm.system_property("app.name"), Some("kafa"));
+        assert_eq!(vm.system_property("no.such.key"), None);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let r = reported.clone();
+        let vm = VMBuilder::new()
+            .verbose(Category::ClassLoading)
+            .diagnostic_sink(move |level, category, msg: &str| {
+                r.borrow_mut().push(format!("{level} {category} {msg}"))
+            })
+            .build();
+
+        let cfg = &vm.config;
+        cfg.report(
+            Level::Info,
+            Category::ClassLoading,
+            format_args!("loaded A"),
+        );
+        cfg.report(
+            Level::Debug,
+            Category::ClassLoading,
+            format_args!("too verbose"),
+        );
+        cfg.report(Level::Warning, Category::Init, format_args!("not enabled"));
+        assert_eq!(*reported.borrow(), ["info class loaded A"]);
+    }
+}
@@ -1,69 +1,235 @@
-use std::rc::Rc;
+use std::{fs::File, rc::Rc};
+
+use crate::class_file::MethodAccessFlags;
 
 use super::{
-    class::{Class, MethodSignature},
-    error::VMResult,
+    class::{Class, Method, MethodCodeSpec, MethodSignature},
+    diagnostics::{Category, Level},
+    error::{JavaException, VMResult},
+    exception,
     frame::Frame,
-    heap::Heap,
+    gc,
+    heap::{Forwarding, Heap, RefValue},
+    hprof,
     instruction::exec_instr,
-    method_area::MethodArea,
+    method_area::{self, MethodArea},
+    native,
+    options::DEFAULT_MAX_FRAME_DEPTH,
+    value::Value,
 };
 
+const OUT_OF_MEMORY_ERROR_CLASS: &str = "java/lang/OutOfMemoryError";
+const CLEANABLE_CLASS: &str = "jdk/internal/ref/PhantomCleanable";
+// number of frames allowed beyond the max depth by `with_reserved_frames`
+const RESERVED_FRAME_DEPTH: usize = 64;
+
 pub struct Thread {
     frames: Vec<Frame>,
+    max_depth: usize,
+    // whether the heap has been dumped on OutOfMemoryError
+    heap_dumped: bool,
+    // whether pending references and finalizers are being processed
+    processing_pending: bool,
 }
 
 impl Thread {
     pub fn new() -> Thread {
-        Thread { frames: Vec::new() }
+        Self::with_max_depth(DEFAULT_MAX_FRAME_DEPTH)
     }
 
-    pub fn push_frame(&mut self, frame: Frame) {
+    pub fn with_max_depth(max_depth: usize) -> Thread {
+        Thread {
+            frames: Vec::new(),
+            max_depth,
+            heap_dumped: false,
+            processing_pending: false,
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: Frame) -> VMResult<()> {
+        if self.frames.len() >= self.max_depth {
+            Err(JavaException::stack_overflow_error(format!(
+                "stack depth exceeds {}",
+                self.max_depth
+            )))?;
+        }
         self.frames.push(frame);
+        Ok(())
+    }
+
+    // run `f` allowing some frames beyond the max depth, e.g. to create StackOverflowError when the stack is exhausted
+    pub fn with_reserved_frames<T>(&mut self, f: impl FnOnce(&mut Thread) -> T) -> T {
+        let max_depth = self.max_depth;
+        self.max_depth = max_depth.saturating_add(RESERVED_FRAME_DEPTH);
+        let res = f(self);
+        self.max_depth = max_depth;
+        res
     }
 
     pub fn pop_frame(&mut self) {
         let _ = self.frames.pop().expect("thread frame stack underflow");
     }
 
+    // values held by all the frames
+    pub fn values(&self) -> impl Iterator<Item = Value> + '_ {
+        self.frames.iter().flat_map(|f| f.values())
+    }
+
+    // frames of the thread (innermost first)
+    pub fn frames(&self) -> impl Iterator<Item = &Frame> {
+        self.frames.iter().rev()
+    }
+
+    pub fn forward_references(&mut self, fwd: &Forwarding) {
+        self.frames
+            .iter_mut()
+            .for_each(|f| f.forward_references(fwd));
+    }
+
     pub fn current_frame(&mut self) -> &mut Frame {
         self.frames
             .last_mut()
             .expect("no frame belongs to the thread")
     }
 
-    pub fn exec_bootstrap_method(
+    // invoke the method with the arguments (including the receiver, if it is an instance method),
+    // and execute it until it returns. returns the return value of the method, or None if it returns void.
+    pub(in crate::vm) fn invoke_method(
         &mut self,
         meth_area: &mut MethodArea,
         heap: &mut Heap,
-        class_name: &str,
-        sig: &MethodSignature,
-    ) -> VMResult<()> {
-        let caller = self.current_frame();
+        cls: Rc<Class>,
+        meth: Rc<Method>,
+        args: &[Value],
+    ) -> VMResult<Option<Value>> {
+        let orig_depth = self.frames.len();
 
-        // lookup method to be called
-        let cls = meth_area.resolve_class(class_name)?;
-        let Some(meth) = cls.lookup_static_method(sig) else {
-            return Err(format!("static method {class_name}.{sig} not found"))?;
+        // pass arguments via a temporary caller frame
+        let mut caller = Frame::new_empty();
+        for arg in args {
+            caller.push_operand(*arg);
+        }
+        self.push_frame(caller)?;
+
+        // execute until returned to the temporary caller frame
+        let res = self
+            .invoke_from_current_frame(meth_area, heap, cls, meth.clone())
+            .and_then(|_| self.exec_until(meth_area, heap, orig_depth + 1));
+        if let Err(e) = res {
+            self.frames.truncate(orig_depth);
+            return Err(e);
+        }
+
+        let ret = if meth.returns_void() {
+            None
+        } else {
+            Some(self.current_frame().pop_operand())
         };
+        self.pop_frame();
+        Ok(ret)
+    }
 
-        // create frame for callee method, and pass arguments from caller's stack
+    // invoke the method with the arguments on the operand stack of the current frame (preceded by the receiver, if it is an instance method).
+    // a frame is created for Java methods, while native methods are executed on the spot and their return values are pushed to the current frame.
+    pub fn invoke_from_current_frame(
+        &mut self,
+        meth_area: &mut MethodArea,
+        heap: &mut Heap,
+        cls: Rc<Class>,
+        meth: Rc<Method>,
+    ) -> VMResult<()> {
+        let is_static = meth.access_flags.contains(MethodAccessFlags::STATIC);
         let num_args = meth.num_args();
-        let mut callee = Frame::new(cls, meth);
-        Frame::transfer_args(caller, &mut callee, num_args);
 
-        // switch to the callee frame
-        self.push_frame(callee);
+        if let MethodCodeSpec::Native = meth.code_spec {
+            // arguments are kept on the operand stack during the call, so that GC can see them
+            let n = if is_static { num_args } else { num_args + 1 };
+            let args = self.current_frame().peek_operands(n);
+            let ret = native::invoke(self, meth_area, heap, &cls, &meth, &args)?;
+            self.current_frame().pop_operands(n);
+            if let Some(ret) = ret {
+                self.current_frame().push_operand(ret);
+            }
+            return Ok(());
+        }
 
-        // execute instructions until end of program
-        loop {
-            if self.frames.len() == 1 {
-                // returned to bootstrap frame -> program finished!
-                break;
+        // create new frame for the method, transfer the receiver(`this`) and args to the frame, then push onto frame stack
+        let caller_frame = self.current_frame();
+        let mut callee_frame = Frame::new(cls, meth)?;
+        if is_static {
+            Frame::transfer_args(caller_frame, &mut callee_frame, num_args);
+        } else {
+            Frame::transfer_receiver_and_args(caller_frame, &mut callee_frame, num_args);
+        }
+        self.push_frame(callee_frame)
+    }
+
+    // invoke the instance method selected by the runtime class of the receiver `this`, like invokevirtual does,
+    // and execute it until it returns. the method is resolved in the runtime class.
+    pub(in crate::vm) fn invoke_virtual(
+        &mut self,
+        meth_area: &mut MethodArea,
+        heap: &mut Heap,
+        this: Value,
+        sig: &MethodSignature,
+        args: &[Value],
+    ) -> VMResult<Option<Value>> {
+        let Value::Reference(r) = this else {
+            return Err("receiver is not a reference value")?;
+        };
+        let rt_cls_name = match heap.get(r) {
+            Some(RefValue::Object(obj)) => obj.get_class().name.clone(),
+            // array classes declare no methods, so the ones of java.lang.Object are resolved
+            Some(RefValue::Array(arr)) => arr.descriptor().to_string(),
+            _ => return Err("receiver is not a object or an array")?,
+        };
+
+        let resolved = meth_area.resolve_class_method(&rt_cls_name, sig)?;
+        self.invoke_resolved_instance_method(meth_area, heap, this, resolved, args)
+    }
+
+    // invoke the interface method on the receiver `this`, like invokeinterface does,
+    // and execute it until it returns.
+    pub(in crate::vm) fn invoke_interface(
+        &mut self,
+        meth_area: &mut MethodArea,
+        heap: &mut Heap,
+        this: Value,
+        iface_name: &str,
+        sig: &MethodSignature,
+        args: &[Value],
+    ) -> VMResult<Option<Value>> {
+        let resolved = meth_area.resolve_interface_method(iface_name, sig)?;
+        if let Value::Reference(r) = this {
+            if let Some(rv) = heap.get(r) {
+                meth_area.check_receiver_implements(rv, iface_name)?;
             }
-            exec_instr(self, meth_area, heap)?;
         }
-        Ok(())
+        self.invoke_resolved_instance_method(meth_area, heap, this, resolved, args)
+    }
+
+    fn invoke_resolved_instance_method(
+        &mut self,
+        meth_area: &mut MethodArea,
+        heap: &mut Heap,
+        this: Value,
+        resolved: (Rc<Class>, Rc<Method>),
+        args: &[Value],
+    ) -> VMResult<Option<Value>> {
+        method_area::check_method_staticness(&resolved, false)?;
+
+        let Value::Reference(r) = this else {
+            return Err("receiver is not a reference value")?;
+        };
+        let Some(rv) = heap.get(r) else {
+            return Err("referent not found on heap")?;
+        };
+        let (cls, meth) = meth_area.select_virtual_method(rv, resolved)?;
+
+        let mut all_args = Vec::with_capacity(args.len() + 1);
+        all_args.push(this);
+        all_args.extend_from_slice(args);
+        self.invoke_method(meth_area, heap, cls, meth, &all_args)
     }
 
     pub(in crate::vm) fn exec_class_initialization(
@@ -77,17 +243,195 @@ impl Thread {
             return Ok(());
         };
 
+        // <clinit> of some classes of the core library are replaced with natives
+        if clinit.access_flags.contains(MethodAccessFlags::NATIVE) {
+            return self
+                .invoke_method(meth_area, heap, cls, clinit, &[])
+                .map(|_| ());
+        }
+
         let orig_depth = self.frames.len();
 
-        let frame = Frame::new(cls, clinit);
-        self.push_frame(frame);
+        let frame = Frame::new(cls, clinit)?;
+        self.push_frame(frame)?;
 
+        self.exec_until(meth_area, heap, orig_depth)
+    }
+
+    // enqueue the references whose referents have been cleared by GC, and finalize unreachable objects,
+    // like the reference handler and finalizer threads of HotSpot do. exceptions thrown by them are ignored.
+    pub(in crate::vm) fn process_pending(&mut self, meth_area: &mut MethodArea, heap: &mut Heap) {
+        // pending ones that arise while processing are processed by the outer loop
+        if self.processing_pending {
+            return;
+        }
+        self.processing_pending = true;
         loop {
-            if self.frames.len() == orig_depth {
+            let (r, name) = if let Some(r) = heap.pop_pending_reference() {
+                // cleanables are cleaned at once, since there is no thread of Cleaner which polls its queue
+                let is_cleanable = matches!(
+                    heap.get(r),
+                    Some(RefValue::Object(obj)) if meth_area.is_subclass_of(&obj.get_class().name, CLEANABLE_CLASS)
+                );
+                (
+                    r,
+                    if is_cleanable {
+                        "clean"
+                    } else {
+                        "enqueueFromPending"
+                    },
+                )
+            } else if let Some(r) = heap.pop_pending_finalization() {
+                (r, "finalize")
+            } else {
                 break;
+            };
+            let sig = MethodSignature::new_with_raw_descriptor(name, "()V");
+            if let Err(err) = self.invoke_virtual(meth_area, heap, Value::Reference(r), &sig, &[]) {
+                meth_area.config().report(
+                    Level::Warning,
+                    Category::Gc,
+                    format_args!("{name}() failed: {err}"),
+                );
+            }
+        }
+        self.processing_pending = false;
+    }
+}
+
+impl Thread {
+    // execute instructions until the frame stack shrinks to `depth`.
+    // exceptions thrown in Java world are caught by the handlers of frames above `depth`, if any.
+    // if an error escapes, the frames above `depth` are discarded.
+    fn exec_until(
+        &mut self,
+        meth_area: &mut MethodArea,
+        heap: &mut Heap,
+        depth: usize,
+    ) -> VMResult<()> {
+        while self.frames.len() > depth {
+            // safepoint: no references are held outside of the frames between instructions
+            if heap.gc_requested() {
+                gc::collect(self, meth_area, heap, false);
+            }
+            if heap.has_pending() {
+                self.process_pending(meth_area, heap);
+            }
+            let Err(err) = exec_instr(self, meth_area, heap) else {
+                continue;
+            };
+            let res = match err.downcast::<JavaException>() {
+                Ok(exc) => self.handle_exception(meth_area, heap, *exc, depth),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = res {
+                self.frames.truncate(depth);
+                return Err(err);
             }
-            exec_instr(self, meth_area, heap)?;
         }
         Ok(())
     }
+
+    // search the handler of the exception from the current frame down to the frame at `depth`, and transfer control to it.
+    // returns the exception as an error if no handler is found.
+    fn handle_exception(
+        &mut self,
+        meth_area: &mut MethodArea,
+        heap: &mut Heap,
+        mut exc: JavaException,
+        depth: usize,
+    ) -> VMResult<()> {
+        if exc.stack_trace.is_empty() {
+            exc.stack_trace = self.stack_trace();
+        }
+        if let Some(at) = exc.stack_trace.first() {
+            meth_area.config().report(
+                Level::Debug,
+                Category::Interpreter,
+                format_args!("exception {exc} thrown at {at}"),
+            );
+        }
+        if exc.class_name == OUT_OF_MEMORY_ERROR_CLASS {
+            self.dump_heap_on_out_of_memory(meth_area, heap);
+        }
+        // handlers need the exception object. if it can't be created, the exception just propagates
+        let materialized =
+            heap.without_limit(|heap| exception::materialize(&mut exc, self, meth_area, heap));
+        let Ok(exc_ref) = materialized else {
+            return Err(exc)?;
+        };
+
+        while self.frames.len() > depth {
+            if let Some(handler_pc) = self.find_exception_handler(meth_area, &exc.class_name)? {
+                let frame = self.current_frame();
+                frame.clear_operands();
+                frame.push_operand(Value::Reference(exc_ref));
+                frame.jump_pc(handler_pc);
+                return Ok(());
+            }
+            // no handler in the current method -> abort it
+            self.pop_frame();
+        }
+        Err(exc)?
+    }
+
+    fn find_exception_handler(
+        &self,
+        meth_area: &mut MethodArea,
+        exc_cls_name: &str,
+    ) -> VMResult<Option<u32>> {
+        let Some(frame) = self.frames.last() else {
+            return Ok(None);
+        };
+        for handler in frame.active_exception_handlers() {
+            match &handler.catch_type {
+                // handler for any exceptions (used to implement `finally`)
+                None => return Ok(Some(handler.handler_pc as u32)),
+                Some(catch_type) => {
+                    meth_area.resolve_class(catch_type)?;
+                    if meth_area.is_subclass_of(exc_cls_name, catch_type) {
+                        return Ok(Some(handler.handler_pc as u32));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // dump the heap for the first OutOfMemoryError, if the path is configured
+    fn dump_heap_on_out_of_memory(&mut self, meth_area: &MethodArea, heap: &Heap) {
+        let Some(path) = &meth_area.config().heap_dump_path else {
+            return;
+        };
+        if self.heap_dumped {
+            return;
+        }
+        self.heap_dumped = true;
+
+        let res = File::create(path)
+            .map_err(Into::into)
+            .and_then(|f| hprof::dump(f, self, meth_area, heap));
+        match res {
+            Ok(()) => meth_area.config().report(
+                Level::Info,
+                Category::Gc,
+                format_args!("heap dumped to {} on OutOfMemoryError", path.display()),
+            ),
+            Err(e) => meth_area.config().report(
+                Level::Warning,
+                Category::Gc,
+                format_args!("failed to dump heap to {}: {e}", path.display()),
+            ),
+        }
+    }
+
+    // methods being executed by the thread (innermost first)
+    pub fn stack_trace(&self) -> Vec<String> {
+        self.frames
+            .iter()
+            .rev()
+            .filter(|f| f.has_code())
+            .map(|f| format!("{} (pc: {})", f.executing_method_info(), f.get_pc()))
+            .collect()
+    }
 }
@@ -1,7 +1,5 @@
 use std::cell::Cell;
 
-use crate::class_file::CPInfo;
-
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Value {
     Byte(i8),
@@ -21,6 +19,17 @@ pub enum ValueCategory {
 }
 
 impl Value {
+    // values of boolean/byte/char/short may be represented as either their own type or int
+    pub fn as_int(&self) -> Option<i32> {
+        match *self {
+            Value::Int(i) => Some(i),
+            Value::Byte(b) => Some(b as i32),
+            Value::Short(s) => Some(s as i32),
+            Value::Char(c) => Some(c as i32),
+            _ => None,
+        }
+    }
+
     pub fn category(&self) -> ValueCategory {
         match &self {
             Value::Long(_) | Value::Double(_) => ValueCategory::Two,
@@ -37,25 +46,6 @@ impl MutValue {
         MutValue(Cell::new(val))
     }
 
-    pub fn from_cp_info(cp_info: &CPInfo) -> Self {
-        use CPInfo::*;
-        let val = match cp_info {
-            Integer(i) => Value::Int(*i),
-            Float(f) => Value::Float(*f),
-            Long(l) => Value::Long(*l),
-            Double(d) => Value::Double(*d),
-            String { .. } => {
-                eprintln!("string constant is not supported");
-                Value::Reference(0) // null
-            }
-            _ => {
-                eprintln!("not a constant value");
-                Value::Reference(0) // null
-            }
-        };
-        MutValue::from_val(val)
-    }
-
     pub fn default_of_type(desc: &str) -> Self {
         assert!(!desc.is_empty());
         let Some(fst_char) = desc.chars().nth(0) else {
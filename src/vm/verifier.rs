@@ -0,0 +1,254 @@
+// verification of classes when they are loaded.
+// only static constraints on the code (cf. JVM spec 4.9.1) and overriding of final methods are checked;
+// type checking of operands (cf. JVM spec 4.10) is not performed.
+
+use std::rc::Rc;
+
+use crate::class_file::MethodAccessFlags;
+
+use super::{
+    class::{Class, Method, MethodCodeSpec},
+    error::{JavaException, VMResult},
+};
+
+// check the class against its superclasses (nearest first)
+pub fn verify_class(cls: &Class, superclasses: &[Rc<Class>]) -> VMResult<()> {
+    for meth in cls.methods() {
+        if let MethodCodeSpec::Java {
+            max_locals,
+            code,
+            exception_table,
+            ..
+        } = &meth.code_spec
+        {
+            let err = |msg: String| verify_error(cls, meth, &msg);
+            let boundaries = check_code(code, *max_locals).map_err(err)?;
+
+            let is_boundary = |pc: u16| boundaries.get(pc as usize).copied().unwrap_or(false);
+            for entry in exception_table {
+                let end_ok = entry.end_pc as usize == code.len() || is_boundary(entry.end_pc);
+                if !is_boundary(entry.start_pc) || !end_ok || entry.start_pc >= entry.end_pc {
+                    Err(err("Illegal exception table range".to_string()))?;
+                }
+                if !is_boundary(entry.handler_pc) {
+                    Err(err("Illegal exception table handler".to_string()))?;
+                }
+            }
+
+            let mut arg_slots: usize = meth
+                .signature
+                .descriptor
+                .param_types()
+                .iter()
+                .map(|t| if matches!(*t, "J" | "D") { 2 } else { 1 })
+                .sum();
+            if !meth.access_flags.is_static() {
+                arg_slots += 1;
+            }
+            if arg_slots > *max_locals as usize {
+                Err(err("Arguments can't fit into locals".to_string()))?;
+            }
+        }
+
+        // final methods can't be overridden by instance methods
+        if meth.access_flags.is_static() || meth.signature.name == "<init>" {
+            continue;
+        }
+        let overridden = superclasses.iter().find(|sc| {
+            sc.lookup_instance_method(&meth.signature)
+                .is_some_and(|m| m.access_flags.contains(MethodAccessFlags::FINAL))
+        });
+        if let Some(sc) = overridden {
+            Err(JavaException::verify_error(format!(
+                "class {} overrides final method {}.{}",
+                cls.name.replace('/', "."),
+                sc.name.replace('/', "."),
+                meth.signature
+            )))?;
+        }
+    }
+    Ok(())
+}
+
+fn verify_error(cls: &Class, meth: &Method, msg: &str) -> JavaException {
+    JavaException::verify_error(format!(
+        "(class: {}, method: {} signature: {}) {msg}",
+        cls.name, meth.signature.name, meth.signature.descriptor
+    ))
+}
+
+// check that instructions are valid, branch targets are at the start of instructions,
+// and local variables are within the range. returns whether each offset is the start of an instruction.
+fn check_code(code: &[u8], max_locals: u16) -> Result<Vec<bool>, String> {
+    if code.is_empty() || code.len() > u16::MAX as usize {
+        return Err("Invalid code length".to_string());
+    }
+
+    let mut boundaries = vec![false; code.len()];
+    let mut targets = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        boundaries[pc] = true;
+        let instr = decode(code, pc)?;
+        for local in instr.locals {
+            if local >= max_locals as usize {
+                return Err(format!("Illegal local variable number at pc {pc}"));
+            }
+        }
+        targets.extend(instr.branch_offsets.into_iter().map(|off| (pc, off)));
+        pc += instr.len;
+    }
+    if pc != code.len() {
+        return Err("Code ends in the middle of an instruction".to_string());
+    }
+
+    for (pc, off) in targets {
+        let target = pc as i64 + off as i64;
+        if target < 0 || target >= code.len() as i64 || !boundaries[target as usize] {
+            return Err(format!("Illegal target of jump or branch at pc {pc}"));
+        }
+    }
+    Ok(boundaries)
+}
+
+struct DecodedInstr {
+    len: usize,
+    // indices of local variables the instruction accesses
+    locals: Vec<usize>,
+    branch_offsets: Vec<i32>,
+}
+
+fn decode(code: &[u8], pc: usize) -> Result<DecodedInstr, String> {
+    let u8_at = |i: usize| {
+        code.get(i)
+            .copied()
+            .ok_or("Code ends in the middle of an instruction")
+    };
+    let u16_at = |i: usize| Ok::<_, &str>(u16::from_be_bytes([u8_at(i)?, u8_at(i + 1)?]));
+    let i32_at = |i: usize| {
+        Ok::<_, &str>(i32::from_be_bytes([
+            u8_at(i)?,
+            u8_at(i + 1)?,
+            u8_at(i + 2)?,
+            u8_at(i + 3)?,
+        ]))
+    };
+    let instr = |len, locals, branch_offsets| DecodedInstr {
+        len,
+        locals,
+        branch_offsets,
+    };
+    // long and double occupy 2 local variables
+    let wide_local = |op: u8| matches!(op, 0x16 | 0x18 | 0x37 | 0x39);
+    let locals_of = |op: u8, idx: usize| match wide_local(op) {
+        true => vec![idx, idx + 1],
+        false => vec![idx],
+    };
+
+    let op = code[pc];
+    let decoded = match op {
+        0x00..=0x0f | 0x2e..=0x35 | 0x4f..=0x83 | 0x85..=0x98 | 0xac..=0xb1 => {
+            instr(1, vec![], vec![])
+        }
+        0x10 | 0x12 | 0xbc => instr(2, vec![], vec![]),
+        0x11 | 0x13 | 0x14 | 0xb2..=0xb8 | 0xbb | 0xbd | 0xc0 | 0xc1 => instr(3, vec![], vec![]),
+        0xbe | 0xbf | 0xc2 | 0xc3 => instr(1, vec![], vec![]),
+        0xb9 | 0xba => instr(5, vec![], vec![]),
+        0xc5 => instr(4, vec![], vec![]),
+        // <x>load, <x>store, ret
+        0x15..=0x19 | 0x36..=0x3a | 0xa9 => {
+            instr(2, locals_of(op, u8_at(pc + 1)? as usize), vec![])
+        }
+        // <x>load_<n>
+        0x1a..=0x2d => {
+            let (base_op, n) = (0x15 + (op - 0x1a) / 4, ((op - 0x1a) % 4) as usize);
+            instr(1, locals_of(base_op, n), vec![])
+        }
+        // <x>store_<n>
+        0x3b..=0x4e => {
+            let (base_op, n) = (0x36 + (op - 0x3b) / 4, ((op - 0x3b) % 4) as usize);
+            instr(1, locals_of(base_op, n), vec![])
+        }
+        0x84 => instr(3, vec![u8_at(pc + 1)? as usize], vec![]),
+        // if<cond>, goto, jsr, ifnull, ifnonnull
+        0x99..=0xa8 | 0xc6 | 0xc7 => instr(3, vec![], vec![u16_at(pc + 1)? as i16 as i32]),
+        // goto_w, jsr_w
+        0xc8 | 0xc9 => instr(5, vec![], vec![i32_at(pc + 1)?]),
+        // tableswitch
+        0xaa => {
+            let base = pc + 1 + (3 - pc % 4);
+            let (default, low, high) = (i32_at(base)?, i32_at(base + 4)?, i32_at(base + 8)?);
+            if low > high {
+                return Err(format!("Illegal tableswitch at pc {pc}"));
+            }
+            let n = (high as i64 - low as i64 + 1) as usize;
+            let mut offsets = vec![default];
+            for i in 0..n {
+                offsets.push(i32_at(base + 12 + 4 * i)?);
+            }
+            instr(base + 12 + 4 * n - pc, vec![], offsets)
+        }
+        // lookupswitch
+        0xab => {
+            let base = pc + 1 + (3 - pc % 4);
+            let (default, n_pairs) = (i32_at(base)?, i32_at(base + 4)?);
+            if n_pairs < 0 {
+                return Err(format!("Illegal lookupswitch at pc {pc}"));
+            }
+            let mut offsets = vec![default];
+            for i in 0..n_pairs as usize {
+                offsets.push(i32_at(base + 8 + 8 * i + 4)?);
+            }
+            instr(base + 8 + 8 * n_pairs as usize - pc, vec![], offsets)
+        }
+        // wide
+        0xc4 => {
+            let (wop, idx) = (u8_at(pc + 1)?, u16_at(pc + 2)? as usize);
+            match wop {
+                0x15..=0x19 | 0x36..=0x3a | 0xa9 => instr(4, locals_of(wop, idx), vec![]),
+                0x84 => instr(6, vec![idx], vec![]),
+                _ => return Err(format!("Illegal instruction modified by wide at pc {pc}")),
+            }
+        }
+        _ => return Err(format!("Illegal instruction found at pc {pc}")),
+    };
+    if pc + decoded.len > code.len() {
+        return Err("Code ends in the middle of an instruction".to_string());
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_code() {
+        // iload_0; ifeq +5; iconst_1; ireturn; iconst_0; ireturn
+        let code = [0x1a, 0x99, 0x00, 0x05, 0x04, 0xac, 0x03, 0xac];
+        let boundaries = check_code(&code, 1).unwrap();
+        assert_eq!(
+            boundaries,
+            [true, true, false, false, true, true, true, true]
+        );
+
+        // local variable out of range
+        assert!(check_code(&code, 0).is_err());
+        // branch into the middle of an instruction
+        let code = [0x1a, 0x99, 0x00, 0x02, 0x04, 0xac, 0x03, 0xac];
+        assert!(check_code(&code, 1).is_err());
+        // lload_1 uses 2 locals
+        assert!(check_code(&[0x1f, 0xad], 2).is_err());
+        // truncated instruction
+        assert!(check_code(&[0x11, 0x00], 0).is_err());
+        // tableswitch at pc 1: padding up to pc 4, default +11, low 0, high 0, offset +11
+        let code = [
+            0x03, 0xaa, 0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 11, 0xb1,
+        ];
+        assert!(check_code(&code[..20], 0).is_err());
+        let mut code = code.to_vec();
+        code[7] = 19;
+        code[19] = 19;
+        assert!(check_code(&code, 0).is_ok());
+    }
+}